@@ -0,0 +1,248 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Fixed-capacity byte container optionally encoded as UTF-8.
+
+use core::{str, fmt};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use alloc::borrow::Cow;
+
+use crate::{MaybeUtf8, MaybeUtf8Buf, MaybeUtf8Slice};
+
+/// Byte container optionally encoded as UTF-8, stored in an array of `N` bytes.
+/// The storage never allocates, and any operation exceeding the capacity fails.
+///
+/// This is to `MaybeUtf8Buf` what `heapless::String` is to `String`,
+/// and is available for `no_std` targets when the `std` feature is disabled.
+#[derive(Clone, Copy)]
+pub struct MaybeUtf8Array<const N: usize> { buf: [u8; N], len: usize, utf8: bool }
+
+/// An error returned when an operation would exceed the capacity of `MaybeUtf8Array`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CapacityError;
+
+impl<const N: usize> MaybeUtf8Array<N> {
+    /// Creates a new empty `MaybeUtf8Array` value (which is, naturally, encoded in UTF-8).
+    pub const fn new() -> MaybeUtf8Array<N> {
+        MaybeUtf8Array { buf: [0; N], len: 0, utf8: true }
+    }
+
+    /// Creates a `MaybeUtf8Array` value by copying a string slice.
+    /// It returns an error if the string doesn't fit in the capacity.
+    pub fn from_str(s: &str) -> Result<MaybeUtf8Array<N>, CapacityError> {
+        let mut ret = MaybeUtf8Array::new();
+        ret.try_push_str(s)?;
+        Ok(ret)
+    }
+
+    /// Creates a `MaybeUtf8Array` value by copying a `u8` slice.
+    /// It returns an error if the bytes don't fit in the capacity.
+    pub fn from_bytes(v: &[u8]) -> Result<MaybeUtf8Array<N>, CapacityError> {
+        let mut ret = MaybeUtf8Array::new();
+        ret.try_push_bytes(v)?;
+        Ok(ret)
+    }
+
+    /// Returns a slice of underlying bytes. It might or might not be encoded in UTF-8.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Returns a string slice encoded in UTF-8 if possible.
+    /// It returns `None` if the underlying bytes are not encoded in UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        self.to_slice().as_str()
+    }
+
+    /// Returns a `Cow` string which represents the current `MaybeUtf8Array`.
+    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn as_cow_lossy(&self) -> Cow<'_, str> {
+        self.to_slice().as_cow_lossy()
+    }
+
+    /// Returns a `MaybeUtf8Slice` borrowed from this `MaybeUtf8Array`.
+    pub fn to_slice(&self) -> MaybeUtf8Slice<'_> {
+        if self.utf8 {
+            MaybeUtf8Slice::from_str(unsafe { str::from_utf8_unchecked(self.as_bytes()) })
+        } else {
+            MaybeUtf8Slice::from_bytes(self.as_bytes())
+        }
+    }
+
+    /// Returns a new `MaybeUtf8Buf` from the current `MaybeUtf8Array`.
+    pub fn to_buf(&self) -> MaybeUtf8Buf {
+        self.to_slice().to_owned()
+    }
+
+    /// Appends a string slice, retaining the UTF-8 encoding.
+    ///
+    /// Panics if the string doesn't fit in the remaining capacity.
+    pub fn push_str(&mut self, s: &str) {
+        self.try_push_str(s).expect("capacity exceeded");
+    }
+
+    /// Appends a string slice, retaining the UTF-8 encoding.
+    /// It returns an error and leaves the value intact
+    /// if the string doesn't fit in the remaining capacity.
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), CapacityError> {
+        self.try_extend(s.as_bytes())
+    }
+
+    /// Appends a character, retaining the UTF-8 encoding.
+    ///
+    /// Panics if the character doesn't fit in the remaining capacity.
+    pub fn push(&mut self, c: char) {
+        self.try_push(c).expect("capacity exceeded");
+    }
+
+    /// Appends a character, retaining the UTF-8 encoding.
+    /// It returns an error and leaves the value intact
+    /// if the character doesn't fit in the remaining capacity.
+    pub fn try_push(&mut self, c: char) -> Result<(), CapacityError> {
+        self.try_push_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Appends a `u8` slice. The value is no longer explicitly encoded in UTF-8.
+    ///
+    /// Panics if the bytes don't fit in the remaining capacity.
+    pub fn push_bytes(&mut self, v: &[u8]) {
+        self.try_push_bytes(v).expect("capacity exceeded");
+    }
+
+    /// Appends a `u8` slice. The value is no longer explicitly encoded in UTF-8.
+    /// It returns an error and leaves the value intact
+    /// if the bytes don't fit in the remaining capacity.
+    pub fn try_push_bytes(&mut self, v: &[u8]) -> Result<(), CapacityError> {
+        self.try_extend(v)?;
+        self.utf8 = false;
+        Ok(())
+    }
+
+    /// Appends a byte. The value is no longer explicitly encoded in UTF-8.
+    ///
+    /// Panics if there is no remaining capacity.
+    pub fn push_byte(&mut self, b: u8) {
+        self.try_push_byte(b).expect("capacity exceeded");
+    }
+
+    /// Appends a byte. The value is no longer explicitly encoded in UTF-8.
+    /// It returns an error and leaves the value intact if there is no remaining capacity.
+    pub fn try_push_byte(&mut self, b: u8) -> Result<(), CapacityError> {
+        self.try_push_bytes(&[b])
+    }
+
+    /// Returns a byte length of the `MaybeUtf8Array` value.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the `MaybeUtf8Array` value has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum byte length of the `MaybeUtf8Array` value, which is `N`.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    fn try_extend(&mut self, v: &[u8]) -> Result<(), CapacityError> {
+        if v.len() > N - self.len {
+            return Err(CapacityError);
+        }
+        self.buf[self.len..self.len + v.len()].copy_from_slice(v);
+        self.len += v.len();
+        Ok(())
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<MaybeUtf8Array<M>> for MaybeUtf8Array<N> {
+    fn eq(&self, other: &MaybeUtf8Array<M>) -> bool { self.as_bytes() == other.as_bytes() }
+}
+
+impl<const N: usize, const M: usize> PartialOrd<MaybeUtf8Array<M>> for MaybeUtf8Array<N> {
+    fn partial_cmp(&self, other: &MaybeUtf8Array<M>) -> Option<Ordering> {
+        self.as_bytes().partial_cmp(other.as_bytes())
+    }
+}
+
+macro_rules! define_array_partial_eq_and_cmp {
+    ($($ty:ty:$meth:ident;)*) => ($(
+        impl<'a, const N: usize> PartialEq<$ty> for MaybeUtf8Array<N> {
+            fn eq(&self, other: &$ty) -> bool { self.as_bytes().eq(other.$meth()) }
+        }
+        impl<'a, const N: usize> PartialOrd<$ty> for MaybeUtf8Array<N> {
+            fn partial_cmp(&self, other: &$ty) -> Option<Ordering> {
+                self.as_bytes().partial_cmp(other.$meth())
+            }
+        }
+        impl<'a, const N: usize> PartialEq<MaybeUtf8Array<N>> for $ty {
+            fn eq(&self, other: &MaybeUtf8Array<N>) -> bool { self.$meth().eq(other.as_bytes()) }
+        }
+        impl<'a, const N: usize> PartialOrd<MaybeUtf8Array<N>> for $ty {
+            fn partial_cmp(&self, other: &MaybeUtf8Array<N>) -> Option<Ordering> {
+                self.$meth().partial_cmp(other.as_bytes())
+            }
+        }
+    )*)
+}
+
+define_array_partial_eq_and_cmp! {
+    MaybeUtf8Buf:as_bytes;
+    MaybeUtf8Slice<'a>:as_bytes;
+    MaybeUtf8:as_bytes;
+    &'a str:as_bytes;
+    &'a [u8]:deref;
+}
+
+impl<const N: usize> Eq for MaybeUtf8Array<N> {
+}
+
+impl<const N: usize> Ord for MaybeUtf8Array<N> {
+    fn cmp(&self, other: &MaybeUtf8Array<N>) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl<const N: usize> Hash for MaybeUtf8Array<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl<const N: usize> Default for MaybeUtf8Array<N> {
+    fn default() -> MaybeUtf8Array<N> { MaybeUtf8Array::new() }
+}
+
+impl<const N: usize> AsRef<[u8]> for MaybeUtf8Array<N> {
+    fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl<const N: usize> Deref for MaybeUtf8Array<N> {
+    type Target = MaybeUtf8;
+    fn deref(&self) -> &MaybeUtf8 { MaybeUtf8::from_bytes(self.as_bytes()) }
+}
+
+impl<const N: usize> fmt::Debug for MaybeUtf8Array<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_slice(), f)
+    }
+}
+
+impl<const N: usize> fmt::Display for MaybeUtf8Array<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_slice(), f)
+    }
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("insufficient capacity")
+    }
+}
+
+impl core::error::Error for CapacityError {
+}
@@ -0,0 +1,47 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Base64 encoding shared by `serde` and `serde_json` supports.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// the key of the map which represents bytes in human-readable forms
+pub(crate) const BASE64_KEY: &str = "base64";
+
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode_base64(v: &[u8]) -> String {
+    let mut ret = String::with_capacity(v.len().div_ceil(3) * 4);
+    for chunk in v.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                ret.push(BASE64_DIGITS[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                ret.push('=');
+            }
+        }
+    }
+    ret
+}
+
+// returns `None` if `s` is not a valid Base64 string. paddings are optional.
+pub(crate) fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    let s = s.strip_suffix(b"==").or_else(|| s.strip_suffix(b"=")).unwrap_or(s);
+    let mut ret = Vec::with_capacity(s.len() / 4 * 3 + 2);
+    for chunk in s.chunks(4) {
+        // a single digit can't make a whole byte
+        if chunk.len() == 1 { return None; }
+        let mut n = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            let digit = BASE64_DIGITS.iter().position(|&d| d == c)? as u32;
+            n |= digit << (18 - 6 * i);
+        }
+        ret.extend_from_slice(&u32::to_be_bytes(n)[1..chunk.len()]);
+    }
+    Some(ret)
+}
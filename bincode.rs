@@ -0,0 +1,104 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Bincode 2 support for `MaybeUtf8` values, independent from the serde support.
+//!
+//! Each value is encoded as a tag byte, which is 1 if explicitly encoded in UTF-8
+//! and 0 otherwise, followed by the underlying bytes prefixed with their length.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use bincode::{Encode, Decode, BorrowDecode};
+use bincode::enc::Encoder;
+use bincode::de::{Decoder, BorrowDecoder};
+use bincode::error::{EncodeError, DecodeError, AllowedEnumVariants};
+
+use crate::{MaybeUtf8Buf, MaybeUtf8Slice, MaybeUtf8Cow, Slice};
+
+const BYTES_TAG: u8 = 0;
+const UTF8_TAG: u8 = 1;
+
+// returns `true` if the value is explicitly encoded in UTF-8
+fn decode_tag(tag: u8) -> Result<bool, DecodeError> {
+    match tag {
+        BYTES_TAG => Ok(false),
+        UTF8_TAG => Ok(true),
+        _ => Err(DecodeError::UnexpectedVariant {
+            type_name: "MaybeUtf8",
+            allowed: &AllowedEnumVariants::Range { min: BYTES_TAG as u32, max: UTF8_TAG as u32 },
+            found: tag as u32,
+        }),
+    }
+}
+
+impl<'a> Encode for MaybeUtf8Slice<'a> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        let (tag, v) = match self.inner {
+            Slice::Utf8(s) => (UTF8_TAG, s.as_bytes()),
+            Slice::Bytes(v) => (BYTES_TAG, v),
+        };
+        tag.encode(encoder)?;
+        v.encode(encoder)
+    }
+}
+
+impl Encode for MaybeUtf8Buf {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.to_slice().encode(encoder)
+    }
+}
+
+impl<'a> Encode for MaybeUtf8Cow<'a> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.to_slice().encode(encoder)
+    }
+}
+
+impl<Context> Decode<Context> for MaybeUtf8Buf {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<MaybeUtf8Buf, DecodeError> {
+        let utf8 = decode_tag(u8::decode(decoder)?)?;
+        let v = Vec::<u8>::decode(decoder)?;
+        if !utf8 {
+            return Ok(MaybeUtf8Buf::from_bytes(v));
+        }
+        match String::from_utf8(v) {
+            Ok(s) => Ok(MaybeUtf8Buf::from_str(s)),
+            Err(e) => Err(DecodeError::Utf8 { inner: e.utf8_error() }),
+        }
+    }
+}
+
+impl<'de, Context> BorrowDecode<'de, Context> for MaybeUtf8Buf {
+    fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(decoder: &mut D)
+            -> Result<MaybeUtf8Buf, DecodeError> {
+        MaybeUtf8Buf::decode(decoder)
+    }
+}
+
+// borrows from the decoder without copying
+impl<'de: 'a, 'a, Context> BorrowDecode<'de, Context> for MaybeUtf8Slice<'a> {
+    fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(decoder: &mut D)
+            -> Result<MaybeUtf8Slice<'a>, DecodeError> {
+        let utf8 = decode_tag(u8::decode(decoder)?)?;
+        if utf8 {
+            <&str>::borrow_decode(decoder).map(MaybeUtf8Slice::from_str)
+        } else {
+            <&[u8]>::borrow_decode(decoder).map(MaybeUtf8Slice::from_bytes)
+        }
+    }
+}
+
+impl<Context> Decode<Context> for MaybeUtf8Cow<'static> {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D)
+            -> Result<MaybeUtf8Cow<'static>, DecodeError> {
+        MaybeUtf8Buf::decode(decoder).map(MaybeUtf8Cow::Owned)
+    }
+}
+
+impl<'de: 'a, 'a, Context> BorrowDecode<'de, Context> for MaybeUtf8Cow<'a> {
+    fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(decoder: &mut D)
+            -> Result<MaybeUtf8Cow<'a>, DecodeError> {
+        MaybeUtf8Slice::borrow_decode(decoder).map(MaybeUtf8Cow::Borrowed)
+    }
+}
@@ -0,0 +1,64 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Borsh support for `MaybeUtf8` values.
+//!
+//! Each value is encoded as a tag byte, which is 1 if explicitly encoded in UTF-8
+//! and 0 otherwise, followed by the underlying bytes prefixed with a 32-bit length.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use borsh::{BorshSerialize, BorshDeserialize};
+use borsh::io::{self, Read, Write};
+
+use crate::{MaybeUtf8Buf, MaybeUtf8Slice, MaybeUtf8Cow, Slice};
+
+const BYTES_TAG: u8 = 0;
+const UTF8_TAG: u8 = 1;
+
+impl<'a> BorshSerialize for MaybeUtf8Slice<'a> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (tag, v) = match self.inner {
+            Slice::Utf8(s) => (UTF8_TAG, s.as_bytes()),
+            Slice::Bytes(v) => (BYTES_TAG, v),
+        };
+        tag.serialize(writer)?;
+        v.serialize(writer)
+    }
+}
+
+impl BorshSerialize for MaybeUtf8Buf {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.to_slice().serialize(writer)
+    }
+}
+
+impl<'a> BorshSerialize for MaybeUtf8Cow<'a> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.to_slice().serialize(writer)
+    }
+}
+
+impl BorshDeserialize for MaybeUtf8Buf {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<MaybeUtf8Buf> {
+        let tag = u8::deserialize_reader(reader)?;
+        if tag != BYTES_TAG && tag != UTF8_TAG {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid MaybeUtf8 tag"));
+        }
+        let v = Vec::<u8>::deserialize_reader(reader)?;
+        if tag == BYTES_TAG {
+            return Ok(MaybeUtf8Buf::from_bytes(v));
+        }
+        match String::from_utf8(v) {
+            Ok(s) => Ok(MaybeUtf8Buf::from_str(s)),
+            Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+impl<'a> BorshDeserialize for MaybeUtf8Cow<'a> {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<MaybeUtf8Cow<'a>> {
+        MaybeUtf8Buf::deserialize_reader(reader).map(MaybeUtf8Cow::Owned)
+    }
+}
@@ -0,0 +1,92 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Builder for concatenating many `MaybeUtf8` values at once.
+
+use alloc::vec::Vec;
+
+use crate::{MaybeUtf8Buf, MaybeUtf8Slice, MaybeUtf8Cow, IntoMaybeUtf8, Slice, Tag};
+
+/// A builder which collects segments without copying
+/// and concatenates them into a single `MaybeUtf8Buf` with a single allocation.
+///
+/// The result is explicitly encoded in UTF-8 only when every segment is.
+#[derive(Clone, Default)]
+pub struct MaybeUtf8Builder<'a> { pieces: Vec<MaybeUtf8Cow<'a>>, len: usize }
+
+impl<'a> MaybeUtf8Builder<'a> {
+    /// Creates a new empty `MaybeUtf8Builder`.
+    pub fn new() -> MaybeUtf8Builder<'a> {
+        MaybeUtf8Builder { pieces: Vec::new(), len: 0 }
+    }
+
+    /// Appends a string slice segment.
+    pub fn push_str(&mut self, s: &'a str) {
+        self.push(MaybeUtf8Slice::from_str(s));
+    }
+
+    /// Appends a `u8` slice segment.
+    /// The result will be no longer explicitly encoded in UTF-8.
+    pub fn push_bytes(&mut self, v: &'a [u8]) {
+        self.push(MaybeUtf8Slice::from_bytes(v));
+    }
+
+    /// Appends a `MaybeUtf8Slice`, a `MaybeUtf8Buf` or anything convertible to `MaybeUtf8Cow`.
+    pub fn push<T: IntoMaybeUtf8<MaybeUtf8Cow<'a>>>(&mut self, piece: T) {
+        let piece = piece.into_maybe_utf8();
+        self.len += piece.len();
+        self.pieces.push(piece);
+    }
+
+    /// Returns the byte length of the concatenated value.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the concatenated value would be empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Concatenates all segments into a new `MaybeUtf8Buf`.
+    pub fn build(self) -> MaybeUtf8Buf {
+        let mut buf = MaybeUtf8Buf::with_capacity(self.len);
+        let mut tag = Tag::KnownUtf8;
+        for piece in &self.pieces {
+            let piece_tag = match *piece {
+                MaybeUtf8Cow::Borrowed(s) => match s.inner {
+                    Slice::Utf8(_) => Tag::KnownUtf8,
+                    Slice::Bytes(_) => Tag::Unknown,
+                },
+                MaybeUtf8Cow::Owned(ref b) => b.repr.tag(),
+            };
+            // segments valid by themselves are still valid when concatenated
+            tag = match (tag, piece_tag) {
+                (Tag::KnownUtf8, Tag::KnownUtf8) => Tag::KnownUtf8,
+                (Tag::KnownUtf8 | Tag::KnownValid, Tag::KnownUtf8 | Tag::KnownValid) =>
+                    Tag::KnownValid,
+                _ => Tag::Unknown,
+            };
+            buf.repr.extend_from_slice(piece.as_bytes());
+        }
+        buf.repr.set_tag(tag);
+        buf
+    }
+}
+
+impl<'a> Extend<MaybeUtf8Slice<'a>> for MaybeUtf8Builder<'a> {
+    fn extend<I: IntoIterator<Item=MaybeUtf8Slice<'a>>>(&mut self, iter: I) {
+        for s in iter { self.push(s); }
+    }
+}
+
+impl<'a> Extend<MaybeUtf8Buf> for MaybeUtf8Builder<'a> {
+    fn extend<I: IntoIterator<Item=MaybeUtf8Buf>>(&mut self, iter: I) {
+        for buf in iter { self.push(buf); }
+    }
+}
+
+impl<'a> From<MaybeUtf8Builder<'a>> for MaybeUtf8Buf {
+    fn from(builder: MaybeUtf8Builder<'a>) -> MaybeUtf8Buf { builder.build() }
+}
@@ -0,0 +1,78 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Lossy character iterators over `MaybeUtf8` values.
+
+use core::str;
+
+/// An iterator over characters decoded from the bytes,
+/// where each invalid UTF-8 sequence is decoded to U+FFFD as like `String::from_utf8_lossy`.
+/// This is returned by `MaybeUtf8Slice::chars_lossy`.
+#[derive(Clone)]
+pub struct CharsLossy<'a>(CharIndicesLossy<'a>);
+
+/// An iterator over characters decoded from the bytes and their byte ranges,
+/// where each invalid UTF-8 sequence is decoded to U+FFFD as like `String::from_utf8_lossy`.
+/// The range covers the whole invalid sequence in that case.
+/// This is returned by `MaybeUtf8Slice::char_indices_lossy`.
+#[derive(Clone)]
+pub struct CharIndicesLossy<'a> { bytes: &'a [u8], pos: usize }
+
+impl<'a> CharsLossy<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> CharsLossy<'a> {
+        CharsLossy(CharIndicesLossy::new(bytes))
+    }
+}
+
+impl<'a> CharIndicesLossy<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> CharIndicesLossy<'a> {
+        CharIndicesLossy { bytes, pos: 0 }
+    }
+}
+
+// decodes the first character from `v`, returning U+FFFD for an invalid sequence.
+// also returns the number of bytes consumed.
+fn decode_lossy(v: &[u8]) -> (char, usize) {
+    // no UTF-8 sequence is longer than 4 bytes
+    let chunk = &v[..v.len().min(4)];
+    let valid = match str::from_utf8(chunk) {
+        Ok(s) => s,
+        Err(e) if e.valid_up_to() > 0 =>
+            unsafe { str::from_utf8_unchecked(&chunk[..e.valid_up_to()]) },
+        // an incomplete sequence at the end is invalid as a whole
+        Err(e) => return ('\u{fffd}', e.error_len().unwrap_or(chunk.len())),
+    };
+    let c = valid.chars().next().unwrap();
+    (c, c.len_utf8())
+}
+
+impl<'a> Iterator for CharsLossy<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        self.0.next().map(|(_, _, c)| c)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a> Iterator for CharIndicesLossy<'a> {
+    /// The start and end byte offsets, and a decoded character.
+    type Item = (usize, usize, char);
+
+    fn next(&mut self) -> Option<(usize, usize, char)> {
+        if self.pos >= self.bytes.len() { return None; }
+        let start = self.pos;
+        let (c, width) = decode_lossy(&self.bytes[start..]);
+        self.pos += width;
+        Some((start, self.pos, c))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.bytes.len() - self.pos;
+        (remaining.div_ceil(4), Some(remaining))
+    }
+}
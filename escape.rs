@@ -0,0 +1,177 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Escaping iterators over `MaybeUtf8` values.
+
+use core::{ascii, char, fmt, str};
+use core::fmt::Write;
+
+/// An iterator over characters of the value escaped as like `str::escape_default`.
+/// This is returned by `MaybeUtf8Slice::escape_default`.
+///
+/// If the original value is not explicitly encoded in UTF-8,
+/// each byte is escaped as like `[u8]::escape_ascii` instead.
+#[derive(Clone)]
+pub struct EscapeDefault<'a> { bytes: &'a [u8], utf8: bool, pending: Pending }
+
+/// A policy for bytes which are not a part of valid UTF-8 sequences,
+/// used by `MaybeUtf8Slice::escape_json`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum JsonEscapePolicy {
+    /// Each invalid UTF-8 sequence is escaped as `\uFFFD`, as like `String::from_utf8_lossy`.
+    #[default]
+    Replace,
+    /// Each invalid byte is escaped as `\u00XX`, as if it were an ISO 8859-1 character.
+    Byte,
+}
+
+/// A quoted JSON string of the value, which is always valid JSON
+/// regardless of invalid UTF-8 sequences.
+/// This is returned by `MaybeUtf8Slice::escape_json`, and is meant to be displayed.
+#[derive(Clone)]
+pub struct EscapeJson<'a> { bytes: &'a [u8], policy: JsonEscapePolicy }
+
+/// An iterator over characters of the value escaped as like `char::escape_debug`,
+/// where each byte which is not a part of valid UTF-8 sequences is escaped as `\xNN`.
+/// This is returned by `MaybeUtf8Slice::escape_debug`.
+#[derive(Clone)]
+pub struct EscapeDebug<'a> { bytes: &'a [u8], pending: Pending }
+
+// the remaining characters of the current escape sequence
+#[derive(Clone)]
+enum Pending {
+    Empty,
+    Default(char::EscapeDefault),
+    Debug(char::EscapeDebug),
+    Byte(ascii::EscapeDefault),
+}
+
+impl Iterator for Pending {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match *self {
+            Pending::Empty => None,
+            Pending::Default(ref mut e) => e.next(),
+            Pending::Debug(ref mut e) => e.next(),
+            Pending::Byte(ref mut e) => e.next().map(char::from),
+        }
+    }
+}
+
+// decodes the first character from `v`, or returns `None` if `v` starts with an invalid byte.
+fn decode(v: &[u8]) -> Option<char> {
+    // no UTF-8 sequence is longer than 4 bytes
+    let chunk = &v[..v.len().min(4)];
+    let valid_up_to = match str::from_utf8(chunk) {
+        Ok(s) => s.len(),
+        Err(e) => e.valid_up_to(),
+    };
+    unsafe { str::from_utf8_unchecked(&chunk[..valid_up_to]) }.chars().next()
+}
+
+impl<'a> EscapeDefault<'a> {
+    pub(crate) fn new(bytes: &'a [u8], utf8: bool) -> EscapeDefault<'a> {
+        EscapeDefault { bytes, utf8, pending: Pending::Empty }
+    }
+}
+
+impl<'a> EscapeDebug<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> EscapeDebug<'a> {
+        EscapeDebug { bytes, pending: Pending::Empty }
+    }
+}
+
+impl<'a> EscapeJson<'a> {
+    pub(crate) fn new(bytes: &'a [u8], policy: JsonEscapePolicy) -> EscapeJson<'a> {
+        EscapeJson { bytes, policy }
+    }
+}
+
+impl<'a> Iterator for EscapeDefault<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        loop {
+            if let Some(c) = self.pending.next() { return Some(c); }
+            let (&b, rest) = self.bytes.split_first()?;
+            match if self.utf8 { decode(self.bytes) } else { None } {
+                Some(c) => {
+                    self.bytes = &self.bytes[c.len_utf8()..];
+                    self.pending = Pending::Default(c.escape_default());
+                }
+                None => {
+                    self.bytes = rest;
+                    self.pending = Pending::Byte(ascii::escape_default(b));
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for EscapeDebug<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        loop {
+            if let Some(c) = self.pending.next() { return Some(c); }
+            let (&b, rest) = self.bytes.split_first()?;
+            match decode(self.bytes) {
+                Some(c) => {
+                    self.bytes = &self.bytes[c.len_utf8()..];
+                    self.pending = Pending::Debug(c.escape_debug());
+                }
+                None => {
+                    // `ascii::escape_default` would keep printable ASCII bytes,
+                    // but they are always valid and never reach here
+                    self.bytes = rest;
+                    self.pending = Pending::Byte(ascii::escape_default(b));
+                }
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Display for EscapeDefault<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.clone().try_for_each(|c| f.write_char(c))
+    }
+}
+
+impl<'a> fmt::Display for EscapeDebug<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.clone().try_for_each(|c| f.write_char(c))
+    }
+}
+
+impl<'a> fmt::Display for EscapeJson<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_char('"')?;
+        for chunk in self.bytes.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                match c {
+                    '"' => f.write_str("\\\"")?,
+                    '\\' => f.write_str("\\\\")?,
+                    '\n' => f.write_str("\\n")?,
+                    '\r' => f.write_str("\\r")?,
+                    '\t' => f.write_str("\\t")?,
+                    '\u{8}' => f.write_str("\\b")?,
+                    '\u{c}' => f.write_str("\\f")?,
+                    '\0'..='\u{1f}' => write!(f, "\\u{:04X}", c as u32)?,
+                    c => f.write_char(c)?,
+                }
+            }
+            if chunk.invalid().is_empty() { continue; }
+            match self.policy {
+                JsonEscapePolicy::Replace => f.write_str("\\uFFFD")?,
+                JsonEscapePolicy::Byte => {
+                    for &b in chunk.invalid() {
+                        write!(f, "\\u00{:02X}", b)?;
+                    }
+                }
+            }
+        }
+        f.write_char('"')
+    }
+}
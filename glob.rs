@@ -0,0 +1,199 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Glob matching over `MaybeUtf8` values.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Options for `MaybeUtf8Slice::matches_glob_with`.
+///
+/// This is only available with the `glob` feature.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GlobOptions {
+    /// Whether characters are matched case-sensitively. Defaults to `true`.
+    pub case_sensitive: bool,
+    /// Whether `/` is only matched by a literal `/` (or `**`),
+    /// so that `*`, `?` and `[...]` never match across path components. Defaults to `true`.
+    pub require_literal_separator: bool,
+}
+
+impl GlobOptions {
+    /// Creates a new `GlobOptions` with the default options.
+    pub const fn new() -> GlobOptions {
+        GlobOptions { case_sensitive: true, require_literal_separator: true }
+    }
+}
+
+impl Default for GlobOptions {
+    fn default() -> GlobOptions { GlobOptions::new() }
+}
+
+// a character, or a byte which is not a part of valid UTF-8 sequences
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Unit { Char(char), Byte(u8) }
+
+enum Token {
+    Unit(Unit),
+    // `?`
+    AnyUnit,
+    // `*`
+    AnySeq,
+    // `**/`, which matches zero or more path components
+    AnyDirs,
+    // `**` at the end, which matches everything
+    AnyPath,
+    // `[...]` or `[!...]`
+    Class { negated: bool, ranges: Vec<(Unit, Unit)> },
+}
+
+const SEP: Unit = Unit::Char('/');
+
+fn units(v: &[u8]) -> Vec<Unit> {
+    let mut units = Vec::with_capacity(v.len());
+    for chunk in v.utf8_chunks() {
+        units.extend(chunk.valid().chars().map(Unit::Char));
+        units.extend(chunk.invalid().iter().map(|&b| Unit::Byte(b)));
+    }
+    units
+}
+
+fn parse(pattern: &[Unit]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < pattern.len() {
+        match pattern[i] {
+            Unit::Char('?') => tokens.push(Token::AnyUnit),
+            Unit::Char('*') => {
+                let begin = i;
+                while pattern.get(i + 1) == Some(&Unit::Char('*')) { i += 1; }
+                let component_start = begin == 0 || pattern[begin - 1] == SEP;
+                if i > begin && component_start && i + 1 == pattern.len() {
+                    tokens.push(Token::AnyPath);
+                } else if i > begin && component_start && pattern[i + 1] == SEP {
+                    tokens.push(Token::AnyDirs);
+                    i += 1;
+                } else {
+                    tokens.push(Token::AnySeq);
+                }
+            }
+            Unit::Char('[') => match parse_class(&pattern[i + 1..]) {
+                Some((token, len)) => { tokens.push(token); i += len; }
+                None => tokens.push(Token::Unit(pattern[i])),
+            },
+            u => tokens.push(Token::Unit(u)),
+        }
+        i += 1;
+    }
+    tokens
+}
+
+// parses a class after `[`, returning the token and the number of units consumed
+fn parse_class(pattern: &[Unit]) -> Option<(Token, usize)> {
+    let negated = matches!(pattern.first(), Some(&Unit::Char('!' | '^')));
+    let mut i = if negated { 1 } else { 0 };
+    let mut ranges = Vec::new();
+    // `]` right after the opening bracket is a literal
+    let mut first = true;
+    loop {
+        let u = *pattern.get(i)?;
+        if u == Unit::Char(']') && !first {
+            return Some((Token::Class { negated, ranges }, i + 1));
+        }
+        first = false;
+        match (pattern.get(i + 1), pattern.get(i + 2)) {
+            (Some(&Unit::Char('-')), Some(&end)) if end != Unit::Char(']') => {
+                ranges.push((u, end));
+                i += 3;
+            }
+            _ => {
+                ranges.push((u, u));
+                i += 1;
+            }
+        }
+    }
+}
+
+fn fold(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+struct Matcher<'a> {
+    tokens: &'a [Token],
+    name: &'a [Unit],
+    options: GlobOptions,
+    // `(token index, name index)` pairs known to fail
+    failed: Vec<bool>,
+}
+
+impl<'a> Matcher<'a> {
+    fn unit_eq(&self, a: Unit, b: Unit) -> bool {
+        match (a, b) {
+            (Unit::Char(a), Unit::Char(b)) if !self.options.case_sensitive => fold(a) == fold(b),
+            _ => a == b,
+        }
+    }
+
+    fn in_class(&self, ranges: &[(Unit, Unit)], u: Unit) -> bool {
+        // characters are ordered before bytes, so a range never mixes them up
+        let in_range = |u: Unit| ranges.iter().any(|&(lo, hi)| lo <= u && u <= hi);
+        match u {
+            Unit::Char(c) if !self.options.case_sensitive => {
+                in_range(u) || in_range(Unit::Char(fold(c))) ||
+                    c.to_uppercase().any(|c| in_range(Unit::Char(c)))
+            }
+            _ => in_range(u),
+        }
+    }
+
+    // returns `true` if the unit can be matched by a wildcard
+    fn is_wild(&self, u: Unit) -> bool {
+        !(self.options.require_literal_separator && u == SEP)
+    }
+
+    fn matches(&mut self, t: usize, n: usize) -> bool {
+        let state = t * (self.name.len() + 1) + n;
+        if self.failed[state] { return false; }
+        let ret = self.matches_uncached(t, n);
+        if !ret { self.failed[state] = true; }
+        ret
+    }
+
+    fn matches_uncached(&mut self, t: usize, n: usize) -> bool {
+        let name = self.name;
+        let Some(token) = self.tokens.get(t) else { return n == name.len() };
+        match *token {
+            Token::Unit(u) => n < name.len() && self.unit_eq(u, name[n]) && self.matches(t + 1, n + 1),
+            Token::AnyUnit => n < name.len() && self.is_wild(name[n]) && self.matches(t + 1, n + 1),
+            Token::Class { negated, ref ranges } => {
+                n < name.len() && self.is_wild(name[n]) &&
+                    self.in_class(ranges, name[n]) != negated && self.matches(t + 1, n + 1)
+            }
+            Token::AnySeq => {
+                for k in n..=name.len() {
+                    if self.matches(t + 1, k) { return true; }
+                    if k == name.len() || !self.is_wild(name[k]) { break; }
+                }
+                false
+            }
+            Token::AnyDirs => {
+                self.matches(t + 1, n) ||
+                    (n + 1..=name.len()).any(|k| name[k - 1] == SEP && self.matches(t + 1, k))
+            }
+            Token::AnyPath => true,
+        }
+    }
+}
+
+// returns `true` if `name` matches the glob `pattern`.
+pub(crate) fn matches_glob(pattern: &[u8], name: &[u8], options: GlobOptions) -> bool {
+    let tokens = parse(&units(pattern));
+    let name = units(name);
+    let failed = vec![false; (tokens.len() + 1) * (name.len() + 1)];
+    Matcher { tokens: &tokens, name: &name, options, failed }.matches(0, 0)
+}
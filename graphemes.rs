@@ -0,0 +1,64 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Grapheme cluster iterator over `MaybeUtf8Slice`.
+
+use core::str::Utf8Chunks;
+use unicode_segmentation::{Graphemes, UnicodeSegmentation};
+
+use crate::MaybeUtf8Slice;
+
+/// An iterator over extended grapheme clusters, where each invalid UTF-8 sequence
+/// is considered a single cluster (as it would be rendered as a single U+FFFD).
+/// This is returned by `MaybeUtf8Slice::graphemes_lossy`.
+///
+/// Each cluster is explicitly encoded in UTF-8 only when the original value is.
+/// This is only available with the `unicode-segmentation` feature.
+#[derive(Clone)]
+pub struct GraphemesLossy<'a> {
+    haystack: MaybeUtf8Slice<'a>,
+    chunks: Utf8Chunks<'a>,
+    // the clusters and the invalid sequence from the current chunk
+    graphemes: Graphemes<'a>,
+    invalid: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> GraphemesLossy<'a> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>) -> GraphemesLossy<'a> {
+        GraphemesLossy {
+            haystack,
+            chunks: haystack.as_bytes().utf8_chunks(),
+            graphemes: "".graphemes(true),
+            invalid: &[],
+            pos: 0,
+        }
+    }
+
+    fn piece(&mut self, len: usize) -> MaybeUtf8Slice<'a> {
+        let piece = self.haystack.get(self.pos..self.pos + len).unwrap();
+        self.pos += len;
+        piece
+    }
+}
+
+impl<'a> Iterator for GraphemesLossy<'a> {
+    type Item = MaybeUtf8Slice<'a>;
+
+    fn next(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        loop {
+            if let Some(g) = self.graphemes.next() {
+                return Some(self.piece(g.len()));
+            }
+            if !self.invalid.is_empty() {
+                let len = self.invalid.len();
+                self.invalid = &[];
+                return Some(self.piece(len));
+            }
+            let chunk = self.chunks.next()?;
+            self.graphemes = chunk.valid().graphemes(true);
+            self.invalid = chunk.invalid();
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Hex dumps of `MaybeUtf8` values.
+
+use core::fmt;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// A hex dump of the underlying bytes in the format of `hexdump -C`,
+/// where each line has an offset, 16 bytes in hexadecimal digits and their ASCII rendering.
+/// This is returned by `MaybeUtf8Slice::hexdump`, and is meant to be displayed.
+///
+/// Lines are separated by `\n` without a trailing newline,
+/// and an empty value results in an empty string.
+#[derive(Clone)]
+pub struct HexDump<'a> { bytes: &'a [u8] }
+
+impl<'a> HexDump<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> HexDump<'a> {
+        HexDump { bytes }
+    }
+}
+
+impl<'a> fmt::Display for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, line) in self.bytes.chunks(BYTES_PER_LINE).enumerate() {
+            if i > 0 { f.write_str("\n")?; }
+            write!(f, "{:08x} ", i * BYTES_PER_LINE)?;
+            for j in 0..BYTES_PER_LINE {
+                // an extra space separates two halves
+                if j % 8 == 0 { f.write_str(" ")?; }
+                match line.get(j) {
+                    Some(b) => write!(f, "{:02x} ", b)?,
+                    None => f.write_str("   ")?,
+                }
+            }
+            f.write_str(" |")?;
+            for &b in line {
+                let c = if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' };
+                write!(f, "{}", c)?;
+            }
+            f.write_str("|")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Debug for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,109 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Interner for `MaybeUtf8` values.
+
+use core::str;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use std::collections::HashMap;
+
+use crate::{MaybeUtf8Slice, Slice};
+
+/// A symbol for an interned value, which is cheap to copy and compare.
+/// It can be resolved back to the value only with the originating `MaybeUtf8Interner`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct MaybeUtf8Symbol(u32);
+
+/// Interner for `MaybeUtf8` values, which stores each distinct value only once.
+///
+/// A value explicitly encoded in UTF-8 and a value with the same bytes
+/// but not explicitly encoded in UTF-8 are considered distinct.
+///
+/// This is only available with the `std` feature.
+#[derive(Clone, Default)]
+pub struct MaybeUtf8Interner {
+    // shared with the maps below, so that each value is allocated only once
+    values: Vec<(Arc<[u8]>, bool)>,
+    utf8_symbols: HashMap<Arc<[u8]>, MaybeUtf8Symbol>,
+    bytes_symbols: HashMap<Arc<[u8]>, MaybeUtf8Symbol>,
+}
+
+impl MaybeUtf8Symbol {
+    /// Returns a zero-based index of the symbol, which is less than the number of interned values.
+    pub fn to_usize(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl MaybeUtf8Interner {
+    /// Creates a new empty `MaybeUtf8Interner`.
+    pub fn new() -> MaybeUtf8Interner {
+        MaybeUtf8Interner {
+            values: Vec::new(),
+            utf8_symbols: HashMap::new(),
+            bytes_symbols: HashMap::new(),
+        }
+    }
+
+    /// Returns a symbol for given value, interning it if not yet done.
+    ///
+    /// Panics if there are more than 2^32 distinct values.
+    pub fn intern<'a>(&mut self, value: MaybeUtf8Slice<'a>) -> MaybeUtf8Symbol {
+        if let Some(sym) = self.get(value) {
+            return sym;
+        }
+
+        assert!(self.values.len() <= u32::MAX as usize, "too many interned values");
+        let sym = MaybeUtf8Symbol(self.values.len() as u32);
+        let bytes: Arc<[u8]> = From::from(value.as_bytes());
+        let utf8 = is_explicitly_utf8(value);
+        if utf8 {
+            self.utf8_symbols.insert(bytes.clone(), sym);
+        } else {
+            self.bytes_symbols.insert(bytes.clone(), sym);
+        }
+        self.values.push((bytes, utf8));
+        sym
+    }
+
+    /// Returns a symbol for given value if it has been interned.
+    pub fn get<'a>(&self, value: MaybeUtf8Slice<'a>) -> Option<MaybeUtf8Symbol> {
+        let symbols = if is_explicitly_utf8(value) {
+            &self.utf8_symbols
+        } else {
+            &self.bytes_symbols
+        };
+        symbols.get(value.as_bytes()).cloned()
+    }
+
+    /// Returns an interned value for given symbol.
+    ///
+    /// Panics if the symbol has not been returned by this interner.
+    pub fn resolve<'a>(&'a self, sym: MaybeUtf8Symbol) -> MaybeUtf8Slice<'a> {
+        let (ref bytes, utf8) = self.values[sym.to_usize()];
+        if utf8 {
+            MaybeUtf8Slice::from_str(unsafe { str::from_utf8_unchecked(bytes) })
+        } else {
+            MaybeUtf8Slice::from_bytes(bytes)
+        }
+    }
+
+    /// Returns the number of interned values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if no value has been interned.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+fn is_explicitly_utf8<'a>(value: MaybeUtf8Slice<'a>) -> bool {
+    match value.inner {
+        Slice::Utf8(_) => true,
+        Slice::Bytes(_) => false,
+    }
+}
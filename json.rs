@@ -0,0 +1,55 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Conversions between `MaybeUtf8` values and `serde_json::Value`.
+
+use alloc::borrow::ToOwned;
+use serde_json::{Map, Value};
+
+use crate::{MaybeUtf8Buf, MaybeUtf8Slice, Slice};
+use crate::base64::{BASE64_KEY, encode_base64, decode_base64};
+
+/// A representation of values not explicitly encoded in UTF-8,
+/// used by `MaybeUtf8Slice::to_json_value`.
+///
+/// This is only available with the `serde_json` feature.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum JsonBytes {
+    /// An object with a single entry `base64`, whose value is a Base64 string of the bytes.
+    /// This is same to the human-readable form of the `serde` feature.
+    #[default]
+    Base64,
+    /// An array of numbers, one for each byte.
+    Array,
+}
+
+pub(crate) fn to_json_value(s: MaybeUtf8Slice, bytes: JsonBytes) -> Value {
+    let v = match s.inner {
+        Slice::Utf8(s) => return Value::String(s.to_owned()),
+        Slice::Bytes(v) => v,
+    };
+    match bytes {
+        JsonBytes::Base64 => {
+            let mut map = Map::new();
+            map.insert(BASE64_KEY.to_owned(), Value::String(encode_base64(v)));
+            Value::Object(map)
+        }
+        JsonBytes::Array => Value::Array(v.iter().map(|&b| Value::from(b)).collect()),
+    }
+}
+
+pub(crate) fn from_json_value(value: &Value) -> Option<MaybeUtf8Buf> {
+    match *value {
+        Value::String(ref s) => Some(MaybeUtf8Buf::from(&s[..])),
+        Value::Array(ref values) => {
+            let v = values.iter().map(|b| u8::try_from(b.as_u64()?).ok()).collect::<Option<_>>()?;
+            Some(MaybeUtf8Buf::from_bytes(v))
+        }
+        Value::Object(ref map) if map.len() == 1 => {
+            let v = decode_base64(map.get(BASE64_KEY)?.as_str()?)?;
+            Some(MaybeUtf8Buf::from_bytes(v))
+        }
+        _ => None,
+    }
+}
@@ -18,7 +18,7 @@ The newer ZIP standard supports explicitly UTF-8-encoded file names though.
 In this case, the ZIP library may want to return either a `String` or `Vec<u8>`
 depending on the UTF-8 flag.
 
-This crate supports two types,
+This crate supports two main types,
 `MaybeUtf8Buf` (analogous to `String`) and `MaybeUtf8Slice` (analogous to `&str`).
 Both types support various conversion methods.
 For example, if you know that the bytes are encoded in ISO 8859-2,
@@ -28,7 +28,6 @@ For example, if you know that the bytes are encoded in ISO 8859-2,
 # extern crate encoding;
 # extern crate maybe_utf8;
 # fn main() {
-use std::borrow::IntoCow;
 use encoding::{Encoding, DecoderTrap};
 use encoding::all::ISO_8859_2;
 use maybe_utf8::{MaybeUtf8Buf, MaybeUtf8Slice};
@@ -50,94 +49,487 @@ assert_eq!(namebuf.map_into_str(|v| ISO_8859_2.decode(&v, DecoderTrap::Replace).
 # }
 ```
 
+There is also an unsized `MaybeUtf8` type (analogous to `str`),
+which can be used behind references or smart pointers like `Box` and `Rc`
+but doesn't remember if the bytes have been explicitly encoded in UTF-8.
+`MaybeUtf8Cow` is a clone-on-write type which holds either of the main types.
+`MaybeUtf8Of` is a separate, more limited container generic over its byte storage,
+which provides the read-only API via `to_slice` without copying
+and can be appended to when the storage can grow (e.g. `Vec` with a custom allocator or `SmallVec`).
+For example, `MaybeUtf8Shared` uses a range of `Arc<[u8]>` for cheap cloning and slicing,
+`MaybeUtf8Bytes` (available with the `bytes` feature) uses `bytes::Bytes`,
+and `MaybeUtf8SmallBuf` (available with the `smallvec` feature) stores short values inline.
+`MaybeUtf8Array` is a fixed-capacity version of `MaybeUtf8Buf` which stores bytes
+without allocation.
+The crate only requires `core` and `alloc` when the default `std` feature is disabled,
+which leaves out I/O, conversions from and to OS strings and `MaybeUtf8Interner`.
+`MaybeUtf16Buf` and `MaybeUtf16Slice` are their counterparts for 16-bit code units
+optionally encoded as UTF-16, and can be losslessly converted to `MaybeUtf8Buf`.
+
 `IntoMaybeUtf8` trait can be used to uniformly accept either string or vector
 to construct `MaybeUtf8*` values.
+The standard `From` conversions from strings, byte vectors, their slices, boxes and `Cow`s,
+characters and byte arrays are also available, so that `.into()` and `Into<MaybeUtf8Buf>` bounds work as well.
 
 ```rust
 use maybe_utf8::IntoMaybeUtf8;
 assert_eq!("caf\u{e9}".into_maybe_utf8(), b"caf\xc3\xa9".into_maybe_utf8());
 ```
 
+Searching methods like `find` and `contains` accept string slices, `u8` slices, bytes
+and characters alike. They use the SIMD-accelerated `memchr` crate
+when the `memchr` feature is enabled.
+`graphemes_lossy` iterates over grapheme clusters with the `unicode-segmentation` feature,
+treating each invalid UTF-8 sequence as a single cluster.
+`display_width` measures the lossy rendering in terminal columns with the `unicode-width` feature.
+`nfc` and `nfd` normalize the parts valid in UTF-8 with the `unicode-normalization` feature.
+`to_ascii_lossy_translit` approximates the value in ASCII with the `deunicode` feature.
+`eq_ignore_case` and `cmp_ignore_case` apply Unicode simple case folding
+with the `unicode-case-mapping` feature.
+`collation_key` produces a locale-aware sort key with the `icu_collator` feature.
+`is_match`, `find_iter` and `captures` run `regex::bytes::Regex` against the underlying bytes
+with the `regex` feature.
+`matches_glob` matches against glob patterns like `src/**/*.rs` with the `glob` feature,
+where each invalid UTF-8 byte is matched by `?` or the same byte.
+`MaybeUtf8Buf` and others implement `Serialize` and `Deserialize` with the `serde` feature,
+as a string if explicitly encoded in UTF-8 and as bytes otherwise
+(or a map with Base64-encoded bytes in human-readable formats).
+They also implement `BorshSerialize` and `BorshDeserialize` with the `borsh` feature,
+and bincode 2's `Encode` and `Decode` with the `bincode` feature.
+`to_json_value` and `MaybeUtf8Buf::from_json_value` convert from and to `serde_json::Value`
+with the `serde_json` feature, where bytes are represented as configured by `JsonBytes`.
+
 */
 
-#![feature(core)]
+#![no_std]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+// `from_str` constructors predate `FromStr` and are not fallible.
+#![allow(clippy::should_implement_trait)]
+
+extern crate alloc;
+#[cfg(feature = "std")] extern crate std;
+
+use core::{str, char, fmt, iter, mem, slice};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ops::{Add, AddAssign, Deref, Index, RangeBounds, Bound};
+use core::ops::{Range, RangeFrom, RangeTo, RangeFull, RangeInclusive, RangeToInclusive};
+use core::sync::atomic::{self, AtomicU8};
+use alloc::borrow::{Borrow, Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::vec;
+#[cfg(feature = "std")] use std::io;
+#[cfg(feature = "std")] use std::ffi::{OsStr, OsString};
+#[cfg(all(feature = "std", unix))] use std::os::unix::ffi::{OsStrExt, OsStringExt};
+#[cfg(all(feature = "std", windows))] use std::os::windows::ffi::OsStrExt;
+#[cfg(all(feature = "std", unix))] use std::path::Path;
+#[cfg(feature = "bytes")] use bytes::Bytes;
+#[cfg(feature = "smallvec")] use smallvec::SmallVec;
+#[cfg(feature = "allocator_api")] use alloc::alloc::Allocator;
+#[cfg(feature = "icu_collator")] use icu_collator::CollatorBorrowed;
+#[cfg(feature = "regex")] use regex::bytes::Regex;
+#[cfg(feature = "serde_json")] use serde_json::Value;
+#[cfg(feature = "unicode-normalization")] use unicode_normalization::UnicodeNormalization;
+#[cfg(feature = "unicode-width")] use unicode_width::UnicodeWidthStr;
 
-use std::{str, char, fmt};
-use std::borrow::{IntoCow, Cow, ToOwned};
-use std::default::Default;
-use std::cmp::Ordering;
-use std::iter::{IntoIterator, FromIterator};
+use pattern::{trim_start_offset, trim_end_offset};
+
+pub use array::{MaybeUtf8Array, CapacityError};
+pub use builder::MaybeUtf8Builder;
+pub use chars::{CharsLossy, CharIndicesLossy};
+pub use escape::{EscapeDefault, EscapeDebug, EscapeJson, JsonEscapePolicy};
+#[cfg(feature = "glob")] pub use glob::GlobOptions;
+#[cfg(feature = "unicode-segmentation")] pub use graphemes::GraphemesLossy;
+pub use hexdump::HexDump;
+#[cfg(feature = "std")] pub use interner::{MaybeUtf8Interner, MaybeUtf8Symbol};
+pub use pattern::BytePattern;
+#[cfg(feature = "serde_json")] pub use json::JsonBytes;
+#[cfg(feature = "regex")] pub use regexp::{RegexMatches, RegexCaptures};
+pub use split::{Split, RSplit, SplitN, RSplitN, SplitTerminator, Lines,
+                 SplitWhitespace};
+pub use trie::{MaybeUtf8Trie, TrieIter};
+pub use utf16::{MaybeUtf16Buf, MaybeUtf16Slice};
+
+mod array;
+#[cfg(any(feature = "serde", feature = "serde_json"))] mod base64;
+#[cfg(feature = "bincode")] mod bincode;
+#[cfg(feature = "borsh")] mod borsh;
+mod builder;
+mod chars;
+mod escape;
+#[cfg(feature = "glob")] mod glob;
+#[cfg(feature = "unicode-segmentation")] mod graphemes;
+mod hexdump;
+#[cfg(feature = "std")] mod interner;
+#[cfg(feature = "serde_json")] mod json;
+mod pattern;
+#[cfg(feature = "regex")] mod regexp;
+#[cfg(feature = "serde")] pub mod serde;
+mod split;
+mod trie;
+mod utf16;
 
 /// Byte container optionally encoded as UTF-8. It might be either...
 ///
 /// - Definitely UTF-8-encoded string, or
 /// - Bytes. It may be encoded in UTF-8 or other encodings, or it may be simply invalid.
-#[derive(Clone)]
-pub struct MaybeUtf8Buf { inner: Buf }
+///
+/// Short enough values (up to 29 bytes on 64-bit targets) are stored inline and do not allocate,
+/// when they are copied from borrowed data (e.g. `From<&str>` or `clone`) or built by appending.
+/// `from_str` and `from_bytes` keep the given allocation instead, so that
+/// `into_str` and `into_bytes` can give it back without copying.
+///
+/// Hash maps and sets keyed by `MaybeUtf8Buf` can be queried with `&[u8]`,
+/// or with `&MaybeUtf8` which can be made from `&str` by `MaybeUtf8::from_str`.
+pub struct MaybeUtf8Buf { repr: Repr }
 
-// private so that we can tweak the internals when an unsized `MaybeUtf8` can be implemented
-#[derive(Clone)]
-enum Buf {
-    Utf8(String),
-    Bytes(Vec<u8>),
+// private so that we can tweak the internals without breaking the API.
+// the tag is kept in each variant so that `Repr` can be as small as `Vec` plus the tag,
+// which is padded to `Vec` plus `usize` (e.g. 32 bytes on 64-bit targets).
+enum Repr {
+    Heap(Vec<u8>, TagCell),
+    // the short value is kept inline to avoid the allocation
+    Inline(TagCell, u8, [u8; INLINE_CAP]),
+    // hinted values are rare, so the hint is boxed along with the value to keep `Repr` small
+    Hinted(Box<(Repr, EncodingHint)>),
+}
+
+// the maximum number of bytes stored inline, which uses the whole size of `Repr`
+// except for the tag, the length and the discriminant.
+const INLINE_CAP: usize = mem::size_of::<Vec<u8>>() + mem::size_of::<usize>() - 3;
+
+// `Repr::Inline` should never make `Repr` larger than `Repr::Heap` does.
+const _: () = assert!(mem::size_of::<Repr>() == mem::size_of::<Vec<u8>>() + mem::size_of::<usize>());
+
+// what is known about the encoding of `MaybeUtf8Buf`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    // explicitly encoded in UTF-8.
+    KnownUtf8,
+    // not explicitly encoded in UTF-8, but known to be valid in UTF-8.
+    KnownValid,
+    // not explicitly encoded in UTF-8, and known to be invalid in UTF-8
+    // no matter what bytes are appended.
+    KnownInvalid,
+    // not explicitly encoded in UTF-8, and known to be valid in UTF-8
+    // except for an incomplete sequence at the end, which may be completed later.
+    Incomplete,
+    // not explicitly encoded in UTF-8, and yet to be validated.
+    Unknown,
+}
+
+/// Either a character or a byte, depending on whether the originating value
+/// has been explicitly encoded in UTF-8.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CharOrByte {
+    /// A character from the value explicitly encoded in UTF-8.
+    Char(char),
+    /// A byte from the value not explicitly encoded in UTF-8.
+    Byte(u8),
 }
 
+/// A hint about the encoding of bytes which are not explicitly encoded in UTF-8.
+/// It is only advisory and not checked against the bytes in any way.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EncodingHint {
+    /// An encoding label, e.g. `"shift_jis"` as in the WHATWG Encoding Standard.
+    Label(Cow<'static, str>),
+    /// A numeric code page, e.g. `932` as in Windows.
+    CodePage(u16),
+}
+
+// `Tag` which can be updated via a shared reference, so that the validation is done only once.
+// the validation result doesn't depend on the thread, so the relaxed ordering suffices.
+struct TagCell(AtomicU8);
+
 /// Byte slice optionally encoded as UTF-8. A borrowed version of `MaybeUtf8Buf`.
-//
-// Rust: this cannot yet be an unsized item. that's why this is not named `MaybeUtf8`. (#16812)
+///
+/// Unlike `&MaybeUtf8`, this retains the knowledge about whether
+/// it has been explicitly encoded in UTF-8 or not.
+///
+/// It dereferences to the underlying bytes, so read-only `[u8]` methods are also available.
+#[derive(Clone, Copy)]
 pub struct MaybeUtf8Slice<'a> { inner: Slice<'a> }
 
+#[derive(Clone, Copy)]
 enum Slice<'a> {
     Utf8(&'a str),
     Bytes(&'a [u8]),
 }
 
+/// Mutable byte slice optionally encoded as UTF-8.
+///
+/// It allows in-place edits while retaining the UTF-8 encoding whenever possible.
+/// Any edit which may result in an invalid UTF-8 sequence makes it (and the owning
+/// `MaybeUtf8Buf`, if any) no longer explicitly encoded in UTF-8.
+pub struct MaybeUtf8SliceMut<'a> {
+    bytes: &'a mut [u8],
+    // `true` if `bytes` is explicitly encoded in UTF-8.
+    utf8: bool,
+    // the tag of the owning `MaybeUtf8Buf` if any, which should be reset on demotion.
+    owner: Option<&'a TagCell>,
+}
+
+/// Unsized byte sequence optionally encoded as UTF-8.
+/// This is to `MaybeUtf8Buf` what `str` is to `String`,
+/// and can be used behind references, `Box`, `Rc`, `Cow` and so on.
+///
+/// Fat pointers cannot carry any more information than the length,
+/// so `MaybeUtf8` does not remember whether it has been explicitly encoded in UTF-8.
+/// Instead it is considered to be a UTF-8-encoded string
+/// whenever the underlying bytes are valid in UTF-8.
+/// Use `MaybeUtf8Slice` if the distinction is significant.
+///
+/// It dereferences to the underlying bytes, so read-only `[u8]` methods are also available.
+/// This also applies to `MaybeUtf8Buf` and other types dereferencing to `MaybeUtf8`.
+#[repr(transparent)]
+pub struct MaybeUtf8 { inner: [u8] }
+
+/// Byte container optionally encoded as UTF-8, generic over its backing storage `B`.
+///
+/// Any type implementing `ByteStorage` (e.g. `Vec<u8>`, `Box<[u8]>` or `Rc<[u8]>`)
+/// can be used without copying.
+///
+/// This is a separate type from `MaybeUtf8Buf` and `MaybeUtf8Slice`, which are not aliases of it:
+/// `MaybeUtf8Buf` relies on its own representation (inline storage for short values,
+/// the cached validity and encoding hints) which can't be built upon arbitrary storages.
+/// So `MaybeUtf8Of` only provides a small API for constructing, converting and slicing values.
+/// The remaining read-only API is available from `to_slice`, which never copies and
+/// remembers whether the value has been explicitly encoded in UTF-8,
+/// or from `MaybeUtf8` which this type dereferences to.
+/// Values backed by a `GrowableByteStorage` (e.g. `Vec<u8>`) can be also appended to
+/// and edited in place with `push_str`, `insert_str`, `truncate` and so on.
+#[derive(Clone)]
+pub struct MaybeUtf8Of<B> { bytes: B, utf8: bool }
+
+/// A byte storage which can back `MaybeUtf8Of`.
+///
+/// This is an unsafe trait, since `MaybeUtf8Of` trusts that `as_ref` consistently returns
+/// the same bytes as long as the storage is not mutated (via `&mut` or by moving it out).
+/// Otherwise the bytes once known to be encoded in UTF-8 might become invalid.
+///
+/// # Safety
+///
+/// `as_ref` should return the same bytes until the storage is mutated or moved out.
+pub unsafe trait ByteStorage: AsRef<[u8]> {}
+
+#[cfg(not(feature = "allocator_api"))] unsafe impl ByteStorage for Vec<u8> {}
+#[cfg(feature = "allocator_api")] unsafe impl<A: Allocator> ByteStorage for Vec<u8, A> {}
+unsafe impl ByteStorage for Box<[u8]> {}
+unsafe impl ByteStorage for Rc<[u8]> {}
+unsafe impl ByteStorage for Arc<[u8]> {}
+unsafe impl ByteStorage for &[u8] {}
+unsafe impl<'a> ByteStorage for Cow<'a, [u8]> {}
+#[cfg(feature = "bytes")] unsafe impl ByteStorage for Bytes {}
+#[cfg(feature = "smallvec")] unsafe impl<const N: usize> ByteStorage for SmallVec<[u8; N]> {}
+
+/// A byte storage which can back `MaybeUtf8Of` and also grow or shrink (e.g. `Vec<u8>`),
+/// so that `MaybeUtf8Of` can be mutated in place.
+///
+/// # Safety
+///
+/// Each method should change the bytes returned by `as_ref` exactly as documented.
+/// Otherwise the bytes once known to be encoded in UTF-8 might become invalid.
+pub unsafe trait GrowableByteStorage: ByteStorage {
+    /// Appends bytes at the end.
+    fn extend_from_slice(&mut self, v: &[u8]);
+    /// Inserts bytes at given byte offset, which is not greater than the current length.
+    fn insert_from_slice(&mut self, idx: usize, v: &[u8]);
+    /// Shortens the storage to given byte length, which is not greater than the current length.
+    fn truncate(&mut self, len: usize);
+    /// Reserves the capacity for at least `additional` more bytes.
+    fn reserve(&mut self, additional: usize);
+}
+
+macro_rules! define_vec_growable_byte_storage {
+    ($($(#[$attr:meta])* impl[$($gen:tt)*] $ty:ty;)*) => ($(
+        $(#[$attr])*
+        unsafe impl<$($gen)*> GrowableByteStorage for $ty {
+            fn extend_from_slice(&mut self, v: &[u8]) { Vec::extend_from_slice(self, v); }
+            fn insert_from_slice(&mut self, idx: usize, v: &[u8]) {
+                self.splice(idx..idx, v.iter().cloned());
+            }
+            fn truncate(&mut self, len: usize) { Vec::truncate(self, len); }
+            fn reserve(&mut self, additional: usize) { Vec::reserve(self, additional); }
+        }
+    )*)
+}
+
+define_vec_growable_byte_storage! {
+    #[cfg(not(feature = "allocator_api"))] impl[] Vec<u8>;
+    #[cfg(feature = "allocator_api")] impl[A: Allocator] Vec<u8, A>;
+}
+
+#[cfg(feature = "smallvec")]
+unsafe impl<const N: usize> GrowableByteStorage for SmallVec<[u8; N]> {
+    fn extend_from_slice(&mut self, v: &[u8]) { SmallVec::extend_from_slice(self, v); }
+    fn insert_from_slice(&mut self, idx: usize, v: &[u8]) {
+        SmallVec::insert_from_slice(self, idx, v);
+    }
+    fn truncate(&mut self, len: usize) { SmallVec::truncate(self, len); }
+    fn reserve(&mut self, additional: usize) { SmallVec::reserve(self, additional); }
+}
+
+/// Byte container optionally encoded as UTF-8, backed by a reference-counted `bytes::Bytes`.
+/// It can be cheaply cloned and sliced without copying.
+///
+/// This is only available with the `bytes` feature.
+#[cfg(feature = "bytes")]
+pub type MaybeUtf8Bytes = MaybeUtf8Of<Bytes>;
+
+/// Byte container optionally encoded as UTF-8, backed by a `SmallVec` which stores
+/// up to `N` bytes inline without allocation. It can be appended to like `MaybeUtf8Buf`,
+/// and spills to the heap only when it grows beyond `N` bytes.
+///
+/// This is only available with the `smallvec` feature.
+#[cfg(feature = "smallvec")]
+pub type MaybeUtf8SmallBuf<const N: usize> = MaybeUtf8Of<SmallVec<[u8; N]>>;
+
+/// Byte container optionally encoded as UTF-8, backed by a `Vec` using given allocator `A`.
+///
+/// This is only available with the `allocator_api` feature, which requires a nightly compiler.
+#[cfg(feature = "allocator_api")]
+pub type MaybeUtf8BufIn<A> = MaybeUtf8Of<Vec<u8, A>>;
+
+/// Byte container optionally encoded as UTF-8, backed by a boxed slice with no excess capacity.
+/// This is a compact form of `MaybeUtf8Buf` for long-lived values.
+pub type MaybeUtf8Box = MaybeUtf8Of<Box<[u8]>>;
+
+/// Byte container optionally encoded as UTF-8, backed by a range of an `Arc<[u8]>`.
+/// It can be cheaply cloned and sliced without copying, and shared across threads.
+pub type MaybeUtf8Shared = MaybeUtf8Of<SharedBytes>;
+
+/// A byte storage for `MaybeUtf8Shared`, which is a range of an atomically reference-counted
+/// byte slice.
+#[derive(Clone)]
+pub struct SharedBytes { arc: Arc<[u8]>, begin: usize, end: usize }
+
+unsafe impl ByteStorage for SharedBytes {}
+
+/// Clone-on-write byte container optionally encoded as UTF-8.
+/// It is either a borrowed `MaybeUtf8Slice` or an owned `MaybeUtf8Buf`,
+/// and gets converted to the owned form only when a mutation is required.
+#[derive(Clone)]
+pub enum MaybeUtf8Cow<'a> {
+    /// Borrowed data.
+    Borrowed(MaybeUtf8Slice<'a>),
+    /// Owned data.
+    Owned(MaybeUtf8Buf),
+}
+
 impl MaybeUtf8Buf {
     /// Creates a new empty `MaybeUtf8Buf` value (which is, naturally, encoded in UTF-8).
     pub fn new() -> MaybeUtf8Buf {
-        MaybeUtf8Buf { inner: Buf::Utf8(String::new()) }
+        // starts inline so that short values built by appending never allocate
+        MaybeUtf8Buf { repr: Repr::from_slice(&[], Tag::KnownUtf8) }
+    }
+
+    /// Creates a new empty `MaybeUtf8Buf` value (which is, naturally, encoded in UTF-8)
+    /// which can hold at least `capacity` bytes without reallocation.
+    pub fn with_capacity(capacity: usize) -> MaybeUtf8Buf {
+        let mut repr = Repr::from_slice(&[], Tag::KnownUtf8);
+        repr.reserve(capacity);
+        MaybeUtf8Buf { repr }
     }
 
     /// Creates a `MaybeUtf8Buf` value from an owned `String`.
+    /// The allocation of the string is kept even if it is short enough to be stored inline.
     pub fn from_str(s: String) -> MaybeUtf8Buf {
-        MaybeUtf8Buf { inner: Buf::Utf8(s) }
+        MaybeUtf8Buf { repr: Repr::Heap(s.into_bytes(), TagCell::new(Tag::KnownUtf8)) }
     }
 
     /// Creates a `MaybeUtf8Buf` value from an owned `Vec` of `u8` bytes.
+    /// The allocation of the vector is kept even if it is short enough to be stored inline.
     pub fn from_bytes(v: Vec<u8>) -> MaybeUtf8Buf {
-        MaybeUtf8Buf { inner: Buf::Bytes(v) }
+        MaybeUtf8Buf { repr: Repr::Heap(v, TagCell::new(Tag::Unknown)) }
+    }
+
+    /// Creates a `MaybeUtf8Buf` value by reading all bytes from given reader.
+    /// The result is explicitly encoded in UTF-8 when the bytes are valid in UTF-8,
+    /// which is checked while reading.
+    ///
+    /// This is only available with the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: io::Read>(reader: R) -> io::Result<MaybeUtf8Buf> {
+        let mut buf = MaybeUtf8Buf::new();
+        read_to_maybe_utf8(reader, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Same to `from_reader` but reads at most `limit` bytes from given reader.
+    ///
+    /// This is only available with the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn from_reader_with_limit<R: io::Read>(reader: R, limit: u64) -> io::Result<MaybeUtf8Buf> {
+        MaybeUtf8Buf::from_reader(reader.take(limit))
+    }
+
+    /// Creates a `MaybeUtf8Buf` value from a JSON value made by `to_json_value`.
+    /// Strings are explicitly encoded in UTF-8, and arrays of bytes and
+    /// objects with a single entry `base64` are not.
+    /// Returns `None` for other JSON values.
+    ///
+    /// This is only available with the `serde_json` feature.
+    #[cfg(feature = "serde_json")]
+    pub fn from_json_value(value: &Value) -> Option<MaybeUtf8Buf> {
+        json::from_json_value(value)
+    }
+
+    /// Creates a `MaybeUtf8Buf` value from an owned `Vec` of `u8` bytes,
+    /// with a hint about their encoding (e.g. `"shift_jis"` or `932`).
+    pub fn from_bytes_with_hint<H: Into<EncodingHint>>(v: Vec<u8>, hint: H) -> MaybeUtf8Buf {
+        let repr = Repr::Heap(v, TagCell::new(Tag::Unknown));
+        MaybeUtf8Buf { repr: Repr::Hinted(Box::new((repr, hint.into()))) }
+    }
+
+    /// Returns a hint about the encoding of underlying bytes if any.
+    /// The hint is not retained by conversions to other types including `MaybeUtf8Slice`.
+    pub fn hint(&self) -> Option<&EncodingHint> {
+        match self.repr {
+            Repr::Hinted(ref h) => Some(&h.1),
+            Repr::Heap(..) | Repr::Inline(..) => None,
+        }
     }
 
     // ---8<---
-    // the following methods are here due to the inability to
-    // implement `Deref` in the current form.
+    // the following methods shadow those from `Deref<Target=MaybeUtf8>`,
+    // since `MaybeUtf8` cannot know if it has been explicitly encoded in UTF-8.
 
     /// Returns a slice of underlying bytes. It might or might not be encoded in UTF-8.
-    pub fn as_bytes<'a>(&'a self) -> &'a [u8] {
-        match self.inner {
-            Buf::Utf8(ref s) => s.as_bytes(),
-            Buf::Bytes(ref v) => &v,
-        }
+    pub fn as_bytes(&self) -> &[u8] {
+        self.repr.as_bytes()
     }
 
     /// Returns a string slice encoded in UTF-8 if possible.
     /// It returns `None` if the underlying bytes are not encoded in UTF-8.
-    pub fn as_str<'a>(&'a self) -> Option<&'a str> {
-        match self.inner {
-            Buf::Utf8(ref s) => Some(&s),
-            Buf::Bytes(ref v) => str::from_utf8(&v).ok(),
+    pub fn as_str(&self) -> Option<&str> {
+        match self.repr.validate() {
+            Tag::KnownUtf8 | Tag::KnownValid =>
+                Some(unsafe { str::from_utf8_unchecked(self.repr.as_bytes()) }),
+            Tag::KnownInvalid | Tag::Incomplete | Tag::Unknown => None,
+        }
+    }
+
+    /// Returns a string slice encoded in UTF-8 if possible.
+    /// Unlike `as_str`, it returns a `Utf8Error` describing the first invalid UTF-8 sequence
+    /// if the underlying bytes are not encoded in UTF-8.
+    pub fn try_as_str(&self) -> Result<&str, str::Utf8Error> {
+        match self.as_str() {
+            Some(s) => Ok(s),
+            None => str::from_utf8(self.repr.as_bytes()),
         }
     }
 
     /// Returns a `Cow` string which represents the current `MaybeUtf8Slice`.
     /// It may call given `to_cow` function to get a `Cow` string out of the bytes.
     /// `to_cow` function itself may return a `String` or `&str` compatible to `Cow` string.
-    pub fn map_as_cow<'a, F, T>(&'a self, mut to_cow: F) -> Cow<'a, str>
-            where F: FnMut(&'a [u8]) -> T, T: IntoCow<'a, str> {
-        match self.inner {
-            Buf::Utf8(ref s) => s[..].into_cow(),
-            Buf::Bytes(ref v) => to_cow(&v).into_cow(),
-        }
+    pub fn map_as_cow<'a, F, T>(&'a self, to_cow: F) -> Cow<'a, str>
+            where F: FnMut(&'a [u8]) -> T, T: Into<Cow<'a, str>> {
+        self.to_slice().map_as_cow(to_cow)
     }
 
     // there is no `as_cow`; if we can convert bytes to a str, we don't need `Cow` string at all.
@@ -145,7 +537,19 @@ impl MaybeUtf8Buf {
     /// Returns a `Cow` string which represents the current `MaybeUtf8Slice`.
     /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
     pub fn as_cow_lossy<'a>(&'a self) -> Cow<'a, str> {
-        self.map_as_cow(String::from_utf8_lossy)
+        match self.as_str() {
+            Some(s) => Cow::Borrowed(s),
+            None => String::from_utf8_lossy(self.repr.as_bytes()),
+        }
+    }
+
+    /// Same to `as_cow_lossy` but replaces invalid UTF-8 sequences with given string,
+    /// which may be empty to drop them.
+    pub fn as_cow_lossy_with<'a>(&'a self, replacement: &str) -> Cow<'a, str> {
+        match self.as_str() {
+            Some(s) => Cow::Borrowed(s),
+            None => from_utf8_lossy_with(self.repr.as_bytes(), replacement),
+        }
     }
 
     // the end of duplicate methods.
@@ -153,257 +557,4120 @@ impl MaybeUtf8Buf {
 
     /// Returns a `MaybeUtf8Slice` borrowed from this `MaybeUtf8Buf`.
     pub fn to_slice<'a>(&'a self) -> MaybeUtf8Slice<'a> {
-        match self.inner {
-            Buf::Utf8(ref s) => MaybeUtf8Slice::from_str(s),
-            Buf::Bytes(ref v) => MaybeUtf8Slice::from_bytes(v),
+        match self.repr.tag() {
+            Tag::KnownUtf8 =>
+                MaybeUtf8Slice::from_str(unsafe { str::from_utf8_unchecked(self.repr.as_bytes()) }),
+            Tag::KnownValid | Tag::KnownInvalid | Tag::Incomplete | Tag::Unknown =>
+                MaybeUtf8Slice::from_bytes(self.repr.as_bytes()),
         }
     }
 
-    /// Tries to convert a `MaybeUtf8Buf` into a `String`.
-    /// If there is an invalid UTF-8 sequence it returns the original `MaybeUtf8Buf` back.
-    pub fn into_str(self) -> Result<String, MaybeUtf8Buf> {
-        match self.inner {
-            Buf::Utf8(s) => Ok(s),
-            Buf::Bytes(v) => match String::from_utf8(v) {
-                Ok(s) => Ok(s),
-                Err(e) => Err(MaybeUtf8Buf { inner: Buf::Bytes(e.into_bytes()) }),
-            },
-        }
+    /// Returns a `MaybeUtf8Slice` for given byte range, or `None` if it is out of bounds.
+    /// It is no longer explicitly encoded in UTF-8
+    /// if either end of the range is not at a char boundary.
+    pub fn get<R: RangeBounds<usize>>(&self, range: R) -> Option<MaybeUtf8Slice<'_>> {
+        self.to_slice().get(range)
     }
 
-    /// Converts a `MaybeUtf8Buf` into a `String`.
-    /// It may call given `into_str` function to get a `String` out of the bytes.
-    pub fn map_into_str<F>(self, mut into_str: F) -> String
-            where F: FnMut(Vec<u8>) -> String {
-        match self.inner {
-            Buf::Utf8(s) => s,
-            Buf::Bytes(v) => into_str(v),
-        }
+    /// Returns an iterator over extended grapheme clusters as `MaybeUtf8Slice`s.
+    /// Each invalid UTF-8 sequence is considered a single cluster.
+    ///
+    /// This is only available with the `unicode-segmentation` feature.
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn graphemes_lossy(&self) -> GraphemesLossy<'_> {
+        self.to_slice().graphemes_lossy()
     }
 
-    /// Converts a `MaybeUtf8Buf` into a `String`.
-    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
-    pub fn into_str_lossy(self) -> String {
-        self.map_into_str(|v| match String::from_utf8_lossy(v.as_slice()) {
-            // `v` is definitely UTF-8, so do not make a copy!
-            Cow::Borrowed(_) => unsafe {String::from_utf8_unchecked(v)},
-            Cow::Owned(s) => s,
-        })
+    /// Returns the number of terminal columns occupied by the lossy rendering
+    /// (as like `as_cow_lossy`), where each U+FFFD occupies a single column.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn display_width(&self) -> usize {
+        self.to_slice().display_width()
     }
 
-    /// Converts a `MaybeUtf8Buf` into a `Vec` of `u8` bytes.
-    pub fn into_bytes(self) -> Vec<u8> {
-        match self.inner {
-            Buf::Utf8(s) => s.into_bytes(),
-            Buf::Bytes(v) => v,
-        }
+    /// Returns the longest prefix of which lossy rendering occupies at most `cols` columns.
+    /// It is no longer explicitly encoded in UTF-8 if it doesn't end at a char boundary.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn truncate_to_width(&self, cols: usize) -> MaybeUtf8Slice<'_> {
+        self.to_slice().truncate_to_width(cols)
     }
 
-    /// Returns a byte length of the `MaybeUtf8Buf` value.
-    pub fn len(&self) -> usize {
-        match self.inner {
-            Buf::Utf8(ref s) => s.len(),
-            Buf::Bytes(ref v) => v.len(),
-        }
+    /// Returns the longest suffix of which lossy rendering occupies at most `cols` columns.
+    /// It is no longer explicitly encoded in UTF-8 if it doesn't start at a char boundary.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn truncate_start_to_width(&self, cols: usize) -> MaybeUtf8Slice<'_> {
+        self.to_slice().truncate_start_to_width(cols)
     }
-}
 
-impl<'a> MaybeUtf8Slice<'a> {
-    /// Creates a new empty `MaybeUtf8Slice` value (which is, naturally, encoded in UTF-8).
-    pub fn new() -> MaybeUtf8Slice<'static> {
-        MaybeUtf8Slice { inner: Slice::Utf8("") }
+    /// Returns the value truncated to at most `max_cols` columns, where the end is replaced
+    /// with an ellipsis (`…`) if it doesn't fit. It is borrowed from the original value if fits.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn ellipsize(&self, max_cols: usize) -> MaybeUtf8Cow<'_> {
+        self.to_slice().ellipsize(max_cols)
     }
 
-    /// Creates a `MaybeUtf8Slice` reference from a string slice.
-    pub fn from_str(s: &'a str) -> MaybeUtf8Slice<'a> {
-        MaybeUtf8Slice { inner: Slice::Utf8(s) }
+    /// Returns the value truncated to at most `max_cols` columns, where the middle is replaced
+    /// with an ellipsis (`…`) if it doesn't fit. It is borrowed from the original value if fits.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn ellipsize_middle(&self, max_cols: usize) -> MaybeUtf8Cow<'_> {
+        self.to_slice().ellipsize_middle(max_cols)
     }
 
-    /// Creates a `MaybeUtf8Slice` reference from a `u8` slice.
-    pub fn from_bytes(v: &'a [u8]) -> MaybeUtf8Slice<'a> {
-        MaybeUtf8Slice { inner: Slice::Bytes(v) }
+    /// Returns `true` if two values are equal when the case is ignored.
+    /// Unicode simple case folding applies to the parts valid in UTF-8,
+    /// and the remaining bytes are compared as they are.
+    ///
+    /// This is only available with the `unicode-case-mapping` feature.
+    #[cfg(feature = "unicode-case-mapping")]
+    pub fn eq_ignore_case<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, other: T) -> bool {
+        self.to_slice().eq_ignore_case(other)
     }
 
-    /// Returns a slice of underlying bytes. It might or might not be encoded in UTF-8.
-    pub fn as_bytes(&self) -> &'a [u8] {
-        match self.inner {
-            Slice::Utf8(s) => s.as_bytes(),
-            Slice::Bytes(v) => v.as_slice(),
-        }
+    /// Compares two values when the case is ignored.
+    /// Unicode simple case folding applies to the parts valid in UTF-8,
+    /// and the remaining bytes are compared as they are (and ordered after any characters).
+    ///
+    /// This is only available with the `unicode-case-mapping` feature.
+    #[cfg(feature = "unicode-case-mapping")]
+    pub fn cmp_ignore_case<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, other: T) -> Ordering {
+        self.to_slice().cmp_ignore_case(other)
     }
 
-    /// Returns a string slice encoded in UTF-8 if possible.
-    /// It returns `None` if the underlying bytes are not encoded in UTF-8.
-    pub fn as_str(&self) -> Option<&'a str> {
-        match self.inner {
-            Slice::Utf8(s) => Some(s.as_slice()),
-            Slice::Bytes(v) => str::from_utf8(v.as_slice()).ok(),
-        }
+    /// Returns a new `MaybeUtf8Buf` with Unicode simple case folding applied
+    /// to the parts valid in UTF-8, which can be used as a case-insensitive key.
+    /// Invalid UTF-8 sequences are kept as they are.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    ///
+    /// This is only available with the `unicode-case-mapping` feature.
+    #[cfg(feature = "unicode-case-mapping")]
+    pub fn case_fold(&self) -> MaybeUtf8Buf {
+        self.to_slice().case_fold()
     }
 
-    /// Returns a `Cow` string which represents the current `MaybeUtf8Slice`.
-    /// It may call given `to_cow` function to get a `Cow` string out of the bytes.
-    /// `to_cow` function itself may return a `String` or `&str` compatible to `Cow` string.
-    pub fn map_as_cow<F, T>(&self, mut to_cow: F) -> Cow<'a, str>
-            where F: FnMut(&'a [u8]) -> T, T: IntoCow<'a, str> {
-        match self.inner {
-            Slice::Utf8(s) => s.into_cow(),
-            Slice::Bytes(v) => to_cow(&v).into_cow(),
-        }
+    /// Same to `case_fold` but also normalizes the parts valid in UTF-8 to NFD
+    /// before and after the case folding, so that canonically equivalent values
+    /// result in the same key.
+    ///
+    /// This is only available with both the `unicode-case-mapping`
+    /// and `unicode-normalization` features.
+    #[cfg(all(feature = "unicode-case-mapping", feature = "unicode-normalization"))]
+    pub fn case_fold_normalized(&self) -> MaybeUtf8Buf {
+        self.to_slice().case_fold_normalized()
     }
 
+    /// Compares two values in the natural order, where each run of ASCII digits
+    /// is compared by its numeric value (so that `file2` comes before `file10`).
+    /// The underlying bytes are compared as a last resort,
+    /// so only identical values are considered equal.
+    pub fn cmp_natural<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, other: T) -> Ordering {
+        self.to_slice().cmp_natural(other)
+    }
 
-    // there is no `as_cow`; if we can convert bytes to a str, we don't need `Cow` string at all.
+    /// Returns a collation key for given collator (e.g. created from a locale
+    /// with `icu_collator::Collator::try_new`), which can be compared bytewise
+    /// to sort values in the order of the collator.
+    /// Each invalid UTF-8 sequence is collated as U+FFFD,
+    /// and the underlying bytes break ties so that distinct values never have the same key.
+    ///
+    /// This is only available with the `icu_collator` feature.
+    #[cfg(feature = "icu_collator")]
+    pub fn collation_key(&self, collator: &CollatorBorrowed) -> Vec<u8> {
+        self.to_slice().collation_key(collator)
+    }
 
-    /// Returns a `Cow` string which represents the current `MaybeUtf8Slice`.
-    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
-    pub fn as_cow_lossy(&self) -> Cow<'a, str> {
-        self.map_as_cow(String::from_utf8_lossy)
+    /// Returns `true` if given regex matches a part of the underlying bytes.
+    ///
+    /// This is only available with the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn is_match(&self, re: &Regex) -> bool {
+        self.to_slice().is_match(re)
     }
 
-    /// Returns a new `MaybeUtf8Buf` from the current `MaybeUtf8Slice`.
-    pub fn to_owned(&self) -> MaybeUtf8Buf {
-        match self.inner {
-            Slice::Utf8(s) => MaybeUtf8Buf::from_str(s.to_owned()),
-            Slice::Bytes(v) => MaybeUtf8Buf::from_bytes(v.to_owned()),
-        }
+    /// Returns an iterator over non-overlapping matches of given regex as `MaybeUtf8Slice`s
+    /// and their byte offsets.
+    ///
+    /// This is only available with the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn find_iter<'r>(&self, re: &'r Regex) -> RegexMatches<'r, '_> {
+        self.to_slice().find_iter(re)
     }
 
-    /// Returns a byte length of the `MaybeUtf8Slice` value.
-    pub fn len(&self) -> usize {
-        match self.inner {
-            Slice::Utf8(ref s) => s.len(),
-            Slice::Bytes(ref v) => v.len(),
-        }
+    /// Returns capture groups of the first match of given regex,
+    /// or `None` if the regex doesn't match.
+    ///
+    /// This is only available with the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn captures(&self, re: &Regex) -> Option<RegexCaptures<'_>> {
+        self.to_slice().captures(re)
     }
-}
 
-macro_rules! define_partial_eq_and_cmp {
-    ($($lty:ty:$lmeth:ident, $rty:ty:$rmeth:ident;)*) => ($(
-        impl<'a, 'b> PartialEq<$rty> for $lty {
-            fn eq(&self, other: &$rty) -> bool { self.$lmeth().eq(other.$rmeth()) }
-        }
-        impl<'a, 'b> PartialOrd<$rty> for $lty {
-            fn partial_cmp(&self, other: &$rty) -> Option<Ordering> {
-                self.$lmeth().partial_cmp(other.$rmeth())
-            }
-        }
-    )*)
-}
+    /// Returns `true` if the value matches given glob pattern with the default options.
+    /// See `MaybeUtf8Slice::matches_glob` for the syntax.
+    ///
+    /// This is only available with the `glob` feature.
+    #[cfg(feature = "glob")]
+    pub fn matches_glob<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, pattern: T) -> bool {
+        self.to_slice().matches_glob(pattern)
+    }
 
-define_partial_eq_and_cmp! {
-    MaybeUtf8Buf:as_bytes, MaybeUtf8Buf:as_bytes;
-    MaybeUtf8Buf:as_bytes, MaybeUtf8Slice<'b>:as_bytes;
-    MaybeUtf8Buf:as_bytes, &'b str:as_bytes;
-    MaybeUtf8Buf:as_bytes, &'b [u8]:as_slice;
-    MaybeUtf8Slice<'a>:as_bytes, MaybeUtf8Buf:as_bytes;
-    MaybeUtf8Slice<'a>:as_bytes, MaybeUtf8Slice<'b>:as_bytes;
-    MaybeUtf8Slice<'a>:as_bytes, &'b str:as_bytes;
-    MaybeUtf8Slice<'a>:as_bytes, &'b [u8]:as_slice;
-}
+    /// Returns `true` if the value matches given glob pattern with given options.
+    ///
+    /// This is only available with the `glob` feature.
+    #[cfg(feature = "glob")]
+    pub fn matches_glob_with<'b, T>(&self, pattern: T, options: GlobOptions) -> bool
+            where T: IntoMaybeUtf8<MaybeUtf8Slice<'b>> {
+        self.to_slice().matches_glob_with(pattern, options)
+    }
 
-impl Eq for MaybeUtf8Buf {
-}
+    /// Returns `true` if given byte offset is at a char boundary.
+    /// Every offset up to the length is considered at a char boundary
+    /// if the value is not explicitly encoded in UTF-8.
+    pub fn is_char_boundary(&self, idx: usize) -> bool {
+        self.to_slice().is_char_boundary(idx)
+    }
 
-impl<'a> Eq for MaybeUtf8Slice<'a> {
-}
+    /// Returns the closest char boundary at or before given byte offset.
+    /// The length is returned if the offset is greater than the length.
+    pub fn floor_char_boundary(&self, idx: usize) -> usize {
+        self.to_slice().floor_char_boundary(idx)
+    }
 
-impl Ord for MaybeUtf8Buf {
-    fn cmp(&self, other: &MaybeUtf8Buf) -> Ordering {
-        self.as_bytes().cmp(other.as_bytes())
+    /// Returns the closest char boundary at or after given byte offset.
+    /// The length is returned if the offset is greater than the length.
+    pub fn ceil_char_boundary(&self, idx: usize) -> usize {
+        self.to_slice().ceil_char_boundary(idx)
     }
-}
 
-impl<'a> Ord for MaybeUtf8Slice<'a> {
-    fn cmp(&self, other: &MaybeUtf8Slice<'a>) -> Ordering {
-        self.as_bytes().cmp(other.as_bytes())
+    /// Divides the `MaybeUtf8Buf` into two `MaybeUtf8Slice`s at given byte offset.
+    /// They are no longer explicitly encoded in UTF-8 if the offset is not at a char boundary.
+    ///
+    /// Panics if `mid` is greater than the current length.
+    pub fn split_at(&self, mid: usize) -> (MaybeUtf8Slice<'_>, MaybeUtf8Slice<'_>) {
+        self.to_slice().split_at(mid)
     }
-}
 
-impl FromIterator<char> for MaybeUtf8Buf {
-    fn from_iter<I: IntoIterator<Item=char>>(iterator: I) -> MaybeUtf8Buf {
-        MaybeUtf8Buf::from_str(FromIterator::from_iter(iterator))
+    /// Returns a `MaybeUtf8Slice` with given prefix removed,
+    /// or `None` if the pattern doesn't match a prefix of the underlying bytes.
+    /// It is no longer explicitly encoded in UTF-8 if the prefix doesn't end at a char boundary.
+    pub fn strip_prefix<P: BytePattern>(&self, pat: P) -> Option<MaybeUtf8Slice<'_>> {
+        self.to_slice().strip_prefix(pat)
     }
-}
 
-impl FromIterator<u8> for MaybeUtf8Buf {
-    fn from_iter<I: IntoIterator<Item=u8>>(iterator: I) -> MaybeUtf8Buf {
-        MaybeUtf8Buf::from_bytes(FromIterator::from_iter(iterator))
+    /// Returns a `MaybeUtf8Slice` with given suffix removed,
+    /// or `None` if the pattern doesn't match a suffix of the underlying bytes.
+    /// It is no longer explicitly encoded in UTF-8 if the suffix doesn't start at a char boundary.
+    pub fn strip_suffix<P: BytePattern>(&self, pat: P) -> Option<MaybeUtf8Slice<'_>> {
+        self.to_slice().strip_suffix(pat)
     }
-}
 
-impl Default for MaybeUtf8Buf {
-    fn default() -> MaybeUtf8Buf { MaybeUtf8Buf::new() }
-}
+    /// Returns an iterator over `MaybeUtf8Slice`s separated by given pattern.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn split<P: BytePattern>(&self, sep: P) -> Split<'_, P> {
+        self.to_slice().split(sep)
+    }
 
-impl<'a> Default for MaybeUtf8Slice<'a> {
-    fn default() -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::new() }
-}
+    /// Returns an iterator over `MaybeUtf8Slice`s separated by given string slice,
+    /// `u8` slice or anything convertible to `MaybeUtf8Slice`.
+    ///
+    /// Panics if the separator is empty.
+    pub fn split_str<'b, P>(&self, sep: P) -> Split<'_, MaybeUtf8Slice<'b>>
+            where P: IntoMaybeUtf8<MaybeUtf8Slice<'b>> {
+        self.to_slice().split_str(sep)
+    }
 
-impl fmt::Debug for MaybeUtf8Buf {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&self.to_slice(), f)
+    /// Returns an iterator over `MaybeUtf8Slice`s separated by given pattern,
+    /// starting from the end.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn rsplit<P: BytePattern>(&self, sep: P) -> RSplit<'_, P> {
+        self.to_slice().rsplit(sep)
     }
-}
 
-impl fmt::Display for MaybeUtf8Buf {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Display::fmt(&self.to_slice(), f)
+    /// Returns an iterator over at most `n` `MaybeUtf8Slice`s separated by given pattern.
+    /// The last slice contains the remainder.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn splitn<P: BytePattern>(&self, n: usize, sep: P) -> SplitN<'_, P> {
+        self.to_slice().splitn(n, sep)
     }
-}
 
-impl<'a> fmt::Debug for MaybeUtf8Slice<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.inner {
-            Slice::Utf8(ref s) => fmt::Debug::fmt(s, f),
-            Slice::Bytes(ref v) => {
-                try!(write!(f, "b\""));
-                for &c in v.iter() {
-                    match c {
-                        b'\t' => try!(write!(f, "\\t")),
-                        b'\r' => try!(write!(f, "\\r")),
-                        b'\n' => try!(write!(f, "\\n")),
-                        b'\\' => try!(write!(f, "\\\\")),
-                        b'\'' => try!(write!(f, "\\'")),
-                        b'"'  => try!(write!(f, "\\\"")),
-                        b'\x20' ... b'\x7e' => try!(write!(f, "{}", c as char)),
-                        _ => try!(write!(f, "\\x{}{}",
-                                         char::from_digit((c as u32) >> 4, 16).unwrap(),
-                                         char::from_digit((c as u32) & 0xf, 16).unwrap()))
-                    }
-                }
-                write!(f, "\"")
-            }
-        }
+    /// Returns an iterator over at most `n` `MaybeUtf8Slice`s separated by given pattern,
+    /// starting from the end. The last slice contains the remainder.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn rsplitn<P: BytePattern>(&self, n: usize, sep: P) -> RSplitN<'_, P> {
+        self.to_slice().rsplitn(n, sep)
     }
-}
 
-impl<'a> fmt::Display for MaybeUtf8Slice<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.inner {
-            Slice::Utf8(ref s) => fmt::Display::fmt(s, f),
-            Slice::Bytes(ref v) => fmt::Display::fmt(&String::from_utf8_lossy(&*v), f),
-        }
+    /// Returns an iterator over `MaybeUtf8Slice`s terminated by given pattern.
+    /// It is same to `split` except that a trailing empty slice is skipped.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn split_terminator<P: BytePattern>(&self, sep: P) -> SplitTerminator<'_, P> {
+        self.to_slice().split_terminator(sep)
     }
-}
 
-/// A helper trait for uniformly creating `MaybeUtf8Buf` or `MaybeUtf8Slice` values.
-pub trait IntoMaybeUtf8<T> {
-    /// Converts given value into either `MaybeUtf8Buf` or `MaybeUtf8Slice`.
-    fn into_maybe_utf8(self) -> T;
-}
+    /// Returns an iterator over lines as `MaybeUtf8Slice`s.
+    /// Lines are terminated by `\n` optionally preceded by `\r`, which are not included.
+    /// This never fails even when the value is not valid in UTF-8.
+    pub fn lines(&self) -> Lines<'_> {
+        self.to_slice().lines()
+    }
 
-impl IntoMaybeUtf8<MaybeUtf8Buf> for String {
-    fn into_maybe_utf8(self) -> MaybeUtf8Buf { MaybeUtf8Buf::from_str(self) }
-}
+    /// Returns an iterator over non-empty `MaybeUtf8Slice`s separated by whitespace.
+    /// Unicode whitespace is recognized if the value is explicitly encoded in UTF-8,
+    /// and only ASCII whitespace is recognized otherwise.
+    pub fn split_whitespace(&self) -> SplitWhitespace<'_> {
+        self.to_slice().split_whitespace()
+    }
 
-impl IntoMaybeUtf8<MaybeUtf8Buf> for Vec<u8> {
-    fn into_maybe_utf8(self) -> MaybeUtf8Buf { MaybeUtf8Buf::from_bytes(self) }
-}
+    /// Returns a new `MaybeUtf8Buf` with all matches of given pattern replaced
+    /// with a string slice, a `u8` slice or anything convertible to `MaybeUtf8Slice`.
+    /// The result is explicitly encoded in UTF-8 only when the original value and
+    /// the replacement are and every match is at char boundaries.
+    ///
+    /// Panics if the pattern matches an empty sequence.
+    pub fn replace<'b, P, T>(&self, from: P, to: T) -> MaybeUtf8Buf
+            where P: BytePattern, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>> {
+        self.to_slice().replace(from, to)
+    }
 
-impl<'a> IntoMaybeUtf8<MaybeUtf8Slice<'a>> for &'a String {
-    fn into_maybe_utf8(self) -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::from_str(self) }
-}
+    /// Same to `replace` but replaces at most `count` first matches of given pattern.
+    ///
+    /// Panics if the pattern matches an empty sequence.
+    pub fn replacen<'b, P, T>(&self, from: P, to: T, count: usize) -> MaybeUtf8Buf
+            where P: BytePattern, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>> {
+        self.to_slice().replacen(from, to, count)
+    }
+
+    /// Returns a `MaybeUtf8Slice` with leading and trailing whitespace removed.
+    /// Unicode whitespace is removed if the underlying bytes are valid in UTF-8,
+    /// and only ASCII whitespace is removed otherwise.
+    pub fn trim(&self) -> MaybeUtf8Slice<'_> {
+        self.to_slice().trim()
+    }
+
+    /// Returns a `MaybeUtf8Slice` with leading whitespace removed.
+    /// Unicode whitespace is removed if the underlying bytes are valid in UTF-8,
+    /// and only ASCII whitespace is removed otherwise.
+    pub fn trim_start(&self) -> MaybeUtf8Slice<'_> {
+        self.to_slice().trim_start()
+    }
+
+    /// Returns a `MaybeUtf8Slice` with trailing whitespace removed.
+    /// Unicode whitespace is removed if the underlying bytes are valid in UTF-8,
+    /// and only ASCII whitespace is removed otherwise.
+    pub fn trim_end(&self) -> MaybeUtf8Slice<'_> {
+        self.to_slice().trim_end()
+    }
+
+    /// Returns a `MaybeUtf8Slice` with leading and trailing ASCII whitespace removed.
+    pub fn trim_ascii(&self) -> MaybeUtf8Slice<'_> {
+        self.to_slice().trim_ascii()
+    }
+
+    /// Returns a `MaybeUtf8Slice` with leading ASCII whitespace removed.
+    pub fn trim_ascii_start(&self) -> MaybeUtf8Slice<'_> {
+        self.to_slice().trim_ascii_start()
+    }
+
+    /// Returns a `MaybeUtf8Slice` with trailing ASCII whitespace removed.
+    pub fn trim_ascii_end(&self) -> MaybeUtf8Slice<'_> {
+        self.to_slice().trim_ascii_end()
+    }
+
+    /// Returns a `MaybeUtf8Slice` with all leading and trailing matches of given pattern removed.
+    /// It is no longer explicitly encoded in UTF-8 if either end is not at a char boundary.
+    pub fn trim_matches<P: BytePattern>(&self, pat: P) -> MaybeUtf8Slice<'_> {
+        self.to_slice().trim_matches(pat)
+    }
+
+    /// Returns a `MaybeUtf8Slice` with all leading matches of given pattern removed.
+    /// It is no longer explicitly encoded in UTF-8 if the start is not at a char boundary.
+    pub fn trim_start_matches<P: BytePattern>(&self, pat: P) -> MaybeUtf8Slice<'_> {
+        self.to_slice().trim_start_matches(pat)
+    }
+
+    /// Returns a `MaybeUtf8Slice` with all trailing matches of given pattern removed.
+    /// It is no longer explicitly encoded in UTF-8 if the end is not at a char boundary.
+    pub fn trim_end_matches<P: BytePattern>(&self, pat: P) -> MaybeUtf8Slice<'_> {
+        self.to_slice().trim_end_matches(pat)
+    }
+
+    /// Returns a mutable reference to the underlying `Vec`.
+    /// Note that short values stored inline will be moved to the heap.
+    ///
+    /// # Safety
+    ///
+    /// If the value is explicitly encoded in UTF-8, the bytes should remain valid in UTF-8
+    /// after the mutation. Otherwise any bytes can be written.
+    pub unsafe fn as_mut_vec(&mut self) -> &mut Vec<u8> {
+        // any cached validation result would be invalidated by the mutation
+        if self.repr.tag() != Tag::KnownUtf8 {
+            self.repr.set_tag(Tag::Unknown);
+        }
+        self.repr.as_mut_vec()
+    }
+
+    /// Returns a `MaybeUtf8SliceMut` mutably borrowed from this `MaybeUtf8Buf`.
+    pub fn to_slice_mut<'a>(&'a mut self) -> MaybeUtf8SliceMut<'a> {
+        let (bytes, tag) = self.repr.as_bytes_mut_and_tag();
+        MaybeUtf8SliceMut { utf8: tag.get() == Tag::KnownUtf8, bytes, owner: Some(tag) }
+    }
+
+    /// Tries to convert a `MaybeUtf8Buf` into a `String`.
+    /// If there is an invalid UTF-8 sequence it returns the original `MaybeUtf8Buf` back.
+    pub fn into_str(self) -> Result<String, MaybeUtf8Buf> {
+        if self.as_str().is_none() {
+            return Err(self);
+        }
+        Ok(unsafe { String::from_utf8_unchecked(self.repr.into_vec()) })
+    }
+
+    /// Converts a `MaybeUtf8Buf` into a `String`.
+    /// It may call given `into_str` function to get a `String` out of the bytes.
+    pub fn map_into_str<F>(self, mut into_str: F) -> String
+            where F: FnMut(Vec<u8>) -> String {
+        match self.repr.tag() {
+            Tag::KnownUtf8 => unsafe { String::from_utf8_unchecked(self.repr.into_vec()) },
+            Tag::KnownValid | Tag::KnownInvalid | Tag::Incomplete | Tag::Unknown =>
+                into_str(self.repr.into_vec()),
+        }
+    }
+
+    /// Converts a `MaybeUtf8Buf` into a `String`.
+    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn into_str_lossy(self) -> String {
+        self.map_into_str(|v| match String::from_utf8_lossy(&v) {
+            // `v` is definitely UTF-8, so do not make a copy!
+            Cow::Borrowed(_) => unsafe {String::from_utf8_unchecked(v)},
+            Cow::Owned(s) => s,
+        })
+    }
+
+    /// Same to `into_str_lossy` but replaces invalid UTF-8 sequences with given string,
+    /// which may be empty to drop them.
+    pub fn into_str_lossy_with(self, replacement: &str) -> String {
+        self.map_into_str(|v| match from_utf8_lossy_with(&v, replacement) {
+            Cow::Borrowed(_) => unsafe {String::from_utf8_unchecked(v)},
+            Cow::Owned(s) => s,
+        })
+    }
+
+    /// Converts a `MaybeUtf8Buf` into an owned `Cow` string, as like `into_str_lossy`.
+    /// It is useful for passing to APIs accepting `Cow<'static, str>`.
+    pub fn into_cow_lossy(self) -> Cow<'static, str> {
+        Cow::Owned(self.into_str_lossy())
+    }
+
+    /// Converts a `MaybeUtf8Buf` into a `MaybeUtf8Box`, dropping any excess capacity.
+    /// Note that short values stored inline will be moved to the heap.
+    pub fn into_boxed(self) -> MaybeUtf8Box {
+        let utf8 = self.repr.tag() == Tag::KnownUtf8;
+        MaybeUtf8Of { bytes: self.repr.into_vec().into_boxed_slice(), utf8 }
+    }
+
+    /// Decomposes a `MaybeUtf8Buf` into the raw pointer, length and capacity of
+    /// the underlying buffer, and whether it is explicitly encoded in UTF-8.
+    /// The buffer can be reassembled with `MaybeUtf8Buf::from_raw_parts`.
+    /// Note that short values stored inline will be moved to the heap.
+    pub fn into_raw_parts(self) -> (*mut u8, usize, usize, bool) {
+        let utf8 = self.repr.tag() == Tag::KnownUtf8;
+        let mut v = mem::ManuallyDrop::new(self.repr.into_vec());
+        (v.as_mut_ptr(), v.len(), v.capacity(), utf8)
+    }
+
+    /// Creates a `MaybeUtf8Buf` value from the raw pointer, length and capacity of a buffer,
+    /// and whether it is explicitly encoded in UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// The buffer should satisfy all requirements of `Vec::from_raw_parts`,
+    /// and its contents should be valid in UTF-8 if `utf8` is `true`.
+    pub unsafe fn from_raw_parts(ptr: *mut u8, length: usize, capacity: usize,
+                                 utf8: bool) -> MaybeUtf8Buf {
+        let v = Vec::from_raw_parts(ptr, length, capacity);
+        let tag = if utf8 { Tag::KnownUtf8 } else { Tag::Unknown };
+        MaybeUtf8Buf { repr: Repr::Heap(v, TagCell::new(tag)) }
+    }
+
+    /// Converts a `MaybeUtf8Buf` into a `Vec` of `u8` bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.repr.into_vec()
+    }
+
+    /// Consumes and leaks a `MaybeUtf8Buf`, returning a `MaybeUtf8Slice` to its contents
+    /// which lives until the end of the program, as like `String::leak`.
+    /// Note that short values stored inline will be moved to the heap.
+    pub fn leak(self) -> MaybeUtf8Slice<'static> {
+        let utf8 = self.repr.tag() == Tag::KnownUtf8;
+        let v: &'static [u8] = self.repr.into_vec().leak();
+        if utf8 {
+            MaybeUtf8Slice::from_str(unsafe {str::from_utf8_unchecked(v)})
+        } else {
+            MaybeUtf8Slice::from_bytes(v)
+        }
+    }
+
+    /// Returns the number of bytes the `MaybeUtf8Buf` can hold without reallocation.
+    pub fn capacity(&self) -> usize {
+        self.repr.capacity()
+    }
+
+    /// Reserves the capacity for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.repr.reserve(additional);
+    }
+
+    /// Shrinks the capacity as much as possible.
+    /// Short values will be stored inline.
+    pub fn shrink_to_fit(&mut self) {
+        self.repr.shrink_to_fit();
+    }
+
+    /// Converts every ASCII letter to the lower case in place.
+    /// This never breaks the UTF-8 encoding.
+    pub fn make_ascii_lowercase(&mut self) {
+        self.to_slice_mut().make_ascii_lowercase();
+    }
+
+    /// Converts every ASCII letter to the upper case in place.
+    /// This never breaks the UTF-8 encoding.
+    pub fn make_ascii_uppercase(&mut self) {
+        self.to_slice_mut().make_ascii_uppercase();
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with every ASCII letter converted to the lower case.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    pub fn to_ascii_lowercase(&self) -> MaybeUtf8Buf {
+        self.to_slice().to_ascii_lowercase()
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with every ASCII letter converted to the upper case.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    pub fn to_ascii_uppercase(&self) -> MaybeUtf8Buf {
+        self.to_slice().to_ascii_uppercase()
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with every character converted to the lower case,
+    /// as like `str::to_lowercase`. Invalid UTF-8 sequences are kept as they are.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    pub fn to_lowercase_lossy(&self) -> MaybeUtf8Buf {
+        self.to_slice().to_lowercase_lossy()
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with every character converted to the upper case,
+    /// as like `str::to_uppercase`. Invalid UTF-8 sequences are kept as they are.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    pub fn to_uppercase_lossy(&self) -> MaybeUtf8Buf {
+        self.to_slice().to_uppercase_lossy()
+    }
+
+    /// Returns an ASCII string approximating the value, where the parts valid in UTF-8
+    /// are transliterated (e.g. `Æneid` to `AEneid`) and each invalid byte is escaped as `%XX`.
+    ///
+    /// This is only available with the `deunicode` feature.
+    #[cfg(feature = "deunicode")]
+    pub fn to_ascii_lossy_translit(&self) -> String {
+        self.to_slice().to_ascii_lossy_translit()
+    }
+
+    /// Returns a JSON value, which is a string if explicitly encoded in UTF-8
+    /// and represented as given `JsonBytes` otherwise.
+    ///
+    /// This is only available with the `serde_json` feature.
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_value(&self, bytes: JsonBytes) -> Value {
+        self.to_slice().to_json_value(bytes)
+    }
+
+    /// Returns a string which is safe to print to the terminal.
+    /// Control characters (C0, DEL and C1) and bidirectional formatting characters and marks
+    /// are escaped as `\xNN` or `\u{NNNN}`, and so is each invalid byte.
+    /// Backslashes are escaped as `\\` so that the result is unambiguous.
+    /// It is borrowed from the original value if there is nothing to escape.
+    pub fn sanitize_for_terminal(&self) -> Cow<'_, str> {
+        self.to_slice().sanitize_for_terminal()
+    }
+
+    /// Returns an iterator over characters of the value escaped as like `str::escape_default`,
+    /// or as like `[u8]::escape_ascii` if the value is not explicitly encoded in UTF-8.
+    pub fn escape_default(&self) -> EscapeDefault<'_> {
+        self.to_slice().escape_default()
+    }
+
+    /// Returns an iterator over characters of the value escaped as like `char::escape_debug`.
+    /// Each invalid byte is escaped as `\xNN`.
+    pub fn escape_debug(&self) -> EscapeDebug<'_> {
+        self.to_slice().escape_debug()
+    }
+
+    /// Returns a displayable quoted JSON string of the value,
+    /// where invalid UTF-8 sequences are escaped according to given policy.
+    pub fn escape_json(&self, policy: JsonEscapePolicy) -> EscapeJson<'_> {
+        self.to_slice().escape_json(policy)
+    }
+
+    /// Returns a quoted JSON string of the value,
+    /// where invalid UTF-8 sequences are escaped according to given policy.
+    pub fn to_json_string_escaped(&self, policy: JsonEscapePolicy) -> String {
+        self.to_slice().to_json_string_escaped(policy)
+    }
+
+    /// Returns a displayable hex dump of the underlying bytes as like `hexdump -C`.
+    pub fn hexdump(&self) -> HexDump<'_> {
+        self.to_slice().hexdump()
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with ANSI escape sequences (e.g. `ESC [ 3 1 m`) removed.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    /// Only sequences starting with `ESC` are recognized; 8-bit C1 introducers
+    /// (e.g. U+009B or the byte 0x9B for CSI) are kept as they are.
+    pub fn strip_ansi_escapes(&self) -> MaybeUtf8Buf {
+        self.to_slice().strip_ansi_escapes()
+    }
+
+    /// Returns the value with the parts valid in UTF-8 normalized to NFC,
+    /// keeping invalid UTF-8 sequences as they are.
+    /// It is borrowed from the original value if the value is already normalized.
+    ///
+    /// This is only available with the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn nfc(&self) -> MaybeUtf8Cow<'_> {
+        self.to_slice().nfc()
+    }
+
+    /// Returns the value with the parts valid in UTF-8 normalized to NFD,
+    /// keeping invalid UTF-8 sequences as they are.
+    /// It is borrowed from the original value if the value is already normalized.
+    ///
+    /// This is only available with the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn nfd(&self) -> MaybeUtf8Cow<'_> {
+        self.to_slice().nfd()
+    }
+
+    /// Returns the value with the parts valid in UTF-8 decomposed as like HFS+ file names,
+    /// keeping invalid UTF-8 sequences as they are.
+    /// It is borrowed from the original value if the value is already decomposed.
+    ///
+    /// This is only available with the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn normalize_for_macos(&self) -> MaybeUtf8Cow<'_> {
+        self.to_slice().normalize_for_macos()
+    }
+
+    /// Returns `true` if two values are equal after normalizing the parts valid in UTF-8 to NFD,
+    /// as like file names in APFS. Invalid UTF-8 sequences are compared as they are.
+    ///
+    /// This is only available with the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn eq_normalized<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, other: T) -> bool {
+        self.to_slice().eq_normalized(other)
+    }
+
+    /// Appends a string slice.
+    /// This doesn't change whether the value is explicitly encoded in UTF-8.
+    pub fn push_str(&mut self, s: &str) {
+        // appending a valid string never changes the validity of the original value,
+        // except that it can never complete an incomplete sequence at the end
+        if !s.is_empty() && self.repr.tag() == Tag::Incomplete {
+            self.repr.set_tag(Tag::KnownInvalid);
+        }
+        self.repr.extend_from_slice(s.as_bytes());
+    }
+
+    /// Appends a character.
+    /// This doesn't change whether the value is explicitly encoded in UTF-8.
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Appends a `u8` slice. The value is no longer explicitly encoded in UTF-8.
+    pub fn push_bytes(&mut self, v: &[u8]) {
+        self.repr.extend_from_slice(v);
+        self.repr.set_tag(Tag::Unknown);
+    }
+
+    /// Appends a `u8` slice, which may be a chunk of a stream.
+    ///
+    /// Unlike `push_bytes`, this keeps track of whether the value is valid in UTF-8,
+    /// validating only the appended bytes (and an incomplete sequence at the end, if any).
+    /// This is useful for bytes arriving in chunks, which may split a UTF-8 sequence.
+    /// Whenever the whole value is valid in UTF-8 without an incomplete sequence at the end,
+    /// it becomes explicitly encoded in UTF-8 without validating the value again.
+    /// Otherwise it is not explicitly encoded in UTF-8 until such sequence gets completed.
+    pub fn push_bytes_streaming(&mut self, v: &[u8]) {
+        let tag = self.repr.validate();
+        let len = self.len();
+        self.repr.extend_from_slice(v);
+        let start = match tag {
+            Tag::KnownUtf8 | Tag::KnownValid => len,
+            Tag::KnownInvalid => return,
+            // the incomplete sequence starts with the last non-continuation byte
+            Tag::Incomplete => (len.saturating_sub(3)..len).rev()
+                                   .find(|&i| is_char_boundary(self.as_bytes(), i)).unwrap_or(0),
+            Tag::Unknown => unreachable!(),
+        };
+        let tag = match validate_from(self.as_bytes(), start) {
+            Tag::KnownValid => Tag::KnownUtf8,
+            tag => tag,
+        };
+        self.repr.set_tag(tag);
+    }
+
+    /// Appends a byte. The value is no longer explicitly encoded in UTF-8.
+    pub fn push_byte(&mut self, b: u8) {
+        self.push_bytes(&[b]);
+    }
+
+    /// Appends a `MaybeUtf8Slice`.
+    /// The value is no longer explicitly encoded in UTF-8 unless the slice is.
+    pub fn push_maybe_utf8(&mut self, s: MaybeUtf8Slice) {
+        match s.inner {
+            Slice::Utf8(s) => self.push_str(s),
+            Slice::Bytes(v) => self.push_bytes(v),
+        }
+    }
+
+    /// Appends a string slice, a `u8` slice or anything convertible to `MaybeUtf8Slice`
+    /// at once. The value is no longer explicitly encoded in UTF-8 unless the slice is.
+    pub fn extend_from_slice<'a, T: IntoMaybeUtf8<MaybeUtf8Slice<'a>>>(&mut self, v: T) {
+        self.push_maybe_utf8(v.into_maybe_utf8());
+    }
+
+    /// Moves all contents of `other` to the end of `self`, leaving `other` empty.
+    /// `self` is no longer explicitly encoded in UTF-8 unless `other` is.
+    /// `other` retains its capacity and whether it is explicitly encoded in UTF-8.
+    pub fn append(&mut self, other: &mut MaybeUtf8Buf) {
+        self.push_maybe_utf8(other.to_slice());
+        other.clear();
+    }
+
+    /// Shortens the `MaybeUtf8Buf` to given byte length.
+    /// It is no longer explicitly encoded in UTF-8 if the new length is not at a char boundary.
+    /// It has no effect if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len() { return; }
+        let tag = self.edited_tag(is_char_boundary(self.as_bytes(), len));
+        self.repr.truncate(len);
+        self.repr.set_tag(tag);
+    }
+
+    /// Removes all contents of the `MaybeUtf8Buf`.
+    /// This doesn't change whether the value is explicitly encoded in UTF-8.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Removes the last character if explicitly encoded in UTF-8, or the last byte otherwise.
+    /// It returns `None` if the `MaybeUtf8Buf` is empty.
+    pub fn pop(&mut self) -> Option<CharOrByte> {
+        let (ret, width) = match self.to_slice().inner {
+            Slice::Utf8(s) => { let c = s.chars().next_back()?; (CharOrByte::Char(c), c.len_utf8()) }
+            Slice::Bytes(v) => (CharOrByte::Byte(*v.last()?), 1),
+        };
+        self.truncate(self.len() - width);
+        Some(ret)
+    }
+
+    /// Inserts a string slice at given byte offset.
+    /// It is no longer explicitly encoded in UTF-8 if the offset is not at a char boundary.
+    ///
+    /// Panics if `idx` is greater than the current length.
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        assert!(idx <= self.len(), "insertion index {} is greater than the current length {}",
+                idx, self.len());
+        let tag = self.edited_tag(is_char_boundary(self.as_bytes(), idx));
+        self.repr.insert_slice(idx, s.as_bytes());
+        self.repr.set_tag(tag);
+    }
+
+    /// Inserts a character at given byte offset.
+    /// It is no longer explicitly encoded in UTF-8 if the offset is not at a char boundary.
+    ///
+    /// Panics if `idx` is greater than the current length.
+    pub fn insert(&mut self, idx: usize, c: char) {
+        self.insert_str(idx, c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Inserts a `u8` slice at given byte offset.
+    /// The value is no longer explicitly encoded in UTF-8.
+    ///
+    /// Panics if `idx` is greater than the current length.
+    pub fn insert_bytes(&mut self, idx: usize, v: &[u8]) {
+        assert!(idx <= self.len(), "insertion index {} is greater than the current length {}",
+                idx, self.len());
+        self.repr.insert_slice(idx, v);
+        self.repr.set_tag(Tag::Unknown);
+    }
+
+    /// Inserts a byte at given byte offset.
+    /// The value is no longer explicitly encoded in UTF-8.
+    ///
+    /// Panics if `idx` is greater than the current length.
+    pub fn insert_byte(&mut self, idx: usize, b: u8) {
+        self.insert_bytes(idx, &[b]);
+    }
+
+    /// Removes a character at given byte offset if explicitly encoded in UTF-8,
+    /// or a byte otherwise. If the offset is not at a char boundary,
+    /// a byte is removed and the value is no longer explicitly encoded in UTF-8.
+    ///
+    /// Panics if `idx` is not less than the current length.
+    pub fn remove(&mut self, idx: usize) -> CharOrByte {
+        assert!(idx < self.len(), "removal index {} is out of bounds of length {}",
+                idx, self.len());
+        let (ret, width) = match self.to_slice().inner {
+            Slice::Utf8(s) if s.is_char_boundary(idx) => {
+                let c = s[idx..].chars().next().unwrap();
+                (CharOrByte::Char(c), c.len_utf8())
+            }
+            Slice::Utf8(s) => (CharOrByte::Byte(s.as_bytes()[idx]), 1),
+            Slice::Bytes(v) => (CharOrByte::Byte(v[idx]), 1),
+        };
+        self.remove_range(idx, idx + width);
+        ret
+    }
+
+    /// Removes given byte range and returns an iterator over the removed characters
+    /// (if explicitly encoded in UTF-8) or bytes (otherwise).
+    /// The range is removed even when the iterator is not consumed.
+    /// Both the removed and remaining values are no longer explicitly encoded in UTF-8
+    /// if either end of the range is not at a char boundary.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain {
+        let (begin, end) = resolve_range(range, self.len());
+        let tag = match self.repr.tag() {
+            Tag::KnownUtf8 if is_char_boundary(self.as_bytes(), begin) &&
+                              is_char_boundary(self.as_bytes(), end) => Tag::KnownUtf8,
+            _ => Tag::Unknown,
+        };
+        let drained = MaybeUtf8Buf { repr: Repr::from_slice(&self.as_bytes()[begin..end], tag) };
+        self.remove_range(begin, end);
+        Drain { front: 0, back: drained.len(), buf: drained }
+    }
+
+    /// Replaces given byte range with a string slice, a `u8` slice
+    /// or anything convertible to `MaybeUtf8Slice`.
+    /// It is no longer explicitly encoded in UTF-8 if the replacement is not,
+    /// or if either end of the range is not at a char boundary.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn replace_range<'a, R, T>(&mut self, range: R, replacement: T)
+            where R: RangeBounds<usize>, T: IntoMaybeUtf8<MaybeUtf8Slice<'a>> {
+        let (begin, end) = resolve_range(range, self.len());
+        let replacement = replacement.into_maybe_utf8();
+        let tag = match replacement.inner {
+            Slice::Utf8(_) => self.edited_tag(is_char_boundary(self.as_bytes(), begin) &&
+                                              is_char_boundary(self.as_bytes(), end)),
+            Slice::Bytes(_) => Tag::Unknown,
+        };
+        self.repr.remove_range(begin, end);
+        self.repr.insert_slice(begin, replacement.as_bytes());
+        self.repr.set_tag(tag);
+    }
+
+    /// Retains only characters (if explicitly encoded in UTF-8) or bytes (otherwise)
+    /// for which given predicate returns `true`.
+    /// This doesn't change whether the value is explicitly encoded in UTF-8.
+    pub fn retain<F>(&mut self, mut f: F) where F: FnMut(CharOrByte) -> bool {
+        let tag = self.repr.tag();
+        // the bytes are temporarily invalid during the loop, which may panic
+        self.repr.set_tag(Tag::Unknown);
+        let (bytes, _) = self.repr.as_bytes_mut_and_tag();
+        let (mut read, mut write) = (0, 0);
+        while read < bytes.len() {
+            let (unit, width) = if tag == Tag::KnownUtf8 {
+                let c = unsafe { str::from_utf8_unchecked(&bytes[read..]) }.chars().next().unwrap();
+                (CharOrByte::Char(c), c.len_utf8())
+            } else {
+                (CharOrByte::Byte(bytes[read]), 1)
+            };
+            if f(unit) {
+                bytes.copy_within(read..read + width, write);
+                write += width;
+            }
+            read += width;
+        }
+        let removed = write < read;
+        self.repr.truncate(write);
+        self.repr.set_tag(if tag == Tag::KnownUtf8 || !removed { tag } else { Tag::Unknown });
+    }
+
+    /// Returns a byte length of the `MaybeUtf8Buf` value.
+    pub fn len(&self) -> usize {
+        self.repr.as_bytes().len()
+    }
+
+    /// Returns `true` if the `MaybeUtf8Buf` value has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the `MaybeUtf8Buf` value consists of ASCII bytes only.
+    /// Such a value is valid in UTF-8 and many other encodings.
+    pub fn is_ascii(&self) -> bool {
+        self.as_bytes().is_ascii()
+    }
+
+    /// Returns `true` if the `MaybeUtf8Buf` value is valid in UTF-8,
+    /// whether or not it is explicitly encoded in UTF-8.
+    /// The result is cached so that subsequent calls are cheap.
+    pub fn is_utf8(&self) -> bool {
+        matches!(self.repr.validate(), Tag::KnownUtf8 | Tag::KnownValid)
+    }
+
+    // removes given byte range, keeping the tag only when both ends are at char boundaries.
+    fn remove_range(&mut self, begin: usize, end: usize) {
+        let tag = self.edited_tag(is_char_boundary(self.as_bytes(), begin) &&
+                                  is_char_boundary(self.as_bytes(), end));
+        self.repr.remove_range(begin, end);
+        self.repr.set_tag(tag);
+    }
+
+    // returns the tag after an edit, which can be retained only if the edit is at char boundaries.
+    fn edited_tag(&self, at_char_boundaries: bool) -> Tag {
+        match self.repr.tag() {
+            t @ (Tag::KnownUtf8 | Tag::KnownValid) if at_char_boundaries => t,
+            _ => Tag::Unknown,
+        }
+    }
+}
+
+/// An iterator over characters or bytes removed by `MaybeUtf8Buf::drain`.
+pub struct Drain { buf: MaybeUtf8Buf, front: usize, back: usize }
+
+impl Drain {
+    /// Returns a `MaybeUtf8Slice` of the remaining characters or bytes.
+    pub fn to_slice(&self) -> MaybeUtf8Slice<'_> {
+        match self.buf.to_slice().inner {
+            Slice::Utf8(s) => MaybeUtf8Slice::from_str(&s[self.front..self.back]),
+            Slice::Bytes(v) => MaybeUtf8Slice::from_bytes(&v[self.front..self.back]),
+        }
+    }
+}
+
+impl Iterator for Drain {
+    type Item = CharOrByte;
+
+    fn next(&mut self) -> Option<CharOrByte> {
+        let (ret, width) = match self.to_slice().inner {
+            Slice::Utf8(s) => { let c = s.chars().next()?; (CharOrByte::Char(c), c.len_utf8()) }
+            Slice::Bytes(v) => (CharOrByte::Byte(*v.first()?), 1),
+        };
+        self.front += width;
+        Some(ret)
+    }
+}
+
+impl DoubleEndedIterator for Drain {
+    fn next_back(&mut self) -> Option<CharOrByte> {
+        let (ret, width) = match self.to_slice().inner {
+            Slice::Utf8(s) => { let c = s.chars().next_back()?; (CharOrByte::Char(c), c.len_utf8()) }
+            Slice::Bytes(v) => (CharOrByte::Byte(*v.last()?), 1),
+        };
+        self.back -= width;
+        Some(ret)
+    }
+}
+
+/// An error returned when converting a `MaybeUtf8Buf` into a `String` fails.
+/// This is to `MaybeUtf8Buf` what `std::string::FromUtf8Error` is to `Vec<u8>`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FromMaybeUtf8Error { buf: MaybeUtf8Buf, error: str::Utf8Error }
+
+impl FromMaybeUtf8Error {
+    /// Returns a `Utf8Error` describing the first invalid UTF-8 sequence.
+    pub fn utf8_error(&self) -> str::Utf8Error {
+        self.error
+    }
+
+    /// Returns a slice of the original bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buf.as_bytes()
+    }
+
+    /// Returns the original `MaybeUtf8Buf` back.
+    pub fn into_maybe_utf8(self) -> MaybeUtf8Buf {
+        self.buf
+    }
+}
+
+impl fmt::Display for FromMaybeUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl core::error::Error for FromMaybeUtf8Error {
+}
+
+impl Repr {
+    // makes a copy of given bytes, which is stored inline if possible.
+    fn from_slice(v: &[u8], tag: Tag) -> Repr {
+        if v.len() > INLINE_CAP { return Repr::Heap(v.to_vec(), TagCell::new(tag)); }
+        let mut buf = [0; INLINE_CAP];
+        buf[..v.len()].copy_from_slice(v);
+        Repr::Inline(TagCell::new(tag), v.len() as u8, buf)
+    }
+
+    fn tag(&self) -> Tag {
+        match *self {
+            Repr::Heap(_, ref tag) | Repr::Inline(ref tag, _, _) => tag.get(),
+            Repr::Hinted(ref h) => h.0.tag(),
+        }
+    }
+
+    fn set_tag(&self, t: Tag) {
+        match *self {
+            Repr::Heap(_, ref tag) | Repr::Inline(ref tag, _, _) => tag.set(t),
+            Repr::Hinted(ref h) => h.0.set_tag(t),
+        }
+    }
+
+    // returns the tag, validating the bytes and caching the result if not yet done.
+    fn validate(&self) -> Tag {
+        match *self {
+            Repr::Heap(_, ref tag) | Repr::Inline(ref tag, _, _) => {
+                let mut t = tag.get();
+                if t == Tag::Unknown {
+                    t = validate_from(self.as_bytes(), 0);
+                    tag.set(t);
+                }
+                t
+            }
+            Repr::Hinted(ref h) => h.0.validate(),
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match *self {
+            Repr::Heap(ref v, _) => v,
+            Repr::Inline(_, len, ref buf) => &buf[..len as usize],
+            Repr::Hinted(ref h) => h.0.as_bytes(),
+        }
+    }
+
+    fn as_bytes_mut_and_tag(&mut self) -> (&mut [u8], &TagCell) {
+        match *self {
+            Repr::Heap(ref mut v, ref tag) => (v, tag),
+            Repr::Inline(ref tag, len, ref mut buf) => (&mut buf[..len as usize], tag),
+            Repr::Hinted(ref mut h) => h.0.as_bytes_mut_and_tag(),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        match *self {
+            Repr::Heap(ref v, _) => v.capacity(),
+            Repr::Inline(..) => INLINE_CAP,
+            Repr::Hinted(ref h) => h.0.capacity(),
+        }
+    }
+
+    // reserves the capacity, moving the inline value to the heap if it no longer fits.
+    fn reserve(&mut self, additional: usize) {
+        let heap = match *self {
+            Repr::Heap(ref mut v, _) => return v.reserve(additional),
+            Repr::Inline(_, len, _) if len as usize + additional <= INLINE_CAP => return,
+            Repr::Inline(ref tag, len, ref buf) => {
+                let mut v = Vec::with_capacity(len as usize + additional);
+                v.extend_from_slice(&buf[..len as usize]);
+                Repr::Heap(v, TagCell::new(tag.get()))
+            }
+            Repr::Hinted(ref mut h) => return h.0.reserve(additional),
+        };
+        *self = heap;
+    }
+
+    // drops any excess capacity, moving the short value inline.
+    fn shrink_to_fit(&mut self) {
+        let inline = match *self {
+            Repr::Heap(ref v, ref tag) if v.len() <= INLINE_CAP => Repr::from_slice(v, tag.get()),
+            Repr::Heap(ref mut v, _) => return v.shrink_to_fit(),
+            Repr::Inline(..) => return,
+            Repr::Hinted(ref mut h) => return h.0.shrink_to_fit(),
+        };
+        *self = inline;
+    }
+
+    // returns a mutable reference to the `Vec`, moving the inline value to the heap.
+    fn as_mut_vec(&mut self) -> &mut Vec<u8> {
+        if let Repr::Inline(ref tag, len, ref buf) = *self {
+            *self = Repr::Heap(buf[..len as usize].to_vec(), TagCell::new(tag.get()));
+        }
+        match *self {
+            Repr::Heap(ref mut v, _) => v,
+            Repr::Inline(..) => unreachable!(),
+            Repr::Hinted(ref mut h) => h.0.as_mut_vec(),
+        }
+    }
+
+    // inserts given bytes at given byte offset, which should not exceed the current length.
+    fn insert_slice(&mut self, idx: usize, v: &[u8]) {
+        self.extend_from_slice(v);
+        self.as_bytes_mut_and_tag().0[idx..].rotate_right(v.len());
+    }
+
+    // removes given byte range, which should be in bounds.
+    fn remove_range(&mut self, begin: usize, end: usize) {
+        let len = self.as_bytes().len();
+        self.as_bytes_mut_and_tag().0[begin..].rotate_left(end - begin);
+        self.truncate(len - (end - begin));
+    }
+
+    // shortens the value to given byte length, which should not exceed the current length.
+    fn truncate(&mut self, newlen: usize) {
+        match *self {
+            Repr::Heap(ref mut v, _) => v.truncate(newlen),
+            Repr::Inline(_, ref mut len, _) => *len = newlen as u8,
+            Repr::Hinted(ref mut h) => h.0.truncate(newlen),
+        }
+    }
+
+    // appends given bytes, moving the inline value to the heap if it no longer fits.
+    fn extend_from_slice(&mut self, v: &[u8]) {
+        self.reserve(v.len());
+        match *self {
+            Repr::Heap(ref mut vec, _) => vec.extend_from_slice(v),
+            Repr::Inline(_, ref mut len, ref mut buf) => {
+                buf[*len as usize..*len as usize + v.len()].copy_from_slice(v);
+                *len += v.len() as u8;
+            }
+            Repr::Hinted(ref mut h) => h.0.extend_from_slice(v),
+        }
+    }
+
+    fn into_vec(self) -> Vec<u8> {
+        match self {
+            Repr::Heap(v, _) => v,
+            Repr::Inline(_, len, buf) => buf[..len as usize].to_vec(),
+            Repr::Hinted(h) => h.0.into_vec(),
+        }
+    }
+}
+
+impl Clone for Repr {
+    fn clone(&self) -> Repr {
+        match *self {
+            // this may store a short value inline even when the original value is not
+            Repr::Heap(..) | Repr::Inline(..) => Repr::from_slice(self.as_bytes(), self.tag()),
+            Repr::Hinted(ref h) => Repr::Hinted(h.clone()),
+        }
+    }
+}
+
+impl TagCell {
+    fn new(tag: Tag) -> TagCell {
+        TagCell(AtomicU8::new(tag as u8))
+    }
+
+    fn get(&self) -> Tag {
+        match self.0.load(atomic::Ordering::Relaxed) {
+            0 => Tag::KnownUtf8,
+            1 => Tag::KnownValid,
+            2 => Tag::KnownInvalid,
+            3 => Tag::Incomplete,
+            _ => Tag::Unknown,
+        }
+    }
+
+    fn set(&self, tag: Tag) {
+        self.0.store(tag as u8, atomic::Ordering::Relaxed);
+    }
+}
+
+impl MaybeUtf8 {
+    /// Creates a `MaybeUtf8` reference from a string slice.
+    pub fn from_str(s: &str) -> &MaybeUtf8 {
+        MaybeUtf8::from_bytes(s.as_bytes())
+    }
+
+    /// Creates a `MaybeUtf8` reference from a `u8` slice.
+    pub fn from_bytes(v: &[u8]) -> &MaybeUtf8 {
+        // `MaybeUtf8` is a transparent wrapper over `[u8]`
+        unsafe { &*(v as *const [u8] as *const MaybeUtf8) }
+    }
+
+    /// Returns a slice of underlying bytes. It might or might not be encoded in UTF-8.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.inner
+    }
+
+    /// Returns a string slice encoded in UTF-8 if possible.
+    /// It returns `None` if the underlying bytes are not encoded in UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.inner).ok()
+    }
+
+    /// Returns a string slice encoded in UTF-8 if possible.
+    /// Unlike `as_str`, it returns a `Utf8Error` describing the first invalid UTF-8 sequence
+    /// if the underlying bytes are not encoded in UTF-8.
+    pub fn try_as_str(&self) -> Result<&str, str::Utf8Error> {
+        str::from_utf8(&self.inner)
+    }
+
+    /// Returns a `Cow` string which represents the current `MaybeUtf8`.
+    /// It calls given `to_cow` function to get a `Cow` string out of the bytes
+    /// only when the bytes are not valid in UTF-8.
+    /// `to_cow` function itself may return a `String` or `&str` compatible to `Cow` string.
+    pub fn map_as_cow<'a, F, T>(&'a self, mut to_cow: F) -> Cow<'a, str>
+            where F: FnMut(&'a [u8]) -> T, T: Into<Cow<'a, str>> {
+        match str::from_utf8(&self.inner) {
+            Ok(s) => Cow::Borrowed(s),
+            Err(_) => to_cow(&self.inner).into(),
+        }
+    }
+
+    /// Returns a `Cow` string which represents the current `MaybeUtf8`.
+    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn as_cow_lossy<'a>(&'a self) -> Cow<'a, str> {
+        String::from_utf8_lossy(&self.inner)
+    }
+
+    /// Same to `as_cow_lossy` but replaces invalid UTF-8 sequences with given string,
+    /// which may be empty to drop them.
+    pub fn as_cow_lossy_with<'a>(&'a self, replacement: &str) -> Cow<'a, str> {
+        from_utf8_lossy_with(&self.inner, replacement)
+    }
+
+    /// Returns a `MaybeUtf8Slice` borrowed from this `MaybeUtf8`.
+    /// The slice is encoded in UTF-8 only when the underlying bytes are valid in UTF-8.
+    pub fn to_slice<'a>(&'a self) -> MaybeUtf8Slice<'a> {
+        match str::from_utf8(&self.inner) {
+            Ok(s) => MaybeUtf8Slice::from_str(s),
+            Err(_) => MaybeUtf8Slice::from_bytes(&self.inner),
+        }
+    }
+
+    /// Returns a new `MaybeUtf8Buf` from the current `MaybeUtf8`.
+    /// The buffer is encoded in UTF-8 only when the underlying bytes are valid in UTF-8.
+    pub fn to_owned(&self) -> MaybeUtf8Buf {
+        self.to_slice().to_owned()
+    }
+
+    /// Returns a byte length of the `MaybeUtf8` value.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the `MaybeUtf8` value has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the underlying bytes.
+    pub fn bytes(&self) -> iter::Copied<slice::Iter<'_, u8>> {
+        self.inner.iter().copied()
+    }
+
+    /// Returns an iterator over characters decoded from the underlying bytes.
+    /// Each invalid UTF-8 sequence is decoded to U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn chars_lossy(&self) -> CharsLossy<'_> {
+        CharsLossy::new(&self.inner)
+    }
+
+    /// Returns an iterator over characters decoded from the underlying bytes,
+    /// along with their start and end byte offsets.
+    /// Each invalid UTF-8 sequence is decoded to U+FFFD, as like `String::from_utf8_lossy`,
+    /// and the offsets cover the whole invalid sequence.
+    pub fn char_indices_lossy(&self) -> CharIndicesLossy<'_> {
+        CharIndicesLossy::new(&self.inner)
+    }
+
+    /// Returns the number of characters decoded from the underlying bytes,
+    /// where each invalid UTF-8 sequence counts as a single U+FFFD.
+    pub fn char_count_lossy(&self) -> usize {
+        char_count_lossy(&self.inner)
+    }
+
+    /// Returns the number of invalid UTF-8 sequences in the underlying bytes,
+    /// each of which would be decoded to U+FFFD by `String::from_utf8_lossy`.
+    pub fn count_invalid_sequences(&self) -> usize {
+        count_invalid_sequences(&self.inner)
+    }
+
+    /// Returns the byte offset of the first invalid UTF-8 sequence,
+    /// or the byte length if the underlying bytes are valid in UTF-8.
+    /// An incomplete sequence at the end is also considered invalid.
+    pub fn valid_up_to(&self) -> usize {
+        match str::from_utf8(&self.inner) {
+            Ok(s) => s.len(),
+            Err(e) => e.valid_up_to(),
+        }
+    }
+
+    /// Returns the longest prefix of the underlying bytes which is valid in UTF-8.
+    pub fn utf8_prefix(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(&self.inner[..self.valid_up_to()]) }
+    }
+
+    /// Divides the underlying bytes into the longest prefix valid in UTF-8
+    /// and the remainder starting with the first invalid UTF-8 sequence (if any).
+    pub fn split_at_first_invalid(&self) -> (&str, &[u8]) {
+        let (valid, rest) = self.inner.split_at(self.valid_up_to());
+        (unsafe { str::from_utf8_unchecked(valid) }, rest)
+    }
+
+    /// Returns `true` if given pattern matches a prefix of the underlying bytes.
+    pub fn starts_with<P: BytePattern>(&self, mut pat: P) -> bool {
+        pat.prefix_len_in(&self.inner).is_some()
+    }
+
+    /// Returns `true` if given pattern matches a suffix of the underlying bytes.
+    pub fn ends_with<P: BytePattern>(&self, mut pat: P) -> bool {
+        pat.suffix_len_in(&self.inner).is_some()
+    }
+
+    /// Returns `true` if given pattern matches a part of the underlying bytes.
+    pub fn contains<P: BytePattern>(&self, pat: P) -> bool {
+        self.find(pat).is_some()
+    }
+
+    /// Returns the byte offset of the first match of given pattern,
+    /// or `None` if the pattern doesn't match.
+    pub fn find<P: BytePattern>(&self, mut pat: P) -> Option<usize> {
+        pat.find_in(&self.inner).map(|(i, _)| i)
+    }
+
+    /// Returns the byte offset of the last match of given pattern,
+    /// or `None` if the pattern doesn't match.
+    pub fn rfind<P: BytePattern>(&self, mut pat: P) -> Option<usize> {
+        pat.rfind_in(&self.inner).map(|(i, _)| i)
+    }
+}
+
+impl<'a> MaybeUtf8Slice<'a> {
+    /// Creates a new empty `MaybeUtf8Slice` value (which is, naturally, encoded in UTF-8).
+    pub fn new() -> MaybeUtf8Slice<'static> {
+        MaybeUtf8Slice { inner: Slice::Utf8("") }
+    }
+
+    /// Creates a `MaybeUtf8Slice` reference from a string slice.
+    pub fn from_str(s: &'a str) -> MaybeUtf8Slice<'a> {
+        MaybeUtf8Slice { inner: Slice::Utf8(s) }
+    }
+
+    /// Creates a `MaybeUtf8Slice` reference from a `u8` slice.
+    pub fn from_bytes(v: &'a [u8]) -> MaybeUtf8Slice<'a> {
+        MaybeUtf8Slice { inner: Slice::Bytes(v) }
+    }
+
+    /// Returns a slice of underlying bytes. It might or might not be encoded in UTF-8.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match self.inner {
+            Slice::Utf8(s) => s.as_bytes(),
+            Slice::Bytes(v) => v,
+        }
+    }
+
+    /// Returns a string slice encoded in UTF-8 if possible.
+    /// It returns `None` if the underlying bytes are not encoded in UTF-8.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self.inner {
+            Slice::Utf8(s) => Some(s),
+            Slice::Bytes(v) => str::from_utf8(v).ok(),
+        }
+    }
+
+    /// Returns a string slice encoded in UTF-8 if possible.
+    /// Unlike `as_str`, it returns a `Utf8Error` describing the first invalid UTF-8 sequence
+    /// if the underlying bytes are not encoded in UTF-8.
+    pub fn try_as_str(&self) -> Result<&'a str, str::Utf8Error> {
+        match self.inner {
+            Slice::Utf8(s) => Ok(s),
+            Slice::Bytes(v) => str::from_utf8(v),
+        }
+    }
+
+    /// Returns a `Cow` string which represents the current `MaybeUtf8Slice`.
+    /// It may call given `to_cow` function to get a `Cow` string out of the bytes.
+    /// `to_cow` function itself may return a `String` or `&str` compatible to `Cow` string.
+    pub fn map_as_cow<F, T>(&self, mut to_cow: F) -> Cow<'a, str>
+            where F: FnMut(&'a [u8]) -> T, T: Into<Cow<'a, str>> {
+        match self.inner {
+            Slice::Utf8(s) => Cow::Borrowed(s),
+            Slice::Bytes(v) => to_cow(v).into(),
+        }
+    }
+
+
+    // there is no `as_cow`; if we can convert bytes to a str, we don't need `Cow` string at all.
+
+    /// Returns a `Cow` string which represents the current `MaybeUtf8Slice`.
+    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn as_cow_lossy(&self) -> Cow<'a, str> {
+        self.map_as_cow(String::from_utf8_lossy)
+    }
+
+    /// Same to `as_cow_lossy` but replaces invalid UTF-8 sequences with given string,
+    /// which may be empty to drop them.
+    pub fn as_cow_lossy_with(&self, replacement: &str) -> Cow<'a, str> {
+        self.map_as_cow(|v| from_utf8_lossy_with(v, replacement))
+    }
+
+    /// Returns a `MaybeUtf8Slice` for given byte range, or `None` if it is out of bounds.
+    /// It is no longer explicitly encoded in UTF-8
+    /// if either end of the range is not at a char boundary.
+    pub fn get<R: RangeBounds<usize>>(&self, range: R) -> Option<MaybeUtf8Slice<'a>> {
+        let (begin, end) = checked_range(range, self.len())?;
+        Some(match self.inner {
+            Slice::Utf8(s) if s.is_char_boundary(begin) && s.is_char_boundary(end) =>
+                MaybeUtf8Slice::from_str(&s[begin..end]),
+            _ => MaybeUtf8Slice::from_bytes(&self.as_bytes()[begin..end]),
+        })
+    }
+
+    /// Returns `true` if given byte offset is at a char boundary.
+    /// Every offset up to the length is considered at a char boundary
+    /// if the value is not explicitly encoded in UTF-8.
+    pub fn is_char_boundary(&self, idx: usize) -> bool {
+        match self.inner {
+            Slice::Utf8(s) => s.is_char_boundary(idx),
+            Slice::Bytes(v) => idx <= v.len(),
+        }
+    }
+
+    /// Returns the closest char boundary at or before given byte offset.
+    /// The length is returned if the offset is greater than the length.
+    pub fn floor_char_boundary(&self, idx: usize) -> usize {
+        let mut idx = idx.min(self.len());
+        while !self.is_char_boundary(idx) { idx -= 1; }
+        idx
+    }
+
+    /// Returns the closest char boundary at or after given byte offset.
+    /// The length is returned if the offset is greater than the length.
+    pub fn ceil_char_boundary(&self, idx: usize) -> usize {
+        let mut idx = idx.min(self.len());
+        while !self.is_char_boundary(idx) { idx += 1; }
+        idx
+    }
+
+    /// Divides the `MaybeUtf8Slice` into two at given byte offset.
+    /// They are no longer explicitly encoded in UTF-8 if the offset is not at a char boundary.
+    ///
+    /// Panics if `mid` is greater than the current length.
+    pub fn split_at(&self, mid: usize) -> (MaybeUtf8Slice<'a>, MaybeUtf8Slice<'a>) {
+        match self.inner {
+            Slice::Utf8(s) if s.is_char_boundary(mid) => {
+                let (a, b) = s.split_at(mid);
+                (MaybeUtf8Slice::from_str(a), MaybeUtf8Slice::from_str(b))
+            }
+            _ => {
+                let (a, b) = self.as_bytes().split_at(mid);
+                (MaybeUtf8Slice::from_bytes(a), MaybeUtf8Slice::from_bytes(b))
+            }
+        }
+    }
+
+    /// Returns a new `MaybeUtf8Buf` from the current `MaybeUtf8Slice`.
+    pub fn to_owned(&self) -> MaybeUtf8Buf {
+        let tag = match self.inner { Slice::Utf8(_) => Tag::KnownUtf8, Slice::Bytes(_) => Tag::Unknown };
+        MaybeUtf8Buf { repr: Repr::from_slice(self.as_bytes(), tag) }
+    }
+
+    /// Returns a byte length of the `MaybeUtf8Slice` value.
+    pub fn len(&self) -> usize {
+        match self.inner {
+            Slice::Utf8(s) => s.len(),
+            Slice::Bytes(v) => v.len(),
+        }
+    }
+
+    /// Returns `true` if the `MaybeUtf8Slice` value has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the underlying bytes.
+    pub fn bytes(&self) -> iter::Copied<slice::Iter<'a, u8>> {
+        self.as_bytes().iter().copied()
+    }
+
+    /// Returns an iterator over characters decoded from the underlying bytes.
+    /// Each invalid UTF-8 sequence is decoded to U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn chars_lossy(&self) -> CharsLossy<'a> {
+        CharsLossy::new(self.as_bytes())
+    }
+
+    /// Returns an iterator over characters decoded from the underlying bytes,
+    /// along with their start and end byte offsets.
+    /// Each invalid UTF-8 sequence is decoded to U+FFFD, as like `String::from_utf8_lossy`,
+    /// and the offsets cover the whole invalid sequence.
+    pub fn char_indices_lossy(&self) -> CharIndicesLossy<'a> {
+        CharIndicesLossy::new(self.as_bytes())
+    }
+
+    /// Returns the number of characters decoded from the underlying bytes,
+    /// where each invalid UTF-8 sequence counts as a single U+FFFD.
+    pub fn char_count_lossy(&self) -> usize {
+        match self.inner {
+            Slice::Utf8(s) => s.chars().count(),
+            Slice::Bytes(v) => char_count_lossy(v),
+        }
+    }
+
+    /// Returns the number of invalid UTF-8 sequences in the underlying bytes,
+    /// each of which would be decoded to U+FFFD by `String::from_utf8_lossy`.
+    pub fn count_invalid_sequences(&self) -> usize {
+        match self.inner {
+            Slice::Utf8(_) => 0,
+            Slice::Bytes(v) => count_invalid_sequences(v),
+        }
+    }
+
+    /// Returns an iterator over extended grapheme clusters as `MaybeUtf8Slice`s.
+    /// Each invalid UTF-8 sequence is considered a single cluster.
+    ///
+    /// This is only available with the `unicode-segmentation` feature.
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn graphemes_lossy(&self) -> GraphemesLossy<'a> {
+        GraphemesLossy::new(*self)
+    }
+
+    /// Returns the number of terminal columns occupied by the lossy rendering
+    /// (as like `as_cow_lossy`), where each U+FFFD occupies a single column.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn display_width(&self) -> usize {
+        self.as_bytes().utf8_chunks().map(|chunk| {
+            let replacement = if chunk.invalid().is_empty() { 0 } else { 1 };
+            UnicodeWidthStr::width(chunk.valid()) + replacement
+        }).sum()
+    }
+
+    /// Returns the longest prefix of which lossy rendering occupies at most `cols` columns.
+    /// It is no longer explicitly encoded in UTF-8 if it doesn't end at a char boundary.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn truncate_to_width(&self, cols: usize) -> MaybeUtf8Slice<'a> {
+        // `display_width` adds up the widths of `utf8_chunks`, so whole chunks are summed
+        // and only the chunk crossing `cols` is searched for the end
+        let mut width = 0;
+        let mut end = 0;
+        for chunk in self.as_bytes().utf8_chunks() {
+            let valid = chunk.valid();
+            let valid_width = UnicodeWidthStr::width(valid);
+            if width + valid_width > cols {
+                let (fit, _) = partition_char_boundaries(valid, |i| {
+                    width + UnicodeWidthStr::width(&valid[..i]) <= cols
+                });
+                return self.get(..end + fit).unwrap();
+            }
+            width += valid_width;
+            end += valid.len();
+            if !chunk.invalid().is_empty() {
+                if width + 1 > cols { break; }
+                width += 1;
+                end += chunk.invalid().len();
+            }
+        }
+        self.get(..end).unwrap()
+    }
+
+    /// Returns the longest suffix of which lossy rendering occupies at most `cols` columns.
+    /// It is no longer explicitly encoded in UTF-8 if it doesn't start at a char boundary.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn truncate_start_to_width(&self, cols: usize) -> MaybeUtf8Slice<'a> {
+        // as like `truncate_to_width`, but the remaining width is tracked from the start
+        let mut width = self.display_width();
+        let mut start = 0;
+        for chunk in self.as_bytes().utf8_chunks() {
+            if width <= cols { break; }
+            let valid = chunk.valid();
+            let valid_width = UnicodeWidthStr::width(valid);
+            if width - valid_width <= cols {
+                let rest = width - valid_width;
+                let (_, fit) = partition_char_boundaries(valid, |i| {
+                    UnicodeWidthStr::width(&valid[i..]) + rest > cols
+                });
+                return self.get(start + fit..).unwrap();
+            }
+            width -= valid_width;
+            start += valid.len();
+            if !chunk.invalid().is_empty() {
+                width -= 1;
+                start += chunk.invalid().len();
+            }
+        }
+        self.get(start..).unwrap()
+    }
+
+    /// Returns the value truncated to at most `max_cols` columns, where the end is replaced
+    /// with an ellipsis (`…`) if it doesn't fit. It is borrowed from the original value if fits.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn ellipsize(&self, max_cols: usize) -> MaybeUtf8Cow<'a> {
+        if self.display_width() <= max_cols {
+            return MaybeUtf8Cow::Borrowed(*self);
+        }
+        let mut buf = MaybeUtf8Buf::new();
+        if max_cols > 0 {
+            buf.push_maybe_utf8(self.truncate_to_width(max_cols - 1));
+            buf.push_str("\u{2026}");
+        }
+        MaybeUtf8Cow::Owned(buf)
+    }
+
+    /// Returns the value truncated to at most `max_cols` columns, where the middle is replaced
+    /// with an ellipsis (`…`) if it doesn't fit. It is borrowed from the original value if fits.
+    ///
+    /// This is only available with the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    pub fn ellipsize_middle(&self, max_cols: usize) -> MaybeUtf8Cow<'a> {
+        if self.display_width() <= max_cols {
+            return MaybeUtf8Cow::Borrowed(*self);
+        }
+        let mut buf = MaybeUtf8Buf::new();
+        if max_cols > 0 {
+            // the head gets one more column than the tail if they can't be even
+            let tail_cols = (max_cols - 1) / 2;
+            buf.push_maybe_utf8(self.truncate_to_width(max_cols - 1 - tail_cols));
+            buf.push_str("\u{2026}");
+            buf.push_maybe_utf8(self.truncate_start_to_width(tail_cols));
+        }
+        MaybeUtf8Cow::Owned(buf)
+    }
+
+    /// Returns `true` if two values are equal when the case is ignored.
+    /// Unicode simple case folding applies to the parts valid in UTF-8,
+    /// and the remaining bytes are compared as they are.
+    ///
+    /// This is only available with the `unicode-case-mapping` feature.
+    #[cfg(feature = "unicode-case-mapping")]
+    pub fn eq_ignore_case<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, other: T) -> bool {
+        self.cmp_ignore_case(other) == Ordering::Equal
+    }
+
+    /// Compares two values when the case is ignored.
+    /// Unicode simple case folding applies to the parts valid in UTF-8,
+    /// and the remaining bytes are compared as they are (and ordered after any characters).
+    ///
+    /// This is only available with the `unicode-case-mapping` feature.
+    #[cfg(feature = "unicode-case-mapping")]
+    pub fn cmp_ignore_case<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, other: T) -> Ordering {
+        case_folded(self.as_bytes()).cmp(case_folded(other.into_maybe_utf8().as_bytes()))
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with Unicode simple case folding applied
+    /// to the parts valid in UTF-8, which can be used as a case-insensitive key.
+    /// Invalid UTF-8 sequences are kept as they are.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    ///
+    /// This is only available with the `unicode-case-mapping` feature.
+    #[cfg(feature = "unicode-case-mapping")]
+    pub fn case_fold(&self) -> MaybeUtf8Buf {
+        match self.inner {
+            Slice::Utf8(s) => MaybeUtf8Buf::from_str(case_fold_str(s)),
+            Slice::Bytes(v) => MaybeUtf8Buf::from_bytes(map_utf8_chunks(v, case_fold_str)),
+        }
+    }
+
+    /// Same to `case_fold` but also normalizes the parts valid in UTF-8 to NFD
+    /// before and after the case folding, so that canonically equivalent values
+    /// result in the same key.
+    ///
+    /// This is only available with both the `unicode-case-mapping`
+    /// and `unicode-normalization` features.
+    #[cfg(all(feature = "unicode-case-mapping", feature = "unicode-normalization"))]
+    pub fn case_fold_normalized(&self) -> MaybeUtf8Buf {
+        let fold = |s: &str| case_fold_str(&s.nfd().collect::<String>()).nfd().collect();
+        match self.inner {
+            Slice::Utf8(s) => MaybeUtf8Buf::from_str(fold(s)),
+            Slice::Bytes(v) => MaybeUtf8Buf::from_bytes(map_utf8_chunks(v, fold)),
+        }
+    }
+
+    /// Compares two values in the natural order, where each run of ASCII digits
+    /// is compared by its numeric value (so that `file2` comes before `file10`).
+    /// The underlying bytes are compared as a last resort,
+    /// so only identical values are considered equal.
+    pub fn cmp_natural<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, other: T) -> Ordering {
+        cmp_natural(self.as_bytes(), other.into_maybe_utf8().as_bytes())
+    }
+
+    /// Returns a collation key for given collator (e.g. created from a locale
+    /// with `icu_collator::Collator::try_new`), which can be compared bytewise
+    /// to sort values in the order of the collator.
+    /// Each invalid UTF-8 sequence is collated as U+FFFD,
+    /// and the underlying bytes break ties so that distinct values never have the same key.
+    ///
+    /// This is only available with the `icu_collator` feature.
+    #[cfg(feature = "icu_collator")]
+    pub fn collation_key(&self, collator: &CollatorBorrowed) -> Vec<u8> {
+        let mut sort_key = Vec::new();
+        let Ok(()) = collator.write_sort_key_utf8_to(self.as_bytes(), &mut sort_key);
+        // the sort key is not guaranteed to be free of zero bytes,
+        // so 00 and 01 are escaped as 01 01 and 01 02 while preserving the order.
+        // the escaped sort key is then terminated by 00, so the key is ordered by it first.
+        let mut key = Vec::with_capacity(sort_key.len() + self.len() + 1);
+        for b in sort_key {
+            match b {
+                0 | 1 => key.extend_from_slice(&[1, b + 1]),
+                _ => key.push(b),
+            }
+        }
+        key.push(0);
+        key.extend_from_slice(self.as_bytes());
+        key
+    }
+
+    /// Returns `true` if given regex matches a part of the underlying bytes.
+    ///
+    /// This is only available with the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn is_match(&self, re: &Regex) -> bool {
+        re.is_match(self.as_bytes())
+    }
+
+    /// Returns an iterator over non-overlapping matches of given regex as `MaybeUtf8Slice`s
+    /// and their byte offsets.
+    ///
+    /// This is only available with the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn find_iter<'r>(&self, re: &'r Regex) -> RegexMatches<'r, 'a> {
+        RegexMatches::new(*self, re)
+    }
+
+    /// Returns capture groups of the first match of given regex,
+    /// or `None` if the regex doesn't match.
+    ///
+    /// This is only available with the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn captures(&self, re: &Regex) -> Option<RegexCaptures<'a>> {
+        RegexCaptures::new(*self, re)
+    }
+
+    /// Returns `true` if the value matches given glob pattern with the default options.
+    ///
+    /// `?` matches any single character, `*` matches any sequence of characters,
+    /// and `[...]` (or `[!...]` for the negation) matches any character in the class.
+    /// They never match `/` by default, while `**` as a whole path component
+    /// matches any number of components. Each invalid UTF-8 byte is treated as a separate
+    /// character, which is only matched by wildcards or the same byte in the pattern.
+    ///
+    /// This is only available with the `glob` feature.
+    #[cfg(feature = "glob")]
+    pub fn matches_glob<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, pattern: T) -> bool {
+        self.matches_glob_with(pattern, GlobOptions::new())
+    }
+
+    /// Returns `true` if the value matches given glob pattern with given options.
+    ///
+    /// This is only available with the `glob` feature.
+    #[cfg(feature = "glob")]
+    pub fn matches_glob_with<'b, T>(&self, pattern: T, options: GlobOptions) -> bool
+            where T: IntoMaybeUtf8<MaybeUtf8Slice<'b>> {
+        glob::matches_glob(pattern.into_maybe_utf8().as_bytes(), self.as_bytes(), options)
+    }
+
+    /// Returns the byte offset of the first invalid UTF-8 sequence,
+    /// or the byte length if the underlying bytes are valid in UTF-8.
+    /// An incomplete sequence at the end is also considered invalid.
+    pub fn valid_up_to(&self) -> usize {
+        match self.inner {
+            Slice::Utf8(s) => s.len(),
+            Slice::Bytes(v) => match str::from_utf8(v) {
+                Ok(s) => s.len(),
+                Err(e) => e.valid_up_to(),
+            },
+        }
+    }
+
+    /// Returns the longest prefix of the underlying bytes which is valid in UTF-8.
+    pub fn utf8_prefix(&self) -> &'a str {
+        unsafe { str::from_utf8_unchecked(&self.as_bytes()[..self.valid_up_to()]) }
+    }
+
+    /// Divides the underlying bytes into the longest prefix valid in UTF-8
+    /// and the remainder starting with the first invalid UTF-8 sequence (if any).
+    pub fn split_at_first_invalid(&self) -> (&'a str, &'a [u8]) {
+        let (valid, rest) = self.as_bytes().split_at(self.valid_up_to());
+        (unsafe { str::from_utf8_unchecked(valid) }, rest)
+    }
+
+    /// Returns `true` if the `MaybeUtf8Slice` value consists of ASCII bytes only.
+    /// Such a value is valid in UTF-8 and many other encodings.
+    pub fn is_ascii(&self) -> bool {
+        self.as_bytes().is_ascii()
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with every ASCII letter converted to the lower case.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    pub fn to_ascii_lowercase(&self) -> MaybeUtf8Buf {
+        let mut buf = self.to_owned();
+        buf.make_ascii_lowercase();
+        buf
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with every ASCII letter converted to the upper case.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    pub fn to_ascii_uppercase(&self) -> MaybeUtf8Buf {
+        let mut buf = self.to_owned();
+        buf.make_ascii_uppercase();
+        buf
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with every character converted to the lower case,
+    /// as like `str::to_lowercase`. Invalid UTF-8 sequences are kept as they are.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    pub fn to_lowercase_lossy(&self) -> MaybeUtf8Buf {
+        match self.inner {
+            Slice::Utf8(s) => MaybeUtf8Buf::from_str(s.to_lowercase()),
+            Slice::Bytes(v) => MaybeUtf8Buf::from_bytes(map_utf8_chunks(v, str::to_lowercase)),
+        }
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with every character converted to the upper case,
+    /// as like `str::to_uppercase`. Invalid UTF-8 sequences are kept as they are.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    pub fn to_uppercase_lossy(&self) -> MaybeUtf8Buf {
+        match self.inner {
+            Slice::Utf8(s) => MaybeUtf8Buf::from_str(s.to_uppercase()),
+            Slice::Bytes(v) => MaybeUtf8Buf::from_bytes(map_utf8_chunks(v, str::to_uppercase)),
+        }
+    }
+
+    /// Returns an ASCII string approximating the value, where the parts valid in UTF-8
+    /// are transliterated (e.g. `Æneid` to `AEneid`) and each invalid byte is escaped as `%XX`.
+    ///
+    /// This is only available with the `deunicode` feature.
+    #[cfg(feature = "deunicode")]
+    pub fn to_ascii_lossy_translit(&self) -> String {
+        use core::fmt::Write;
+
+        let mut ret = String::with_capacity(self.len());
+        for chunk in self.as_bytes().utf8_chunks() {
+            ret.push_str(&deunicode::deunicode(chunk.valid()));
+            for &b in chunk.invalid() {
+                let _ = write!(ret, "%{:02X}", b);
+            }
+        }
+        ret
+    }
+
+    /// Returns a JSON value, which is a string if explicitly encoded in UTF-8
+    /// and represented as given `JsonBytes` otherwise.
+    ///
+    /// This is only available with the `serde_json` feature.
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_value(&self, bytes: JsonBytes) -> Value {
+        json::to_json_value(*self, bytes)
+    }
+
+    /// Returns a string which is safe to print to the terminal.
+    /// Control characters (C0, DEL and C1) and bidirectional formatting characters and marks
+    /// are escaped as `\xNN` or `\u{NNNN}`, and so is each invalid byte.
+    /// Backslashes are escaped as `\\` so that the result is unambiguous.
+    /// It is borrowed from the original value if there is nothing to escape.
+    pub fn sanitize_for_terminal(&self) -> Cow<'a, str> {
+        use core::fmt::Write;
+
+        // backslashes are escaped as well, so that escapes can't be confused with the input
+        let needs_escape = |c: char| c == '\\' || is_unsafe_for_terminal(c);
+        if let Some(s) = self.as_str() {
+            if !s.contains(needs_escape) {
+                return Cow::Borrowed(s);
+            }
+        }
+        let mut ret = String::with_capacity(self.len());
+        for chunk in self.as_bytes().utf8_chunks() {
+            for c in chunk.valid().chars() {
+                if c == '\\' {
+                    ret.push_str("\\\\");
+                } else if !is_unsafe_for_terminal(c) {
+                    ret.push(c);
+                } else if c.is_ascii() {
+                    let _ = write!(ret, "\\x{:02x}", c as u32);
+                } else {
+                    let _ = write!(ret, "\\u{{{:x}}}", c as u32);
+                }
+            }
+            for &b in chunk.invalid() {
+                let _ = write!(ret, "\\x{:02x}", b);
+            }
+        }
+        Cow::Owned(ret)
+    }
+
+    /// Returns an iterator over characters of the value escaped as like `str::escape_default`,
+    /// or as like `[u8]::escape_ascii` if the value is not explicitly encoded in UTF-8.
+    pub fn escape_default(&self) -> EscapeDefault<'a> {
+        EscapeDefault::new(self.as_bytes(), matches!(self.inner, Slice::Utf8(_)))
+    }
+
+    /// Returns an iterator over characters of the value escaped as like `char::escape_debug`.
+    /// Each invalid byte is escaped as `\xNN`.
+    pub fn escape_debug(&self) -> EscapeDebug<'a> {
+        EscapeDebug::new(self.as_bytes())
+    }
+
+    /// Returns a displayable quoted JSON string of the value,
+    /// where invalid UTF-8 sequences are escaped according to given policy.
+    /// The result is always valid JSON, so it can be safely embedded to logs and such.
+    pub fn escape_json(&self, policy: JsonEscapePolicy) -> EscapeJson<'a> {
+        EscapeJson::new(self.as_bytes(), policy)
+    }
+
+    /// Returns a quoted JSON string of the value,
+    /// where invalid UTF-8 sequences are escaped according to given policy.
+    pub fn to_json_string_escaped(&self, policy: JsonEscapePolicy) -> String {
+        self.escape_json(policy).to_string()
+    }
+
+    /// Returns a displayable hex dump of the underlying bytes as like `hexdump -C`,
+    /// which is useful for investigating values not valid in UTF-8.
+    pub fn hexdump(&self) -> HexDump<'a> {
+        HexDump::new(self.as_bytes())
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with ANSI escape sequences (e.g. `ESC [ 3 1 m`) removed.
+    /// It is explicitly encoded in UTF-8 only when the original value is.
+    /// Only sequences starting with `ESC` are recognized; 8-bit C1 introducers
+    /// (e.g. U+009B or the byte 0x9B for CSI) are kept as they are.
+    pub fn strip_ansi_escapes(&self) -> MaybeUtf8Buf {
+        let v = strip_ansi(self.as_bytes());
+        match self.inner {
+            // escape sequences start and end with ASCII bytes, so removing them keeps UTF-8
+            Slice::Utf8(_) => MaybeUtf8Buf::from_str(unsafe { String::from_utf8_unchecked(v) }),
+            Slice::Bytes(_) => MaybeUtf8Buf::from_bytes(v),
+        }
+    }
+
+    /// Returns the value with the parts valid in UTF-8 normalized to NFC,
+    /// keeping invalid UTF-8 sequences as they are.
+    /// It is borrowed from the original value if the value is already normalized.
+    ///
+    /// This is only available with the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn nfc(&self) -> MaybeUtf8Cow<'a> {
+        normalized(*self, unicode_normalization::is_nfc, |s| s.nfc().collect())
+    }
+
+    /// Returns the value with the parts valid in UTF-8 normalized to NFD,
+    /// keeping invalid UTF-8 sequences as they are.
+    /// It is borrowed from the original value if the value is already normalized.
+    ///
+    /// This is only available with the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn nfd(&self) -> MaybeUtf8Cow<'a> {
+        normalized(*self, unicode_normalization::is_nfd, |s| s.nfd().collect())
+    }
+
+    /// Returns the value with the parts valid in UTF-8 decomposed as like HFS+ file names,
+    /// keeping invalid UTF-8 sequences as they are.
+    /// It is borrowed from the original value if the value is already decomposed.
+    ///
+    /// This is same to NFD except that U+2000 through U+2FFF, U+F900 through U+FAFF
+    /// and U+2F800 through U+2FAFF are not decomposed.
+    ///
+    /// This is only available with the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn normalize_for_macos(&self) -> MaybeUtf8Cow<'a> {
+        normalized(*self, |s| s.split(is_hfs_excluded).all(unicode_normalization::is_nfd),
+                   hfs_decomposed)
+    }
+
+    /// Returns `true` if two values are equal after normalizing the parts valid in UTF-8 to NFD,
+    /// as like file names in APFS. Invalid UTF-8 sequences are compared as they are.
+    ///
+    /// This is only available with the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn eq_normalized<'b, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>>>(&self, other: T) -> bool {
+        self.nfd().as_bytes() == other.into_maybe_utf8().nfd().as_bytes()
+    }
+
+    /// Returns `true` if the `MaybeUtf8Slice` value is valid in UTF-8,
+    /// whether or not it is explicitly encoded in UTF-8.
+    pub fn is_utf8(&self) -> bool {
+        match self.inner {
+            Slice::Utf8(_) => true,
+            Slice::Bytes(v) => str::from_utf8(v).is_ok(),
+        }
+    }
+
+    /// Returns `true` if given pattern matches a prefix of the underlying bytes.
+    pub fn starts_with<P: BytePattern>(&self, mut pat: P) -> bool {
+        pat.prefix_len_in(self.as_bytes()).is_some()
+    }
+
+    /// Returns `true` if given pattern matches a suffix of the underlying bytes.
+    pub fn ends_with<P: BytePattern>(&self, mut pat: P) -> bool {
+        pat.suffix_len_in(self.as_bytes()).is_some()
+    }
+
+    /// Returns `true` if given pattern matches a part of the underlying bytes.
+    pub fn contains<P: BytePattern>(&self, pat: P) -> bool {
+        self.find(pat).is_some()
+    }
+
+    /// Returns the byte offset of the first match of given pattern,
+    /// or `None` if the pattern doesn't match.
+    pub fn find<P: BytePattern>(&self, mut pat: P) -> Option<usize> {
+        pat.find_in(self.as_bytes()).map(|(i, _)| i)
+    }
+
+    /// Returns the byte offset of the last match of given pattern,
+    /// or `None` if the pattern doesn't match.
+    pub fn rfind<P: BytePattern>(&self, mut pat: P) -> Option<usize> {
+        pat.rfind_in(self.as_bytes()).map(|(i, _)| i)
+    }
+
+    /// Returns a `MaybeUtf8Slice` with given prefix removed,
+    /// or `None` if the pattern doesn't match a prefix of the underlying bytes.
+    /// It is no longer explicitly encoded in UTF-8 if the prefix doesn't end at a char boundary.
+    pub fn strip_prefix<P: BytePattern>(&self, mut pat: P) -> Option<MaybeUtf8Slice<'a>> {
+        pat.prefix_len_in(self.as_bytes()).and_then(|n| self.get(n..))
+    }
+
+    /// Returns a `MaybeUtf8Slice` with given suffix removed,
+    /// or `None` if the pattern doesn't match a suffix of the underlying bytes.
+    /// It is no longer explicitly encoded in UTF-8 if the suffix doesn't start at a char boundary.
+    pub fn strip_suffix<P: BytePattern>(&self, mut pat: P) -> Option<MaybeUtf8Slice<'a>> {
+        pat.suffix_len_in(self.as_bytes()).and_then(|n| self.get(..self.len() - n))
+    }
+
+    /// Returns an iterator over `MaybeUtf8Slice`s separated by given pattern.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn split<P: BytePattern>(&self, sep: P) -> Split<'a, P> {
+        Split::new(*self, sep)
+    }
+
+    /// Returns an iterator over `MaybeUtf8Slice`s separated by given string slice,
+    /// `u8` slice or anything convertible to `MaybeUtf8Slice`.
+    ///
+    /// Panics if the separator is empty.
+    pub fn split_str<'b, P>(&self, sep: P) -> Split<'a, MaybeUtf8Slice<'b>>
+            where P: IntoMaybeUtf8<MaybeUtf8Slice<'b>> {
+        let sep = sep.into_maybe_utf8();
+        assert!(!sep.is_empty(), "cannot split with an empty pattern");
+        Split::new(*self, sep)
+    }
+
+    /// Returns an iterator over `MaybeUtf8Slice`s separated by given pattern,
+    /// starting from the end.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn rsplit<P: BytePattern>(&self, sep: P) -> RSplit<'a, P> {
+        RSplit::new(*self, sep)
+    }
+
+    /// Returns an iterator over at most `n` `MaybeUtf8Slice`s separated by given pattern.
+    /// The last slice contains the remainder.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn splitn<P: BytePattern>(&self, n: usize, sep: P) -> SplitN<'a, P> {
+        SplitN::new(*self, sep, n)
+    }
+
+    /// Returns an iterator over at most `n` `MaybeUtf8Slice`s separated by given pattern,
+    /// starting from the end. The last slice contains the remainder.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn rsplitn<P: BytePattern>(&self, n: usize, sep: P) -> RSplitN<'a, P> {
+        RSplitN::new(*self, sep, n)
+    }
+
+    /// Returns an iterator over `MaybeUtf8Slice`s terminated by given pattern.
+    /// It is same to `split` except that a trailing empty slice is skipped.
+    ///
+    /// The iterator panics if the pattern matches an empty sequence.
+    pub fn split_terminator<P: BytePattern>(&self, sep: P) -> SplitTerminator<'a, P> {
+        SplitTerminator::new(*self, sep)
+    }
+
+    /// Returns an iterator over lines as `MaybeUtf8Slice`s.
+    /// Lines are terminated by `\n` optionally preceded by `\r`, which are not included.
+    /// This never fails even when the value is not valid in UTF-8.
+    pub fn lines(&self) -> Lines<'a> {
+        Lines::new(*self)
+    }
+
+    /// Returns an iterator over non-empty `MaybeUtf8Slice`s separated by whitespace.
+    /// Unicode whitespace is recognized if the value is explicitly encoded in UTF-8,
+    /// and only ASCII whitespace is recognized otherwise.
+    pub fn split_whitespace(&self) -> SplitWhitespace<'a> {
+        SplitWhitespace::new(*self)
+    }
+
+    /// Returns a new `MaybeUtf8Buf` with all matches of given pattern replaced
+    /// with a string slice, a `u8` slice or anything convertible to `MaybeUtf8Slice`.
+    /// The result is explicitly encoded in UTF-8 only when the original value and
+    /// the replacement are and every match is at char boundaries.
+    ///
+    /// Panics if the pattern matches an empty sequence.
+    pub fn replace<'b, P, T>(&self, from: P, to: T) -> MaybeUtf8Buf
+            where P: BytePattern, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>> {
+        self.replacen(from, to, usize::MAX)
+    }
+
+    /// Same to `replace` but replaces at most `count` first matches of given pattern.
+    ///
+    /// Panics if the pattern matches an empty sequence.
+    pub fn replacen<'b, P, T>(&self, from: P, to: T, count: usize) -> MaybeUtf8Buf
+            where P: BytePattern, T: IntoMaybeUtf8<MaybeUtf8Slice<'b>> {
+        let mut from = from;
+        let to = to.into_maybe_utf8();
+        let bytes = self.as_bytes();
+        let mut buf = MaybeUtf8Buf::with_capacity(bytes.len());
+        let mut last = 0;
+        for _ in 0..count {
+            let (begin, end) = match from.find_in(&bytes[last..]) {
+                Some((begin, end)) => (last + begin, last + end),
+                None => break,
+            };
+            assert!(begin < end, "cannot replace an empty pattern");
+            buf.push_maybe_utf8(self.get(last..begin).unwrap());
+            buf.push_maybe_utf8(to);
+            last = end;
+        }
+        buf.push_maybe_utf8(self.get(last..).unwrap());
+        buf
+    }
+
+    /// Returns a `MaybeUtf8Slice` with leading and trailing whitespace removed.
+    /// Unicode whitespace is removed if the underlying bytes are valid in UTF-8,
+    /// and only ASCII whitespace is removed otherwise.
+    pub fn trim(&self) -> MaybeUtf8Slice<'a> {
+        match self.as_str() {
+            Some(s) => {
+                let rest = s.trim_start();
+                let begin = s.len() - rest.len();
+                self.get(begin..begin + rest.trim_end().len()).unwrap()
+            }
+            None => self.trim_ascii(),
+        }
+    }
+
+    /// Returns a `MaybeUtf8Slice` with leading whitespace removed.
+    /// Unicode whitespace is removed if the underlying bytes are valid in UTF-8,
+    /// and only ASCII whitespace is removed otherwise.
+    pub fn trim_start(&self) -> MaybeUtf8Slice<'a> {
+        match self.as_str() {
+            Some(s) => self.get(s.len() - s.trim_start().len()..).unwrap(),
+            None => self.trim_ascii_start(),
+        }
+    }
+
+    /// Returns a `MaybeUtf8Slice` with trailing whitespace removed.
+    /// Unicode whitespace is removed if the underlying bytes are valid in UTF-8,
+    /// and only ASCII whitespace is removed otherwise.
+    pub fn trim_end(&self) -> MaybeUtf8Slice<'a> {
+        match self.as_str() {
+            Some(s) => self.get(..s.trim_end().len()).unwrap(),
+            None => self.trim_ascii_end(),
+        }
+    }
+
+    /// Returns a `MaybeUtf8Slice` with leading and trailing ASCII whitespace removed.
+    pub fn trim_ascii(&self) -> MaybeUtf8Slice<'a> {
+        self.trim_matches(|b: u8| b.is_ascii_whitespace())
+    }
+
+    /// Returns a `MaybeUtf8Slice` with leading ASCII whitespace removed.
+    pub fn trim_ascii_start(&self) -> MaybeUtf8Slice<'a> {
+        self.trim_start_matches(|b: u8| b.is_ascii_whitespace())
+    }
+
+    /// Returns a `MaybeUtf8Slice` with trailing ASCII whitespace removed.
+    pub fn trim_ascii_end(&self) -> MaybeUtf8Slice<'a> {
+        self.trim_end_matches(|b: u8| b.is_ascii_whitespace())
+    }
+
+    /// Returns a `MaybeUtf8Slice` with all leading and trailing matches of given pattern removed.
+    /// It is no longer explicitly encoded in UTF-8 if either end is not at a char boundary.
+    pub fn trim_matches<P: BytePattern>(&self, mut pat: P) -> MaybeUtf8Slice<'a> {
+        let bytes = self.as_bytes();
+        let begin = trim_start_offset(bytes, &mut pat);
+        let end = begin + trim_end_offset(&bytes[begin..], &mut pat);
+        self.get(begin..end).unwrap()
+    }
+
+    /// Returns a `MaybeUtf8Slice` with all leading matches of given pattern removed.
+    /// It is no longer explicitly encoded in UTF-8 if the start is not at a char boundary.
+    pub fn trim_start_matches<P: BytePattern>(&self, mut pat: P) -> MaybeUtf8Slice<'a> {
+        self.get(trim_start_offset(self.as_bytes(), &mut pat)..).unwrap()
+    }
+
+    /// Returns a `MaybeUtf8Slice` with all trailing matches of given pattern removed.
+    /// It is no longer explicitly encoded in UTF-8 if the end is not at a char boundary.
+    pub fn trim_end_matches<P: BytePattern>(&self, mut pat: P) -> MaybeUtf8Slice<'a> {
+        self.get(..trim_end_offset(self.as_bytes(), &mut pat)).unwrap()
+    }
+}
+
+impl<'a> MaybeUtf8SliceMut<'a> {
+    /// Creates a `MaybeUtf8SliceMut` reference from a mutable `u8` slice.
+    pub fn from_bytes(v: &'a mut [u8]) -> MaybeUtf8SliceMut<'a> {
+        MaybeUtf8SliceMut { bytes: v, utf8: false, owner: None }
+    }
+
+    /// Returns a slice of underlying bytes. It might or might not be encoded in UTF-8.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes
+    }
+
+    /// Returns a mutable slice of underlying bytes.
+    /// Since any byte can be changed, the slice is no longer explicitly encoded in UTF-8.
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.demote();
+        self.bytes
+    }
+
+    /// Returns a string slice encoded in UTF-8 if possible.
+    /// It returns `None` if the underlying bytes are not encoded in UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        self.to_slice().as_str()
+    }
+
+    /// Returns a `MaybeUtf8Slice` borrowed from this `MaybeUtf8SliceMut`.
+    pub fn to_slice<'b>(&'b self) -> MaybeUtf8Slice<'b> {
+        if self.utf8 {
+            MaybeUtf8Slice::from_str(unsafe { str::from_utf8_unchecked(self.bytes) })
+        } else {
+            MaybeUtf8Slice::from_bytes(self.bytes)
+        }
+    }
+
+    /// Converts every ASCII letter to the lower case in place.
+    /// This never breaks the UTF-8 encoding.
+    pub fn make_ascii_lowercase(&mut self) {
+        for c in self.bytes.iter_mut() { *c = c.to_ascii_lowercase(); }
+    }
+
+    /// Converts every ASCII letter to the upper case in place.
+    /// This never breaks the UTF-8 encoding.
+    pub fn make_ascii_uppercase(&mut self) {
+        for c in self.bytes.iter_mut() { *c = c.to_ascii_uppercase(); }
+    }
+
+    /// Replaces every occurrence of the byte `from` with the byte `to` in place.
+    /// The UTF-8 encoding is retained only when both bytes are in ASCII.
+    pub fn replace_byte(&mut self, from: u8, to: u8) {
+        if from >= 0x80 || to >= 0x80 { self.demote(); }
+        for c in self.bytes.iter_mut() {
+            if *c == from { *c = to; }
+        }
+    }
+
+    /// Shortens the `MaybeUtf8SliceMut` to given byte length, leaving the remainder intact.
+    /// It is no longer explicitly encoded in UTF-8 if the new length is not at a char boundary.
+    ///
+    /// Panics if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        assert!(len <= self.bytes.len(), "new length {} is greater than the current length {}",
+                len, self.bytes.len());
+        if self.utf8 && !is_char_boundary(self.bytes, len) {
+            // the bytes themselves are not changed, so the owner doesn't need the demotion
+            self.utf8 = false;
+        }
+        let bytes = mem::take(&mut self.bytes);
+        self.bytes = &mut bytes[..len];
+    }
+
+    /// Returns a byte length of the `MaybeUtf8SliceMut` value.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns `true` if the `MaybeUtf8SliceMut` value has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // makes the slice no longer explicitly encoded in UTF-8, as the bytes are about to change.
+    fn demote(&mut self) {
+        self.utf8 = false;
+        if let Some(tag) = self.owner {
+            tag.set(Tag::Unknown);
+        }
+    }
+}
+
+impl<B: ByteStorage> MaybeUtf8Of<B> {
+    /// Creates a `MaybeUtf8Of` value from a storage with bytes.
+    pub fn from_bytes(bytes: B) -> MaybeUtf8Of<B> {
+        MaybeUtf8Of { bytes, utf8: false }
+    }
+
+    /// Creates a `MaybeUtf8Of` value from a storage with a string.
+    /// `Into` conversion from the string to the storage should not change the bytes.
+    pub fn from_str<S>(s: S) -> MaybeUtf8Of<B> where S: AsRef<str> + Into<B> {
+        // we can't trust `Into` to retain the bytes, so check them once
+        let bytes = s.into();
+        let utf8 = str::from_utf8(bytes.as_ref()).is_ok();
+        MaybeUtf8Of { bytes, utf8 }
+    }
+
+    /// Creates a `MaybeUtf8Of` value encoded in UTF-8 from a storage with bytes.
+    /// If the bytes are not encoded in UTF-8 it returns the original storage back.
+    pub fn from_utf8(bytes: B) -> Result<MaybeUtf8Of<B>, B> {
+        if str::from_utf8(bytes.as_ref()).is_ok() {
+            Ok(MaybeUtf8Of { bytes, utf8: true })
+        } else {
+            Err(bytes)
+        }
+    }
+
+    /// Creates a `MaybeUtf8Of` value encoded in UTF-8 from a storage with bytes,
+    /// without checking if the bytes are actually encoded in UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// The bytes should be valid in UTF-8.
+    pub unsafe fn from_utf8_unchecked(bytes: B) -> MaybeUtf8Of<B> {
+        MaybeUtf8Of { bytes, utf8: true }
+    }
+
+    /// Returns a slice of underlying bytes. It might or might not be encoded in UTF-8.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+
+    /// Returns a string slice encoded in UTF-8 if possible.
+    /// It returns `None` if the underlying bytes are not encoded in UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        self.to_slice().as_str()
+    }
+
+    /// Returns a `Cow` string which represents the current `MaybeUtf8Of`.
+    /// It may call given `to_cow` function to get a `Cow` string out of the bytes.
+    /// `to_cow` function itself may return a `String` or `&str` compatible to `Cow` string.
+    pub fn map_as_cow<'a, F, T>(&'a self, to_cow: F) -> Cow<'a, str>
+            where F: FnMut(&'a [u8]) -> T, T: Into<Cow<'a, str>> {
+        self.to_slice().map_as_cow(to_cow)
+    }
+
+    /// Returns a `Cow` string which represents the current `MaybeUtf8Of`.
+    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn as_cow_lossy<'a>(&'a self) -> Cow<'a, str> {
+        self.to_slice().as_cow_lossy()
+    }
+
+    /// Returns a `MaybeUtf8Slice` borrowed from this `MaybeUtf8Of`.
+    pub fn to_slice<'a>(&'a self) -> MaybeUtf8Slice<'a> {
+        if self.utf8 {
+            MaybeUtf8Slice::from_str(unsafe { str::from_utf8_unchecked(self.bytes.as_ref()) })
+        } else {
+            MaybeUtf8Slice::from_bytes(self.bytes.as_ref())
+        }
+    }
+
+    /// Returns a new `MaybeUtf8Buf` from the current `MaybeUtf8Of`.
+    pub fn to_buf(&self) -> MaybeUtf8Buf {
+        self.to_slice().to_owned()
+    }
+
+    /// Returns a reference to the backing storage.
+    pub fn get_ref(&self) -> &B {
+        &self.bytes
+    }
+
+    /// Converts a `MaybeUtf8Of` into the backing storage.
+    pub fn into_inner(self) -> B {
+        self.bytes
+    }
+
+    /// Returns a byte length of the `MaybeUtf8Of` value.
+    pub fn len(&self) -> usize {
+        self.bytes.as_ref().len()
+    }
+
+    /// Returns `true` if the `MaybeUtf8Of` value has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // returns `true` if the sub-slice for given byte range can retain the UTF-8 encoding.
+    fn is_utf8_range(&self, begin: usize, end: usize) -> bool {
+        self.utf8 && is_char_boundary(self.as_bytes(), begin) &&
+                     is_char_boundary(self.as_bytes(), end)
+    }
+}
+
+impl<B: GrowableByteStorage> MaybeUtf8Of<B> {
+    /// Reserves the capacity for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.bytes.reserve(additional);
+    }
+
+    /// Appends a string slice.
+    /// This doesn't change whether the value is explicitly encoded in UTF-8.
+    pub fn push_str(&mut self, s: &str) {
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    /// Appends a character.
+    /// This doesn't change whether the value is explicitly encoded in UTF-8.
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Appends a `u8` slice. The value is no longer explicitly encoded in UTF-8.
+    pub fn push_bytes(&mut self, v: &[u8]) {
+        self.bytes.extend_from_slice(v);
+        self.utf8 = false;
+    }
+
+    /// Appends a byte. The value is no longer explicitly encoded in UTF-8.
+    pub fn push_byte(&mut self, b: u8) {
+        self.push_bytes(&[b]);
+    }
+
+    /// Appends a `MaybeUtf8Slice`.
+    /// The value is no longer explicitly encoded in UTF-8 unless the slice is.
+    pub fn push_maybe_utf8(&mut self, s: MaybeUtf8Slice) {
+        match s.inner {
+            Slice::Utf8(s) => self.push_str(s),
+            Slice::Bytes(v) => self.push_bytes(v),
+        }
+    }
+
+    /// Shortens the `MaybeUtf8Of` to given byte length.
+    /// It is no longer explicitly encoded in UTF-8 if the new length is not at a char boundary.
+    /// It has no effect if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len() { return; }
+        self.utf8 = self.is_utf8_range(0, len);
+        self.bytes.truncate(len);
+    }
+
+    /// Removes all contents of the `MaybeUtf8Of`.
+    /// This doesn't change whether the value is explicitly encoded in UTF-8.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Inserts a string slice at given byte offset.
+    /// It is no longer explicitly encoded in UTF-8 if the offset is not at a char boundary.
+    ///
+    /// Panics if `idx` is greater than the current length.
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        assert!(idx <= self.len(), "insertion index {} is greater than the current length {}",
+                idx, self.len());
+        self.utf8 = self.is_utf8_range(idx, idx);
+        self.bytes.insert_from_slice(idx, s.as_bytes());
+    }
+
+    /// Inserts a character at given byte offset.
+    /// It is no longer explicitly encoded in UTF-8 if the offset is not at a char boundary.
+    ///
+    /// Panics if `idx` is greater than the current length.
+    pub fn insert(&mut self, idx: usize, c: char) {
+        self.insert_str(idx, c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Inserts a `u8` slice at given byte offset.
+    /// The value is no longer explicitly encoded in UTF-8.
+    ///
+    /// Panics if `idx` is greater than the current length.
+    pub fn insert_bytes(&mut self, idx: usize, v: &[u8]) {
+        assert!(idx <= self.len(), "insertion index {} is greater than the current length {}",
+                idx, self.len());
+        self.bytes.insert_from_slice(idx, v);
+        self.utf8 = false;
+    }
+
+    /// Inserts a byte at given byte offset.
+    /// The value is no longer explicitly encoded in UTF-8.
+    ///
+    /// Panics if `idx` is greater than the current length.
+    pub fn insert_byte(&mut self, idx: usize, b: u8) {
+        self.insert_bytes(idx, &[b]);
+    }
+}
+
+impl<'a, B: GrowableByteStorage> Extend<&'a str> for MaybeUtf8Of<B> {
+    fn extend<I: IntoIterator<Item=&'a str>>(&mut self, iterator: I) {
+        for s in iterator { self.push_str(s); }
+    }
+}
+
+impl<B: GrowableByteStorage> Extend<char> for MaybeUtf8Of<B> {
+    fn extend<I: IntoIterator<Item=char>>(&mut self, iterator: I) {
+        let iterator = iterator.into_iter();
+        self.reserve(iterator.size_hint().0);
+        for c in iterator { self.push(c); }
+    }
+}
+
+impl<B: GrowableByteStorage> fmt::Write for MaybeUtf8Of<B> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl MaybeUtf8Of<Vec<u8>> {
+    /// Tries to convert a `MaybeUtf8Of` into a `String`.
+    /// If there is an invalid UTF-8 sequence it returns the original `MaybeUtf8Of` back.
+    pub fn into_str(self) -> Result<String, MaybeUtf8Of<Vec<u8>>> {
+        MaybeUtf8Buf::from(self).into_str().map_err(From::from)
+    }
+
+    /// Converts a `MaybeUtf8Of` into a `String`.
+    /// It may call given `into_str` function to get a `String` out of the bytes.
+    pub fn map_into_str<F>(self, into_str: F) -> String where F: FnMut(Vec<u8>) -> String {
+        MaybeUtf8Buf::from(self).map_into_str(into_str)
+    }
+
+    /// Converts a `MaybeUtf8Of` into a `String`.
+    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn into_str_lossy(self) -> String {
+        MaybeUtf8Buf::from(self).into_str_lossy()
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl MaybeUtf8Of<Bytes> {
+    /// Returns a slice of the `MaybeUtf8Bytes` value for given byte range without copying.
+    /// The UTF-8 encoding is retained only when both ends of the range are char boundaries.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> MaybeUtf8Bytes {
+        let (begin, end) = resolve_range(range, self.len());
+        MaybeUtf8Of { bytes: self.bytes.slice(begin..end), utf8: self.is_utf8_range(begin, end) }
+    }
+
+    /// Tries to convert a `MaybeUtf8Bytes` into a `String`.
+    /// If there is an invalid UTF-8 sequence it returns the original `MaybeUtf8Bytes` back.
+    pub fn into_str(self) -> Result<String, MaybeUtf8Bytes> {
+        if self.as_str().is_some() {
+            Ok(unsafe { String::from_utf8_unchecked(Vec::from(self.bytes)) })
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Converts a `MaybeUtf8Bytes` into a `String`.
+    /// It may call given `into_str` function to get a `String` out of the bytes.
+    pub fn map_into_str<F>(self, mut into_str: F) -> String where F: FnMut(Bytes) -> String {
+        if self.utf8 {
+            unsafe { String::from_utf8_unchecked(Vec::from(self.bytes)) }
+        } else {
+            into_str(self.bytes)
+        }
+    }
+
+    /// Converts a `MaybeUtf8Bytes` into a `String`.
+    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn into_str_lossy(self) -> String {
+        match self.into_str() {
+            Ok(s) => s,
+            Err(v) => v.as_cow_lossy().into_owned(),
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<A: Allocator> MaybeUtf8Of<Vec<u8, A>> {
+    /// Creates a new empty `MaybeUtf8BufIn` value (which is, naturally, encoded in UTF-8)
+    /// with given allocator.
+    pub fn new_in(alloc: A) -> MaybeUtf8BufIn<A> {
+        MaybeUtf8Of { bytes: Vec::new_in(alloc), utf8: true }
+    }
+
+    /// Creates a `MaybeUtf8BufIn` value by copying a string slice with given allocator.
+    pub fn from_str_in(s: &str, alloc: A) -> MaybeUtf8BufIn<A> {
+        MaybeUtf8Of { bytes: copy_in(s.as_bytes(), alloc), utf8: true }
+    }
+
+    /// Creates a `MaybeUtf8BufIn` value by copying a `u8` slice with given allocator.
+    pub fn from_bytes_in(v: &[u8], alloc: A) -> MaybeUtf8BufIn<A> {
+        MaybeUtf8Of { bytes: copy_in(v, alloc), utf8: false }
+    }
+
+    /// Returns a reference to the underlying allocator.
+    pub fn allocator(&self) -> &A {
+        self.bytes.allocator()
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+fn copy_in<A: Allocator>(v: &[u8], alloc: A) -> Vec<u8, A> {
+    let mut ret = Vec::with_capacity_in(v.len(), alloc);
+    ret.extend_from_slice(v);
+    ret
+}
+
+impl MaybeUtf8Of<Box<[u8]>> {
+    /// Converts a `MaybeUtf8Box` into a `MaybeUtf8Buf` without copying.
+    pub fn into_buf(self) -> MaybeUtf8Buf {
+        MaybeUtf8Buf::from(MaybeUtf8Of { bytes: self.bytes.into_vec(), utf8: self.utf8 })
+    }
+}
+
+impl From<MaybeUtf8Buf> for MaybeUtf8Box {
+    fn from(buf: MaybeUtf8Buf) -> MaybeUtf8Box { buf.into_boxed() }
+}
+
+impl From<MaybeUtf8Box> for MaybeUtf8Buf {
+    fn from(v: MaybeUtf8Box) -> MaybeUtf8Buf { v.into_buf() }
+}
+
+impl MaybeUtf8Of<SharedBytes> {
+    /// Returns a slice of the `MaybeUtf8Shared` value for given byte range without copying.
+    /// The UTF-8 encoding is retained only when both ends of the range are char boundaries.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> MaybeUtf8Shared {
+        let (begin, end) = resolve_range(range, self.len());
+        MaybeUtf8Of { bytes: self.bytes.slice(begin..end), utf8: self.is_utf8_range(begin, end) }
+    }
+}
+
+impl From<MaybeUtf8Buf> for MaybeUtf8Shared {
+    fn from(buf: MaybeUtf8Buf) -> MaybeUtf8Shared {
+        let v = MaybeUtf8Of::<Vec<u8>>::from(buf);
+        MaybeUtf8Of { bytes: SharedBytes::from(v.bytes), utf8: v.utf8 }
+    }
+}
+
+impl<'a> From<MaybeUtf8Slice<'a>> for MaybeUtf8Shared {
+    fn from(s: MaybeUtf8Slice<'a>) -> MaybeUtf8Shared {
+        let utf8 = match s.inner { Slice::Utf8(_) => true, Slice::Bytes(_) => false };
+        MaybeUtf8Of { bytes: SharedBytes::from(s.as_bytes()), utf8 }
+    }
+}
+
+impl SharedBytes {
+    /// Creates a `SharedBytes` storage spanning the entire `Arc<[u8]>`.
+    pub fn new(arc: Arc<[u8]>) -> SharedBytes {
+        let end = arc.len();
+        SharedBytes { arc, begin: 0, end }
+    }
+
+    /// Returns a sub-range of the `SharedBytes` storage without copying.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> SharedBytes {
+        let (begin, end) = resolve_range(range, self.end - self.begin);
+        SharedBytes { arc: self.arc.clone(), begin: self.begin + begin, end: self.begin + end }
+    }
+}
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] { &self.arc[self.begin..self.end] }
+}
+
+impl From<Arc<[u8]>> for SharedBytes {
+    fn from(arc: Arc<[u8]>) -> SharedBytes { SharedBytes::new(arc) }
+}
+
+impl<'a> From<&'a [u8]> for SharedBytes {
+    fn from(v: &'a [u8]) -> SharedBytes { SharedBytes::new(From::from(v)) }
+}
+
+impl From<Vec<u8>> for SharedBytes {
+    fn from(v: Vec<u8>) -> SharedBytes { SharedBytes::new(From::from(v)) }
+}
+
+impl<'a> From<&'a str> for SharedBytes {
+    fn from(s: &'a str) -> SharedBytes { SharedBytes::from(s.as_bytes()) }
+}
+
+impl From<String> for SharedBytes {
+    fn from(s: String) -> SharedBytes { SharedBytes::from(s.into_bytes()) }
+}
+
+#[cfg(feature = "bytes")]
+impl From<MaybeUtf8Buf> for MaybeUtf8Bytes {
+    fn from(buf: MaybeUtf8Buf) -> MaybeUtf8Bytes {
+        let v = MaybeUtf8Of::<Vec<u8>>::from(buf);
+        MaybeUtf8Of { bytes: Bytes::from(v.bytes), utf8: v.utf8 }
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<const N: usize> From<MaybeUtf8Buf> for MaybeUtf8SmallBuf<N> {
+    fn from(buf: MaybeUtf8Buf) -> MaybeUtf8SmallBuf<N> {
+        let v = MaybeUtf8Of::<Vec<u8>>::from(buf);
+        MaybeUtf8Of { bytes: SmallVec::from_vec(v.bytes), utf8: v.utf8 }
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<const N: usize> From<MaybeUtf8SmallBuf<N>> for MaybeUtf8Buf {
+    fn from(v: MaybeUtf8SmallBuf<N>) -> MaybeUtf8Buf {
+        MaybeUtf8Buf::from(MaybeUtf8Of { bytes: v.bytes.into_vec(), utf8: v.utf8 })
+    }
+}
+
+impl<'a> MaybeUtf8Cow<'a> {
+    /// Creates a new empty borrowed `MaybeUtf8Cow` value
+    /// (which is, naturally, encoded in UTF-8).
+    pub fn new() -> MaybeUtf8Cow<'static> {
+        MaybeUtf8Cow::Borrowed(MaybeUtf8Slice::new())
+    }
+
+    /// Returns a mutable reference to the owned `MaybeUtf8Buf`,
+    /// making a copy of the borrowed data if needed.
+    pub fn to_mut(&mut self) -> &mut MaybeUtf8Buf {
+        if let MaybeUtf8Cow::Borrowed(s) = *self {
+            *self = MaybeUtf8Cow::Owned(s.to_owned());
+        }
+        match *self {
+            MaybeUtf8Cow::Borrowed(_) => unreachable!(),
+            MaybeUtf8Cow::Owned(ref mut buf) => buf,
+        }
+    }
+
+    /// Converts a `MaybeUtf8Cow` into an owned `MaybeUtf8Buf`,
+    /// making a copy of the borrowed data if needed.
+    pub fn into_owned(self) -> MaybeUtf8Buf {
+        match self {
+            MaybeUtf8Cow::Borrowed(s) => s.to_owned(),
+            MaybeUtf8Cow::Owned(buf) => buf,
+        }
+    }
+
+    /// Returns a slice of underlying bytes. It might or might not be encoded in UTF-8.
+    pub fn as_bytes(&self) -> &[u8] {
+        match *self {
+            MaybeUtf8Cow::Borrowed(ref s) => s.as_bytes(),
+            MaybeUtf8Cow::Owned(ref buf) => buf.as_bytes(),
+        }
+    }
+
+    /// Returns a string slice encoded in UTF-8 if possible.
+    /// It returns `None` if the underlying bytes are not encoded in UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            MaybeUtf8Cow::Borrowed(ref s) => s.as_str(),
+            MaybeUtf8Cow::Owned(ref buf) => buf.as_str(),
+        }
+    }
+
+    /// Returns a `Cow` string which represents the current `MaybeUtf8Cow`.
+    /// It may call given `to_cow` function to get a `Cow` string out of the bytes.
+    /// `to_cow` function itself may return a `String` or `&str` compatible to `Cow` string.
+    pub fn map_as_cow<'b, F, T>(&'b self, to_cow: F) -> Cow<'b, str>
+            where F: FnMut(&'b [u8]) -> T, T: Into<Cow<'b, str>> {
+        self.to_slice().map_as_cow(to_cow)
+    }
+
+    /// Returns a `Cow` string which represents the current `MaybeUtf8Cow`.
+    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn as_cow_lossy<'b>(&'b self) -> Cow<'b, str> {
+        self.map_as_cow(String::from_utf8_lossy)
+    }
+
+    /// Returns a `MaybeUtf8Slice` borrowed from this `MaybeUtf8Cow`.
+    pub fn to_slice<'b>(&'b self) -> MaybeUtf8Slice<'b> {
+        match *self {
+            MaybeUtf8Cow::Borrowed(s) => s,
+            MaybeUtf8Cow::Owned(ref buf) => buf.to_slice(),
+        }
+    }
+
+    /// Tries to convert a `MaybeUtf8Cow` into a `Cow` string without copying.
+    /// If there is an invalid UTF-8 sequence it returns the original `MaybeUtf8Cow` back.
+    pub fn into_str(self) -> Result<Cow<'a, str>, MaybeUtf8Cow<'a>> {
+        match self {
+            MaybeUtf8Cow::Borrowed(s) => match s.as_str() {
+                Some(s) => Ok(Cow::Borrowed(s)),
+                None => Err(MaybeUtf8Cow::Borrowed(s)),
+            },
+            MaybeUtf8Cow::Owned(buf) => match buf.into_str() {
+                Ok(s) => Ok(Cow::Owned(s)),
+                Err(buf) => Err(MaybeUtf8Cow::Owned(buf)),
+            },
+        }
+    }
+
+    /// Converts a `MaybeUtf8Cow` into a `Cow` string.
+    /// It may call given `into_str` function to get a `Cow` string out of the bytes,
+    /// which are borrowed or owned depending on the current `MaybeUtf8Cow`.
+    /// `into_str` function itself may return a `String` or `&str` compatible to `Cow` string.
+    pub fn map_into_str<F, T>(self, mut into_str: F) -> Cow<'a, str>
+            where F: FnMut(Cow<'a, [u8]>) -> T, T: Into<Cow<'a, str>> {
+        match self {
+            MaybeUtf8Cow::Borrowed(s) => match s.inner {
+                Slice::Utf8(s) => Cow::Borrowed(s),
+                Slice::Bytes(v) => into_str(Cow::Borrowed(v)).into(),
+            },
+            MaybeUtf8Cow::Owned(buf) => match buf.repr.tag() {
+                Tag::KnownUtf8 =>
+                    Cow::Owned(unsafe { String::from_utf8_unchecked(buf.repr.into_vec()) }),
+                Tag::KnownValid | Tag::KnownInvalid | Tag::Incomplete | Tag::Unknown =>
+                    into_str(Cow::Owned(buf.repr.into_vec())).into(),
+            },
+        }
+    }
+
+    /// Converts a `MaybeUtf8Cow` into a `Cow` string.
+    /// Any invalid UTF-8 sequences are replaced by U+FFFD, as like `String::from_utf8_lossy`.
+    pub fn into_str_lossy(self) -> Cow<'a, str> {
+        match self {
+            MaybeUtf8Cow::Borrowed(s) => s.as_cow_lossy(),
+            MaybeUtf8Cow::Owned(buf) => Cow::Owned(buf.into_str_lossy()),
+        }
+    }
+
+    /// Converts a `MaybeUtf8Cow` into a `Cow` slice of `u8` bytes.
+    pub fn into_bytes(self) -> Cow<'a, [u8]> {
+        match self {
+            MaybeUtf8Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+            MaybeUtf8Cow::Owned(buf) => Cow::Owned(buf.into_bytes()),
+        }
+    }
+
+    /// Returns a byte length of the `MaybeUtf8Cow` value.
+    pub fn len(&self) -> usize {
+        match *self {
+            MaybeUtf8Cow::Borrowed(ref s) => s.len(),
+            MaybeUtf8Cow::Owned(ref buf) => buf.len(),
+        }
+    }
+
+    /// Returns `true` if the `MaybeUtf8Cow` value has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+macro_rules! define_partial_eq_and_cmp {
+    ($($lty:ty:$lmeth:ident, $rty:ty:$rmeth:ident;)*) => ($(
+        impl<'a, 'b> PartialEq<$rty> for $lty {
+            fn eq(&self, other: &$rty) -> bool { self.$lmeth().eq(other.$rmeth()) }
+        }
+        // this is equivalent to `Ord::cmp` when `$lty` and `$rty` are same
+        #[allow(clippy::non_canonical_partial_ord_impl)]
+        impl<'a, 'b> PartialOrd<$rty> for $lty {
+            fn partial_cmp(&self, other: &$rty) -> Option<Ordering> {
+                self.$lmeth().partial_cmp(other.$rmeth())
+            }
+        }
+    )*)
+}
+
+define_partial_eq_and_cmp! {
+    MaybeUtf8Buf:as_bytes, MaybeUtf8Buf:as_bytes;
+    MaybeUtf8Buf:as_bytes, MaybeUtf8Slice<'b>:as_bytes;
+    MaybeUtf8Buf:as_bytes, MaybeUtf8:as_bytes;
+    MaybeUtf8Buf:as_bytes, MaybeUtf8Cow<'b>:as_bytes;
+    MaybeUtf8Buf:as_bytes, &'b str:as_bytes;
+    MaybeUtf8Buf:as_bytes, &'b [u8]:deref;
+    MaybeUtf8Slice<'a>:as_bytes, MaybeUtf8Buf:as_bytes;
+    MaybeUtf8Slice<'a>:as_bytes, MaybeUtf8Slice<'b>:as_bytes;
+    MaybeUtf8Slice<'a>:as_bytes, MaybeUtf8:as_bytes;
+    MaybeUtf8Slice<'a>:as_bytes, MaybeUtf8Cow<'b>:as_bytes;
+    MaybeUtf8Slice<'a>:as_bytes, &'b str:as_bytes;
+    MaybeUtf8Slice<'a>:as_bytes, &'b [u8]:deref;
+    MaybeUtf8:as_bytes, MaybeUtf8Buf:as_bytes;
+    MaybeUtf8:as_bytes, MaybeUtf8Slice<'b>:as_bytes;
+    MaybeUtf8:as_bytes, MaybeUtf8:as_bytes;
+    MaybeUtf8:as_bytes, MaybeUtf8Cow<'b>:as_bytes;
+    MaybeUtf8:as_bytes, &'b str:as_bytes;
+    MaybeUtf8:as_bytes, &'b [u8]:deref;
+    MaybeUtf8Cow<'a>:as_bytes, MaybeUtf8Buf:as_bytes;
+    MaybeUtf8Cow<'a>:as_bytes, MaybeUtf8Slice<'b>:as_bytes;
+    MaybeUtf8Cow<'a>:as_bytes, MaybeUtf8:as_bytes;
+    MaybeUtf8Cow<'a>:as_bytes, MaybeUtf8Cow<'b>:as_bytes;
+    MaybeUtf8Cow<'a>:as_bytes, &'b str:as_bytes;
+    MaybeUtf8Cow<'a>:as_bytes, &'b [u8]:deref;
+    MaybeUtf8SliceMut<'a>:as_bytes, MaybeUtf8Buf:as_bytes;
+    MaybeUtf8SliceMut<'a>:as_bytes, MaybeUtf8Slice<'b>:as_bytes;
+    MaybeUtf8SliceMut<'a>:as_bytes, MaybeUtf8SliceMut<'b>:as_bytes;
+    MaybeUtf8SliceMut<'a>:as_bytes, MaybeUtf8:as_bytes;
+    MaybeUtf8SliceMut<'a>:as_bytes, &'b str:as_bytes;
+    MaybeUtf8SliceMut<'a>:as_bytes, &'b [u8]:deref;
+    MaybeUtf8Buf:as_bytes, MaybeUtf8SliceMut<'b>:as_bytes;
+    MaybeUtf8Slice<'a>:as_bytes, MaybeUtf8SliceMut<'b>:as_bytes;
+    MaybeUtf8:as_bytes, MaybeUtf8SliceMut<'b>:as_bytes;
+}
+
+macro_rules! define_generic_partial_eq_and_cmp {
+    ($($lty:ty:$lmeth:ident, $rty:ty:$rmeth:ident;)*) => ($(
+        impl<'a, B: ByteStorage> PartialEq<$rty> for $lty {
+            fn eq(&self, other: &$rty) -> bool { self.$lmeth().eq(other.$rmeth()) }
+        }
+        impl<'a, B: ByteStorage> PartialOrd<$rty> for $lty {
+            fn partial_cmp(&self, other: &$rty) -> Option<Ordering> {
+                self.$lmeth().partial_cmp(other.$rmeth())
+            }
+        }
+    )*)
+}
+
+define_generic_partial_eq_and_cmp! {
+    MaybeUtf8Of<B>:as_bytes, MaybeUtf8Buf:as_bytes;
+    MaybeUtf8Of<B>:as_bytes, MaybeUtf8Slice<'a>:as_bytes;
+    MaybeUtf8Of<B>:as_bytes, MaybeUtf8:as_bytes;
+    MaybeUtf8Of<B>:as_bytes, MaybeUtf8Cow<'a>:as_bytes;
+    MaybeUtf8Of<B>:as_bytes, &'a str:as_bytes;
+    MaybeUtf8Of<B>:as_bytes, &'a [u8]:deref;
+    MaybeUtf8Buf:as_bytes, MaybeUtf8Of<B>:as_bytes;
+    MaybeUtf8Slice<'a>:as_bytes, MaybeUtf8Of<B>:as_bytes;
+    MaybeUtf8:as_bytes, MaybeUtf8Of<B>:as_bytes;
+    MaybeUtf8Cow<'a>:as_bytes, MaybeUtf8Of<B>:as_bytes;
+}
+
+// same to `define_partial_eq_and_cmp` but also defines the reverse comparison,
+// which is possible for foreign types as well
+macro_rules! define_symmetric_partial_eq_and_cmp {
+    ($(impl[$($gen:tt)*] $lty:ty:$lmeth:ident, $rty:ty:$rmeth:ident;)*) => ($(
+        impl<$($gen)*> PartialEq<$rty> for $lty {
+            fn eq(&self, other: &$rty) -> bool { self.$lmeth().eq(other.$rmeth()) }
+        }
+        impl<$($gen)*> PartialEq<$lty> for $rty {
+            fn eq(&self, other: &$lty) -> bool { self.$rmeth().eq(other.$lmeth()) }
+        }
+        impl<$($gen)*> PartialOrd<$rty> for $lty {
+            fn partial_cmp(&self, other: &$rty) -> Option<Ordering> {
+                self.$lmeth().partial_cmp(other.$rmeth())
+            }
+        }
+        impl<$($gen)*> PartialOrd<$lty> for $rty {
+            fn partial_cmp(&self, other: &$lty) -> Option<Ordering> {
+                self.$rmeth().partial_cmp(other.$lmeth())
+            }
+        }
+    )*)
+}
+
+define_symmetric_partial_eq_and_cmp! {
+    impl['a, 'b] MaybeUtf8Buf:as_bytes, String:as_bytes;
+    impl['a, 'b] MaybeUtf8Slice<'a>:as_bytes, String:as_bytes;
+    impl['a, 'b] MaybeUtf8:as_bytes, String:as_bytes;
+    impl['a, 'b] MaybeUtf8Cow<'a>:as_bytes, String:as_bytes;
+    impl['a, 'b] MaybeUtf8Buf:as_bytes, Vec<u8>:as_slice;
+    impl['a, 'b] MaybeUtf8Slice<'a>:as_bytes, Vec<u8>:as_slice;
+    impl['a, 'b] MaybeUtf8:as_bytes, Vec<u8>:as_slice;
+    impl['a, 'b] MaybeUtf8Cow<'a>:as_bytes, Vec<u8>:as_slice;
+    impl['a, 'b] MaybeUtf8Buf:as_bytes, Cow<'b, str>:as_bytes;
+    impl['a, 'b] MaybeUtf8Slice<'a>:as_bytes, Cow<'b, str>:as_bytes;
+    impl['a, 'b] MaybeUtf8:as_bytes, Cow<'b, str>:as_bytes;
+    impl['a, 'b] MaybeUtf8Cow<'a>:as_bytes, Cow<'b, str>:as_bytes;
+    impl['a, const N: usize] MaybeUtf8Buf:as_bytes, [u8; N]:as_slice;
+    impl['a, const N: usize] MaybeUtf8Slice<'a>:as_bytes, [u8; N]:as_slice;
+    impl['a, const N: usize] MaybeUtf8:as_bytes, [u8; N]:as_slice;
+    impl['a, const N: usize] MaybeUtf8Cow<'a>:as_bytes, [u8; N]:as_slice;
+    impl['a, 'b, const N: usize] MaybeUtf8Buf:as_bytes, &'b [u8; N]:as_slice;
+    impl['a, 'b, const N: usize] MaybeUtf8Slice<'a>:as_bytes, &'b [u8; N]:as_slice;
+    impl['a, 'b, const N: usize] MaybeUtf8:as_bytes, &'b [u8; N]:as_slice;
+    impl['a, 'b, const N: usize] MaybeUtf8Cow<'a>:as_bytes, &'b [u8; N]:as_slice;
+}
+
+impl<B: ByteStorage, C: ByteStorage> PartialEq<MaybeUtf8Of<C>> for MaybeUtf8Of<B> {
+    fn eq(&self, other: &MaybeUtf8Of<C>) -> bool { self.as_bytes().eq(other.as_bytes()) }
+}
+
+impl<B: ByteStorage, C: ByteStorage> PartialOrd<MaybeUtf8Of<C>> for MaybeUtf8Of<B> {
+    fn partial_cmp(&self, other: &MaybeUtf8Of<C>) -> Option<Ordering> {
+        self.as_bytes().partial_cmp(other.as_bytes())
+    }
+}
+
+impl Eq for MaybeUtf8Buf {
+}
+
+impl<'a> Eq for MaybeUtf8Slice<'a> {
+}
+
+impl Eq for MaybeUtf8 {
+}
+
+impl<'a> Eq for MaybeUtf8SliceMut<'a> {
+}
+
+impl<'a> Eq for MaybeUtf8Cow<'a> {
+}
+
+impl<B: ByteStorage> Eq for MaybeUtf8Of<B> {
+}
+
+impl Ord for MaybeUtf8Buf {
+    fn cmp(&self, other: &MaybeUtf8Buf) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl<'a> Ord for MaybeUtf8Slice<'a> {
+    fn cmp(&self, other: &MaybeUtf8Slice<'a>) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl<'a> Ord for MaybeUtf8SliceMut<'a> {
+    fn cmp(&self, other: &MaybeUtf8SliceMut<'a>) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl Ord for MaybeUtf8 {
+    fn cmp(&self, other: &MaybeUtf8) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl<'a> Ord for MaybeUtf8Cow<'a> {
+    fn cmp(&self, other: &MaybeUtf8Cow<'a>) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl<B: ByteStorage> Ord for MaybeUtf8Of<B> {
+    fn cmp(&self, other: &MaybeUtf8Of<B>) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+// hashing only depends on the underlying bytes, so it is consistent with `Eq`
+// and a value explicitly encoded in UTF-8 hashes identically to the same bytes.
+impl Hash for MaybeUtf8Buf {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl<'a> Hash for MaybeUtf8Slice<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl<'a> Hash for MaybeUtf8SliceMut<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl Hash for MaybeUtf8 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl<'a> Hash for MaybeUtf8Cow<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl<B: ByteStorage> Hash for MaybeUtf8Of<B> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl Clone for MaybeUtf8Buf {
+    fn clone(&self) -> MaybeUtf8Buf {
+        MaybeUtf8Buf { repr: self.repr.clone() }
+    }
+}
+
+impl From<&'static str> for EncodingHint {
+    fn from(label: &'static str) -> EncodingHint { EncodingHint::Label(Cow::Borrowed(label)) }
+}
+
+impl From<String> for EncodingHint {
+    fn from(label: String) -> EncodingHint { EncodingHint::Label(Cow::Owned(label)) }
+}
+
+impl From<u16> for EncodingHint {
+    fn from(codepage: u16) -> EncodingHint { EncodingHint::CodePage(codepage) }
+}
+
+impl From<String> for MaybeUtf8Buf {
+    fn from(s: String) -> MaybeUtf8Buf { MaybeUtf8Buf::from_str(s) }
+}
+
+impl From<Vec<u8>> for MaybeUtf8Buf {
+    fn from(v: Vec<u8>) -> MaybeUtf8Buf { MaybeUtf8Buf::from_bytes(v) }
+}
+
+impl<'a> From<&'a str> for MaybeUtf8Buf {
+    fn from(s: &'a str) -> MaybeUtf8Buf { MaybeUtf8Slice::from_str(s).to_owned() }
+}
+
+impl<'a> From<&'a [u8]> for MaybeUtf8Buf {
+    fn from(v: &'a [u8]) -> MaybeUtf8Buf { MaybeUtf8Slice::from_bytes(v).to_owned() }
+}
+
+impl<'a> From<Cow<'a, str>> for MaybeUtf8Buf {
+    fn from(s: Cow<'a, str>) -> MaybeUtf8Buf {
+        match s {
+            Cow::Borrowed(s) => From::from(s),
+            Cow::Owned(s) => MaybeUtf8Buf::from_str(s),
+        }
+    }
+}
+
+impl<'a> From<Cow<'a, [u8]>> for MaybeUtf8Buf {
+    fn from(v: Cow<'a, [u8]>) -> MaybeUtf8Buf {
+        match v {
+            Cow::Borrowed(v) => From::from(v),
+            Cow::Owned(v) => MaybeUtf8Buf::from_bytes(v),
+        }
+    }
+}
+
+impl From<char> for MaybeUtf8Buf {
+    fn from(c: char) -> MaybeUtf8Buf {
+        MaybeUtf8Slice::from_str(c.encode_utf8(&mut [0; 4])).to_owned()
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for MaybeUtf8Buf {
+    fn from(v: [u8; N]) -> MaybeUtf8Buf { MaybeUtf8Slice::from_bytes(&v).to_owned() }
+}
+
+impl From<Box<str>> for MaybeUtf8Buf {
+    fn from(s: Box<str>) -> MaybeUtf8Buf { MaybeUtf8Buf::from_str(s.into_string()) }
+}
+
+impl From<Box<[u8]>> for MaybeUtf8Buf {
+    fn from(v: Box<[u8]>) -> MaybeUtf8Buf { MaybeUtf8Buf::from_bytes(v.into_vec()) }
+}
+
+impl<'a> From<MaybeUtf8Slice<'a>> for MaybeUtf8Buf {
+    fn from(s: MaybeUtf8Slice<'a>) -> MaybeUtf8Buf { s.to_owned() }
+}
+
+impl<'a> From<&'a str> for MaybeUtf8Slice<'a> {
+    fn from(s: &'a str) -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::from_str(s) }
+}
+
+impl<'a> From<&'a [u8]> for MaybeUtf8Slice<'a> {
+    fn from(v: &'a [u8]) -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::from_bytes(v) }
+}
+
+impl<'a> From<&'a MaybeUtf8Buf> for MaybeUtf8Slice<'a> {
+    fn from(buf: &'a MaybeUtf8Buf) -> MaybeUtf8Slice<'a> { buf.to_slice() }
+}
+
+impl<'a> From<String> for MaybeUtf8Cow<'a> {
+    fn from(s: String) -> MaybeUtf8Cow<'a> { MaybeUtf8Cow::Owned(MaybeUtf8Buf::from_str(s)) }
+}
+
+impl<'a> From<Vec<u8>> for MaybeUtf8Cow<'a> {
+    fn from(v: Vec<u8>) -> MaybeUtf8Cow<'a> { MaybeUtf8Cow::Owned(MaybeUtf8Buf::from_bytes(v)) }
+}
+
+impl<'a> From<&'a str> for MaybeUtf8Cow<'a> {
+    fn from(s: &'a str) -> MaybeUtf8Cow<'a> { MaybeUtf8Cow::Borrowed(MaybeUtf8Slice::from_str(s)) }
+}
+
+impl<'a> From<&'a [u8]> for MaybeUtf8Cow<'a> {
+    fn from(v: &'a [u8]) -> MaybeUtf8Cow<'a> {
+        MaybeUtf8Cow::Borrowed(MaybeUtf8Slice::from_bytes(v))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for MaybeUtf8Cow<'a> {
+    fn from(s: Cow<'a, str>) -> MaybeUtf8Cow<'a> {
+        match s {
+            Cow::Borrowed(s) => From::from(s),
+            Cow::Owned(s) => From::from(s),
+        }
+    }
+}
+
+impl<'a> From<Cow<'a, [u8]>> for MaybeUtf8Cow<'a> {
+    fn from(v: Cow<'a, [u8]>) -> MaybeUtf8Cow<'a> {
+        match v {
+            Cow::Borrowed(v) => From::from(v),
+            Cow::Owned(v) => From::from(v),
+        }
+    }
+}
+
+impl<'a> From<MaybeUtf8Slice<'a>> for MaybeUtf8Cow<'a> {
+    fn from(s: MaybeUtf8Slice<'a>) -> MaybeUtf8Cow<'a> { MaybeUtf8Cow::Borrowed(s) }
+}
+
+impl<'a> From<MaybeUtf8Buf> for MaybeUtf8Cow<'a> {
+    fn from(buf: MaybeUtf8Buf) -> MaybeUtf8Cow<'a> { MaybeUtf8Cow::Owned(buf) }
+}
+
+impl From<MaybeUtf8Buf> for Cow<'static, [u8]> {
+    fn from(buf: MaybeUtf8Buf) -> Cow<'static, [u8]> { Cow::Owned(buf.into_bytes()) }
+}
+
+impl TryFrom<MaybeUtf8Buf> for String {
+    type Error = FromMaybeUtf8Error;
+    fn try_from(buf: MaybeUtf8Buf) -> Result<String, FromMaybeUtf8Error> {
+        buf.into_str().map_err(|buf| {
+            let error = str::from_utf8(buf.as_bytes()).unwrap_err();
+            FromMaybeUtf8Error { buf, error }
+        })
+    }
+}
+
+impl<'a> TryFrom<MaybeUtf8Slice<'a>> for &'a str {
+    type Error = str::Utf8Error;
+    fn try_from(s: MaybeUtf8Slice<'a>) -> Result<&'a str, str::Utf8Error> { s.try_as_str() }
+}
+
+impl<'a> TryFrom<&MaybeUtf8Slice<'a>> for &'a str {
+    type Error = str::Utf8Error;
+    fn try_from(s: &MaybeUtf8Slice<'a>) -> Result<&'a str, str::Utf8Error> { s.try_as_str() }
+}
+
+impl FromIterator<char> for MaybeUtf8Buf {
+    fn from_iter<I: IntoIterator<Item=char>>(iterator: I) -> MaybeUtf8Buf {
+        MaybeUtf8Buf::from_str(FromIterator::from_iter(iterator))
+    }
+}
+
+impl FromIterator<u8> for MaybeUtf8Buf {
+    fn from_iter<I: IntoIterator<Item=u8>>(iterator: I) -> MaybeUtf8Buf {
+        MaybeUtf8Buf::from_bytes(FromIterator::from_iter(iterator))
+    }
+}
+
+impl IntoIterator for MaybeUtf8Buf {
+    type Item = u8;
+    type IntoIter = vec::IntoIter<u8>;
+    fn into_iter(self) -> vec::IntoIter<u8> { self.into_bytes().into_iter() }
+}
+
+impl<'a> IntoIterator for &'a MaybeUtf8Buf {
+    type Item = u8;
+    type IntoIter = iter::Copied<slice::Iter<'a, u8>>;
+    fn into_iter(self) -> iter::Copied<slice::Iter<'a, u8>> { self.bytes() }
+}
+
+impl<'a> IntoIterator for &'a MaybeUtf8 {
+    type Item = u8;
+    type IntoIter = iter::Copied<slice::Iter<'a, u8>>;
+    fn into_iter(self) -> iter::Copied<slice::Iter<'a, u8>> { self.bytes() }
+}
+
+impl<'a> IntoIterator for MaybeUtf8Slice<'a> {
+    type Item = u8;
+    type IntoIter = iter::Copied<slice::Iter<'a, u8>>;
+    fn into_iter(self) -> iter::Copied<slice::Iter<'a, u8>> { self.bytes() }
+}
+
+impl<'a> IntoIterator for &MaybeUtf8Slice<'a> {
+    type Item = u8;
+    type IntoIter = iter::Copied<slice::Iter<'a, u8>>;
+    fn into_iter(self) -> iter::Copied<slice::Iter<'a, u8>> { self.bytes() }
+}
+
+impl<'a> FromIterator<&'a str> for MaybeUtf8Buf {
+    fn from_iter<I: IntoIterator<Item=&'a str>>(iterator: I) -> MaybeUtf8Buf {
+        let mut buf = MaybeUtf8Buf::new();
+        buf.extend(iterator);
+        buf
+    }
+}
+
+impl<'a> FromIterator<&'a [u8]> for MaybeUtf8Buf {
+    fn from_iter<I: IntoIterator<Item=&'a [u8]>>(iterator: I) -> MaybeUtf8Buf {
+        let mut buf = MaybeUtf8Buf::new();
+        buf.extend(iterator);
+        buf
+    }
+}
+
+impl FromIterator<String> for MaybeUtf8Buf {
+    fn from_iter<I: IntoIterator<Item=String>>(iterator: I) -> MaybeUtf8Buf {
+        let mut buf = MaybeUtf8Buf::new();
+        buf.extend(iterator);
+        buf
+    }
+}
+
+impl FromIterator<Vec<u8>> for MaybeUtf8Buf {
+    fn from_iter<I: IntoIterator<Item=Vec<u8>>>(iterator: I) -> MaybeUtf8Buf {
+        let mut buf = MaybeUtf8Buf::new();
+        buf.extend(iterator);
+        buf
+    }
+}
+
+impl<'a> FromIterator<Cow<'a, str>> for MaybeUtf8Buf {
+    fn from_iter<I: IntoIterator<Item=Cow<'a, str>>>(iterator: I) -> MaybeUtf8Buf {
+        let mut buf = MaybeUtf8Buf::new();
+        buf.extend(iterator);
+        buf
+    }
+}
+
+impl<'a> FromIterator<MaybeUtf8Slice<'a>> for MaybeUtf8Buf {
+    fn from_iter<I: IntoIterator<Item=MaybeUtf8Slice<'a>>>(iterator: I) -> MaybeUtf8Buf {
+        let mut buf = MaybeUtf8Buf::new();
+        buf.extend(iterator);
+        buf
+    }
+}
+
+impl FromIterator<MaybeUtf8Buf> for MaybeUtf8Buf {
+    fn from_iter<I: IntoIterator<Item=MaybeUtf8Buf>>(iterator: I) -> MaybeUtf8Buf {
+        // do not reuse the first value, its encoding hint would leak into the result
+        let mut buf = MaybeUtf8Buf::new();
+        buf.extend(iterator);
+        buf
+    }
+}
+
+impl Extend<char> for MaybeUtf8Buf {
+    fn extend<I: IntoIterator<Item=char>>(&mut self, iterator: I) {
+        let iterator = iterator.into_iter();
+        self.reserve(iterator.size_hint().0);
+        for c in iterator { self.push(c); }
+    }
+}
+
+impl Extend<u8> for MaybeUtf8Buf {
+    fn extend<I: IntoIterator<Item=u8>>(&mut self, iterator: I) {
+        let iterator = iterator.into_iter();
+        self.reserve(iterator.size_hint().0);
+        for b in iterator { self.push_byte(b); }
+    }
+}
+
+impl<'a> Extend<&'a str> for MaybeUtf8Buf {
+    fn extend<I: IntoIterator<Item=&'a str>>(&mut self, iterator: I) {
+        for s in iterator { self.push_str(s); }
+    }
+}
+
+impl<'a> Extend<&'a [u8]> for MaybeUtf8Buf {
+    fn extend<I: IntoIterator<Item=&'a [u8]>>(&mut self, iterator: I) {
+        for v in iterator { self.push_bytes(v); }
+    }
+}
+
+impl Extend<String> for MaybeUtf8Buf {
+    fn extend<I: IntoIterator<Item=String>>(&mut self, iterator: I) {
+        for s in iterator { self.push_str(&s); }
+    }
+}
+
+impl Extend<Vec<u8>> for MaybeUtf8Buf {
+    fn extend<I: IntoIterator<Item=Vec<u8>>>(&mut self, iterator: I) {
+        for v in iterator { self.push_bytes(&v); }
+    }
+}
+
+impl<'a> Extend<Cow<'a, str>> for MaybeUtf8Buf {
+    fn extend<I: IntoIterator<Item=Cow<'a, str>>>(&mut self, iterator: I) {
+        for s in iterator { self.push_str(&s); }
+    }
+}
+
+impl<'a> Extend<MaybeUtf8Slice<'a>> for MaybeUtf8Buf {
+    fn extend<I: IntoIterator<Item=MaybeUtf8Slice<'a>>>(&mut self, iterator: I) {
+        for s in iterator { self.push_maybe_utf8(s); }
+    }
+}
+
+impl Extend<MaybeUtf8Buf> for MaybeUtf8Buf {
+    fn extend<I: IntoIterator<Item=MaybeUtf8Buf>>(&mut self, iterator: I) {
+        for buf in iterator { self.push_maybe_utf8(buf.to_slice()); }
+    }
+}
+
+impl<'a> AddAssign<&'a str> for MaybeUtf8Buf {
+    fn add_assign(&mut self, other: &'a str) { self.push_str(other); }
+}
+
+impl<'a> AddAssign<&'a [u8]> for MaybeUtf8Buf {
+    fn add_assign(&mut self, other: &'a [u8]) { self.push_bytes(other); }
+}
+
+impl<'a, const N: usize> AddAssign<&'a [u8; N]> for MaybeUtf8Buf {
+    fn add_assign(&mut self, other: &'a [u8; N]) { self.push_bytes(other); }
+}
+
+impl<'a> AddAssign<MaybeUtf8Slice<'a>> for MaybeUtf8Buf {
+    fn add_assign(&mut self, other: MaybeUtf8Slice<'a>) { self.push_maybe_utf8(other); }
+}
+
+impl<'a> AddAssign<&'a MaybeUtf8Buf> for MaybeUtf8Buf {
+    fn add_assign(&mut self, other: &'a MaybeUtf8Buf) { self.push_maybe_utf8(other.to_slice()); }
+}
+
+impl<'a> Add<&'a str> for MaybeUtf8Buf {
+    type Output = MaybeUtf8Buf;
+    fn add(mut self, other: &'a str) -> MaybeUtf8Buf { self += other; self }
+}
+
+impl<'a> Add<&'a [u8]> for MaybeUtf8Buf {
+    type Output = MaybeUtf8Buf;
+    fn add(mut self, other: &'a [u8]) -> MaybeUtf8Buf { self += other; self }
+}
+
+impl<'a, const N: usize> Add<&'a [u8; N]> for MaybeUtf8Buf {
+    type Output = MaybeUtf8Buf;
+    fn add(mut self, other: &'a [u8; N]) -> MaybeUtf8Buf { self += other; self }
+}
+
+impl<'a> Add<MaybeUtf8Slice<'a>> for MaybeUtf8Buf {
+    type Output = MaybeUtf8Buf;
+    fn add(mut self, other: MaybeUtf8Slice<'a>) -> MaybeUtf8Buf { self += other; self }
+}
+
+impl<'a> Add<&'a MaybeUtf8Buf> for MaybeUtf8Buf {
+    type Output = MaybeUtf8Buf;
+    fn add(mut self, other: &'a MaybeUtf8Buf) -> MaybeUtf8Buf { self += other; self }
+}
+
+impl Default for MaybeUtf8Buf {
+    fn default() -> MaybeUtf8Buf { MaybeUtf8Buf::new() }
+}
+
+impl<'a> Default for MaybeUtf8Slice<'a> {
+    fn default() -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::new() }
+}
+
+impl<'a> Default for &'a MaybeUtf8 {
+    fn default() -> &'a MaybeUtf8 { MaybeUtf8::from_str("") }
+}
+
+impl<'a> Default for MaybeUtf8Cow<'a> {
+    fn default() -> MaybeUtf8Cow<'a> { MaybeUtf8Cow::new() }
+}
+
+impl<B: ByteStorage + Default> Default for MaybeUtf8Of<B> {
+    fn default() -> MaybeUtf8Of<B> {
+        let bytes = B::default();
+        let utf8 = bytes.as_ref().is_empty();
+        MaybeUtf8Of { bytes, utf8 }
+    }
+}
+
+impl Default for Box<MaybeUtf8> {
+    fn default() -> Box<MaybeUtf8> { From::from(<&MaybeUtf8>::default()) }
+}
+
+macro_rules! define_range_index {
+    ($($range:ty),*) => ($(
+        impl Index<$range> for MaybeUtf8 {
+            type Output = MaybeUtf8;
+            fn index(&self, index: $range) -> &MaybeUtf8 {
+                MaybeUtf8::from_bytes(&self.as_bytes()[index])
+            }
+        }
+        impl<'a> Index<$range> for MaybeUtf8Slice<'a> {
+            type Output = MaybeUtf8;
+            fn index(&self, index: $range) -> &MaybeUtf8 {
+                MaybeUtf8::from_bytes(&self.as_bytes()[index])
+            }
+        }
+    )*)
+}
+
+// other types are indexed via `Deref<Target=MaybeUtf8>`
+define_range_index!(Range<usize>, RangeFrom<usize>, RangeTo<usize>, RangeFull,
+                    RangeInclusive<usize>, RangeToInclusive<usize>);
+
+impl Deref for MaybeUtf8Buf {
+    type Target = MaybeUtf8;
+    fn deref(&self) -> &MaybeUtf8 { MaybeUtf8::from_bytes(self.as_bytes()) }
+}
+
+impl Deref for MaybeUtf8 {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] { &self.inner }
+}
+
+impl<'a> Deref for MaybeUtf8Slice<'a> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl<'a> Deref for MaybeUtf8SliceMut<'a> {
+    type Target = MaybeUtf8;
+    fn deref(&self) -> &MaybeUtf8 { MaybeUtf8::from_bytes(self.as_bytes()) }
+}
+
+impl<'a> Deref for MaybeUtf8Cow<'a> {
+    type Target = MaybeUtf8;
+    fn deref(&self) -> &MaybeUtf8 { MaybeUtf8::from_bytes(self.as_bytes()) }
+}
+
+impl<B: ByteStorage> Deref for MaybeUtf8Of<B> {
+    type Target = MaybeUtf8;
+    fn deref(&self) -> &MaybeUtf8 { MaybeUtf8::from_bytes(self.as_bytes()) }
+}
+
+impl From<MaybeUtf8Buf> for MaybeUtf8Of<Vec<u8>> {
+    fn from(buf: MaybeUtf8Buf) -> MaybeUtf8Of<Vec<u8>> {
+        MaybeUtf8Of { utf8: buf.repr.tag() == Tag::KnownUtf8, bytes: buf.repr.into_vec() }
+    }
+}
+
+impl From<MaybeUtf8Of<Vec<u8>>> for MaybeUtf8Buf {
+    fn from(v: MaybeUtf8Of<Vec<u8>>) -> MaybeUtf8Buf {
+        if v.utf8 {
+            MaybeUtf8Buf::from_str(unsafe { String::from_utf8_unchecked(v.bytes) })
+        } else {
+            MaybeUtf8Buf::from_bytes(v.bytes)
+        }
+    }
+}
+
+impl AsRef<MaybeUtf8> for MaybeUtf8 {
+    fn as_ref(&self) -> &MaybeUtf8 { self }
+}
+
+impl AsRef<MaybeUtf8> for MaybeUtf8Buf {
+    fn as_ref(&self) -> &MaybeUtf8 { self }
+}
+
+impl AsRef<[u8]> for MaybeUtf8 {
+    fn as_ref(&self) -> &[u8] { &self.inner }
+}
+
+impl AsRef<[u8]> for MaybeUtf8Buf {
+    fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl<'a> AsRef<[u8]> for MaybeUtf8Slice<'a> {
+    fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl<'a> AsRef<[u8]> for MaybeUtf8Cow<'a> {
+    fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl<B: ByteStorage> AsRef<[u8]> for MaybeUtf8Of<B> {
+    fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+// Unix paths are arbitrary bytes, so any value converts to them without loss.
+// other platforms can't represent invalid UTF-8 in `OsStr` and have no such conversions.
+macro_rules! define_os_str_as_ref {
+    ($(impl[$($gen:tt)*] $ty:ty;)*) => ($(
+        #[cfg(all(feature = "std", unix))]
+        impl<$($gen)*> AsRef<OsStr> for $ty {
+            fn as_ref(&self) -> &OsStr { OsStr::from_bytes(self.as_bytes()) }
+        }
+
+        #[cfg(all(feature = "std", unix))]
+        impl<$($gen)*> AsRef<Path> for $ty {
+            fn as_ref(&self) -> &Path { Path::new(OsStr::from_bytes(self.as_bytes())) }
+        }
+    )*)
+}
+
+define_os_str_as_ref! {
+    impl[] MaybeUtf8;
+    impl[] MaybeUtf8Buf;
+    impl['a] MaybeUtf8Slice<'a>;
+    impl['a] MaybeUtf8Cow<'a>;
+    impl[B: ByteStorage] MaybeUtf8Of<B>;
+}
+
+// `Hash`, `Eq` and `Ord` only depend on the underlying bytes, so they agree with borrowed ones
+impl Borrow<[u8]> for MaybeUtf8Buf {
+    fn borrow(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl Borrow<MaybeUtf8> for MaybeUtf8Buf {
+    fn borrow(&self) -> &MaybeUtf8 { self }
+}
+
+impl AsRef<MaybeUtf8> for str {
+    fn as_ref(&self) -> &MaybeUtf8 { MaybeUtf8::from_str(self) }
+}
+
+impl AsRef<MaybeUtf8> for String {
+    fn as_ref(&self) -> &MaybeUtf8 { MaybeUtf8::from_str(self) }
+}
+
+impl AsRef<MaybeUtf8> for [u8] {
+    fn as_ref(&self) -> &MaybeUtf8 { MaybeUtf8::from_bytes(self) }
+}
+
+impl AsRef<MaybeUtf8> for Vec<u8> {
+    fn as_ref(&self) -> &MaybeUtf8 { MaybeUtf8::from_bytes(self) }
+}
+
+impl<'a> From<&'a MaybeUtf8> for Box<MaybeUtf8> {
+    fn from(s: &'a MaybeUtf8) -> Box<MaybeUtf8> {
+        let v: Box<[u8]> = From::from(s.as_bytes());
+        From::from(v)
+    }
+}
+
+impl From<Box<[u8]>> for Box<MaybeUtf8> {
+    fn from(v: Box<[u8]>) -> Box<MaybeUtf8> {
+        unsafe { Box::from_raw(Box::into_raw(v) as *mut MaybeUtf8) }
+    }
+}
+
+impl From<Box<str>> for Box<MaybeUtf8> {
+    fn from(s: Box<str>) -> Box<MaybeUtf8> {
+        unsafe { Box::from_raw(Box::into_raw(s) as *mut MaybeUtf8) }
+    }
+}
+
+impl From<MaybeUtf8Buf> for Box<MaybeUtf8> {
+    fn from(s: MaybeUtf8Buf) -> Box<MaybeUtf8> {
+        From::from(s.into_bytes().into_boxed_slice())
+    }
+}
+
+impl From<Box<MaybeUtf8>> for Box<[u8]> {
+    fn from(s: Box<MaybeUtf8>) -> Box<[u8]> {
+        unsafe { Box::from_raw(Box::into_raw(s) as *mut [u8]) }
+    }
+}
+
+impl From<Box<MaybeUtf8>> for MaybeUtf8Buf {
+    fn from(s: Box<MaybeUtf8>) -> MaybeUtf8Buf {
+        let v: Box<[u8]> = From::from(s);
+        match String::from_utf8(v.into_vec()) {
+            Ok(s) => MaybeUtf8Buf::from_str(s),
+            Err(e) => {
+                let tag = if e.utf8_error().error_len().is_none() { Tag::Incomplete }
+                          else { Tag::KnownInvalid };
+                MaybeUtf8Buf { repr: Repr::Heap(e.into_bytes(), TagCell::new(tag)) }
+            }
+        }
+    }
+}
+
+impl Clone for Box<MaybeUtf8> {
+    fn clone(&self) -> Box<MaybeUtf8> { From::from(&**self) }
+}
+
+impl<'a> From<&'a MaybeUtf8> for Rc<MaybeUtf8> {
+    fn from(s: &'a MaybeUtf8) -> Rc<MaybeUtf8> {
+        let v: Rc<[u8]> = From::from(s.as_bytes());
+        unsafe { Rc::from_raw(Rc::into_raw(v) as *const MaybeUtf8) }
+    }
+}
+
+impl<'a> From<&'a MaybeUtf8> for Arc<MaybeUtf8> {
+    fn from(s: &'a MaybeUtf8) -> Arc<MaybeUtf8> {
+        let v: Arc<[u8]> = From::from(s.as_bytes());
+        unsafe { Arc::from_raw(Arc::into_raw(v) as *const MaybeUtf8) }
+    }
+}
+
+impl ToOwned for MaybeUtf8 {
+    type Owned = MaybeUtf8Buf;
+    fn to_owned(&self) -> MaybeUtf8Buf { MaybeUtf8::to_owned(self) }
+}
+
+impl<'a> From<&'a MaybeUtf8> for Cow<'a, MaybeUtf8> {
+    fn from(s: &'a MaybeUtf8) -> Cow<'a, MaybeUtf8> { Cow::Borrowed(s) }
+}
+
+impl<'a> From<MaybeUtf8Buf> for Cow<'a, MaybeUtf8> {
+    fn from(buf: MaybeUtf8Buf) -> Cow<'a, MaybeUtf8> { Cow::Owned(buf) }
+}
+
+impl<'a> From<Cow<'a, MaybeUtf8>> for MaybeUtf8Buf {
+    fn from(s: Cow<'a, MaybeUtf8>) -> MaybeUtf8Buf { s.into_owned() }
+}
+
+impl fmt::Debug for MaybeUtf8Buf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_slice(), f)
+    }
+}
+
+impl fmt::Display for MaybeUtf8Buf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.as_cow_lossy(), f)
+    }
+}
+
+impl fmt::Write for MaybeUtf8Buf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push(c);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for MaybeUtf8Buf {
+    // the validity is tracked across chunks, so the value is explicitly encoded in UTF-8
+    // whenever the bytes written so far are valid in UTF-8, even when a chunk splits a character.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.push_bytes_streaming(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for MaybeUtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_slice(), f)
+    }
+}
+
+impl fmt::Display for MaybeUtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.as_cow_lossy(), f)
+    }
+}
+
+impl<B: ByteStorage> fmt::Debug for MaybeUtf8Of<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_slice(), f)
+    }
+}
+
+impl<B: ByteStorage> fmt::Display for MaybeUtf8Of<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_slice(), f)
+    }
+}
+
+impl<'a> fmt::Debug for MaybeUtf8SliceMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_slice(), f)
+    }
+}
+
+impl<'a> fmt::Display for MaybeUtf8SliceMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_slice(), f)
+    }
+}
+
+impl<'a> fmt::Debug for MaybeUtf8Cow<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_slice(), f)
+    }
+}
+
+impl<'a> fmt::Display for MaybeUtf8Cow<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_slice(), f)
+    }
+}
+
+impl<'a> fmt::Debug for MaybeUtf8Slice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.inner {
+            Slice::Utf8(ref s) => fmt::Debug::fmt(s, f),
+            Slice::Bytes(v) => {
+                write!(f, "b\"")?;
+                for &c in v.iter() {
+                    match c {
+                        b'\t' => write!(f, "\\t")?,
+                        b'\r' => write!(f, "\\r")?,
+                        b'\n' => write!(f, "\\n")?,
+                        b'\\' => write!(f, "\\\\")?,
+                        b'\'' => write!(f, "\\'")?,
+                        b'"'  => write!(f, "\\\"")?,
+                        b'\x20' ..= b'\x7e' => write!(f, "{}", c as char)?,
+                        _ => write!(f, "\\x{}{}",
+                                    char::from_digit((c as u32) >> 4, 16).unwrap(),
+                                    char::from_digit((c as u32) & 0xf, 16).unwrap())?,
+                    }
+                }
+                write!(f, "\"")
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Display for MaybeUtf8Slice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.inner {
+            Slice::Utf8(ref s) => fmt::Display::fmt(s, f),
+            Slice::Bytes(v) => fmt::Display::fmt(&String::from_utf8_lossy(v), f),
+        }
+    }
+}
+
+// writes the underlying bytes as hexadecimal digits, prefixed with `0x` for `{:#x}`.
+// the width, fill and alignment apply as like integers.
+fn fmt_hex(v: &[u8], f: &mut fmt::Formatter, upper: bool) -> fmt::Result {
+    let digits: &[u8; 16] = if upper { b"0123456789ABCDEF" } else { b"0123456789abcdef" };
+    let mut buf = String::with_capacity(v.len() * 2);
+    for &b in v {
+        buf.push(digits[(b >> 4) as usize] as char);
+        buf.push(digits[(b & 0xf) as usize] as char);
+    }
+    f.pad_integral(true, "0x", &buf)
+}
+
+macro_rules! define_hex_fmt {
+    ($(impl[$($gen:tt)*] $ty:ty;)*) => ($(
+        impl<$($gen)*> fmt::LowerHex for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt_hex(self.as_bytes(), f, false)
+            }
+        }
+
+        impl<$($gen)*> fmt::UpperHex for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt_hex(self.as_bytes(), f, true)
+            }
+        }
+    )*)
+}
+
+define_hex_fmt! {
+    impl[] MaybeUtf8;
+    impl[] MaybeUtf8Buf;
+    impl['a] MaybeUtf8Slice<'a>;
+    impl['a] MaybeUtf8SliceMut<'a>;
+    impl['a] MaybeUtf8Cow<'a>;
+    impl[B: ByteStorage] MaybeUtf8Of<B>;
+    impl[const N: usize] MaybeUtf8Array<N>;
+}
+
+// validates `v[start..]`, assuming that `v[..start]` is valid and `start` is at a char boundary.
+fn validate_from(v: &[u8], start: usize) -> Tag {
+    match str::from_utf8(&v[start..]) {
+        Ok(_) => Tag::KnownValid,
+        Err(e) if e.error_len().is_none() => Tag::Incomplete,
+        Err(_) => Tag::KnownInvalid,
+    }
+}
+
+// same to `String::from_utf8_lossy` but replaces invalid UTF-8 sequences with `replacement`.
+fn from_utf8_lossy_with<'a>(v: &'a [u8], replacement: &str) -> Cow<'a, str> {
+    if let Ok(s) = str::from_utf8(v) {
+        return Cow::Borrowed(s);
+    }
+    let mut ret = String::with_capacity(v.len());
+    for chunk in v.utf8_chunks() {
+        ret.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            ret.push_str(replacement);
+        }
+    }
+    Cow::Owned(ret)
+}
+
+// returns the number of characters in `v` decoded as like `String::from_utf8_lossy`.
+fn char_count_lossy(v: &[u8]) -> usize {
+    v.utf8_chunks().map(|chunk| {
+        chunk.valid().chars().count() + if chunk.invalid().is_empty() { 0 } else { 1 }
+    }).sum()
+}
+
+// returns the number of invalid UTF-8 sequences in `v`.
+fn count_invalid_sequences(v: &[u8]) -> usize {
+    v.utf8_chunks().filter(|chunk| !chunk.invalid().is_empty()).count()
+}
+
+// applies `f` to each part of `v` valid in UTF-8, keeping invalid UTF-8 sequences as they are.
+fn map_utf8_chunks<F: FnMut(&str) -> String>(v: &[u8], mut f: F) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(v.len());
+    for chunk in v.utf8_chunks() {
+        ret.extend_from_slice(f(chunk.valid()).as_bytes());
+        ret.extend_from_slice(chunk.invalid());
+    }
+    ret
+}
+
+// normalizes each part of `s` valid in UTF-8 with `normalize`, unless `is_normalized` holds.
+// finds adjacent char boundaries `i < j` of `s` where `pred(i)` holds and `pred(j)` doesn't,
+// given that `pred(0)` holds and `pred(s.len())` doesn't. it does a binary search,
+// since widths of characters don't add up to the width of string (e.g. for emoji sequences).
+#[cfg(feature = "unicode-width")]
+fn partition_char_boundaries<F: FnMut(usize) -> bool>(s: &str, mut pred: F) -> (usize, usize) {
+    let (mut lo, mut hi) = (0, s.len());
+    loop {
+        let mut mid = lo + (hi - lo) / 2;
+        while !s.is_char_boundary(mid) { mid -= 1; }
+        if mid == lo {
+            mid += s[lo..].chars().next().map_or(0, char::len_utf8);
+            if mid == hi { return (lo, hi); }
+        }
+        if pred(mid) { lo = mid; } else { hi = mid; }
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+fn normalized<F, G>(s: MaybeUtf8Slice, is_normalized: F, mut normalize: G) -> MaybeUtf8Cow
+        where F: Fn(&str) -> bool, G: FnMut(&str) -> String {
+    if s.as_bytes().utf8_chunks().all(|chunk| is_normalized(chunk.valid())) {
+        return MaybeUtf8Cow::Borrowed(s);
+    }
+    MaybeUtf8Cow::Owned(match s.inner {
+        Slice::Utf8(v) => MaybeUtf8Buf::from_str(normalize(v)),
+        Slice::Bytes(v) => MaybeUtf8Buf::from_bytes(map_utf8_chunks(v, normalize)),
+    })
+}
+
+// returns `true` if the character may change the state of the terminal or how text is shown.
+fn is_unsafe_for_terminal(c: char) -> bool {
+    c.is_control() || matches!(c, '\u{61c}' | '\u{200e}' | '\u{200f}' |
+                                  '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+// removes ANSI escape sequences from `v`, including unterminated ones at the end.
+fn strip_ansi(v: &[u8]) -> Vec<u8> {
+    const ESC: u8 = 0x1b;
+    const BEL: u8 = 0x07;
+    let mut ret = Vec::with_capacity(v.len());
+    let mut i = 0;
+    while i < v.len() {
+        if v[i] != ESC {
+            ret.push(v[i]);
+            i += 1;
+            continue;
+        }
+        i += 1;
+        match v.get(i) {
+            // CSI: parameter and intermediate bytes followed by a final byte
+            Some(b'[') => {
+                i += 1;
+                while v.get(i).is_some_and(|b| (0x20..0x40).contains(b)) { i += 1; }
+                if v.get(i).is_some_and(|b| (0x40..0x7f).contains(b)) { i += 1; }
+            }
+            // OSC, DCS, SOS, PM and APC: a string terminated by ST (`ESC \`) or BEL
+            Some(b']' | b'P' | b'X' | b'^' | b'_') => {
+                i += 1;
+                while v.get(i).is_some_and(|&b| b != ESC && b != BEL) { i += 1; }
+                if v.get(i) == Some(&BEL) {
+                    i += 1;
+                } else if v.get(i + 1) == Some(&b'\\') {
+                    i += 2;
+                }
+            }
+            // others: intermediate bytes followed by a final byte
+            _ => {
+                while v.get(i).is_some_and(|b| (0x20..0x30).contains(b)) { i += 1; }
+                if v.get(i).is_some_and(|b| (0x30..0x7f).contains(b)) { i += 1; }
+            }
+        }
+    }
+    ret
+}
+
+// returns `true` if HFS+ doesn't decompose the character.
+#[cfg(feature = "unicode-normalization")]
+fn is_hfs_excluded(c: char) -> bool {
+    matches!(c, '\u{2000}'..='\u{2fff}' | '\u{f900}'..='\u{faff}' | '\u{2f800}'..='\u{2faff}')
+}
+
+// decomposes `s` to NFD, except for characters which HFS+ doesn't decompose.
+#[cfg(feature = "unicode-normalization")]
+fn hfs_decomposed(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for piece in s.split_inclusive(is_hfs_excluded) {
+        let (body, excluded) = match piece.char_indices().next_back() {
+            Some((i, c)) if is_hfs_excluded(c) => (&piece[..i], Some(c)),
+            _ => (piece, None),
+        };
+        ret.extend(body.nfd());
+        ret.extend(excluded);
+    }
+    ret
+}
+
+// returns the byte offset of the first occurrence of `needle` in `haystack`.
+#[cfg(feature = "memchr")]
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    match *needle {
+        [b] => memchr::memchr(b, haystack),
+        _ => memchr::memmem::find(haystack, needle),
+    }
+}
+
+#[cfg(not(feature = "memchr"))]
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    match *needle {
+        [] => Some(0),
+        [b] => haystack.iter().position(|&c| c == b),
+        _ => haystack.windows(needle.len()).position(|w| w == needle),
+    }
+}
+
+// returns the byte offset of the last occurrence of `needle` in `haystack`.
+#[cfg(feature = "memchr")]
+fn rfind_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    match *needle {
+        [b] => memchr::memrchr(b, haystack),
+        _ => memchr::memmem::rfind(haystack, needle),
+    }
+}
+
+#[cfg(not(feature = "memchr"))]
+fn rfind_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    match *needle {
+        [] => Some(haystack.len()),
+        [b] => haystack.iter().rposition(|&c| c == b),
+        _ => haystack.windows(needle.len()).rposition(|w| w == needle),
+    }
+}
+
+// returns an iterator over case-folded characters and remaining bytes from `v`,
+// where bytes are mapped past the last code point so that they are ordered after characters.
+#[cfg(feature = "unicode-case-mapping")]
+fn case_folded(v: &[u8]) -> impl Iterator<Item=u32> + '_ {
+    v.utf8_chunks().flat_map(|chunk| {
+        let chars = chunk.valid().chars().map(|c| {
+            unicode_case_mapping::case_folded(c).map_or(c as u32, |c| c.get())
+        });
+        chars.chain(chunk.invalid().iter().map(|&b| char::MAX as u32 + 1 + b as u32))
+    })
+}
+
+// applies Unicode simple case folding to `s`.
+#[cfg(feature = "unicode-case-mapping")]
+fn case_fold_str(s: &str) -> String {
+    s.chars().map(|c| {
+        unicode_case_mapping::case_folded(c).and_then(|c| char::from_u32(c.get())).unwrap_or(c)
+    }).collect()
+}
+
+// compares two byte slices in the natural order, falling back to the bytewise order.
+fn cmp_natural(a: &[u8], b: &[u8]) -> Ordering {
+    fn digits(v: &[u8]) -> usize {
+        v.iter().position(|c| !c.is_ascii_digit()).unwrap_or(v.len())
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let (m, n) = (digits(&a[i..]), digits(&b[j..]));
+        if m > 0 && n > 0 {
+            // compare numeric values, i.e. the number of significant digits and then digits
+            let x = &a[i..i + m];
+            let y = &b[j..j + n];
+            let x = &x[x.iter().position(|&c| c != b'0').unwrap_or(m)..];
+            let y = &y[y.iter().position(|&c| c != b'0').unwrap_or(n)..];
+            match x.len().cmp(&y.len()).then_with(|| x.cmp(y)) {
+                Ordering::Equal => { i += m; j += n; }
+                ord => return ord,
+            }
+        } else {
+            match a[i].cmp(&b[j]) {
+                Ordering::Equal => { i += 1; j += 1; }
+                ord => return ord,
+            }
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j)).then_with(|| a.cmp(b))
+}
+
+// returns `true` if the byte offset `i` does not split any UTF-8 sequence in `v`.
+fn is_char_boundary(v: &[u8], i: usize) -> bool {
+    i == 0 || i == v.len() || (i < v.len() && (v[i] & 0xc0) != 0x80)
+}
+
+// converts a `RangeBounds` into a pair of byte offsets, or `None` if it is out of bounds.
+fn checked_range<R: RangeBounds<usize>>(range: R, len: usize) -> Option<(usize, usize)> {
+    let begin = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i.checked_add(1)?,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+    if begin <= end && end <= len { Some((begin, end)) } else { None }
+}
+
+// converts a `RangeBounds` into a pair of byte offsets, panicking if it is out of bounds.
+fn resolve_range<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let begin = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) =>
+            i.checked_add(1).expect("attempted to index from after maximum usize"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) =>
+            i.checked_add(1).expect("attempted to index up to maximum usize"),
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+    assert!(begin <= end, "range start {} is greater than end {}", begin, end);
+    assert!(end <= len, "range end {} is out of bounds of length {}", end, len);
+    (begin, end)
+}
+
+/// Concatenates given slices into a new `MaybeUtf8Buf`.
+/// The result is explicitly encoded in UTF-8 only when every slice is.
+pub fn concat(pieces: &[MaybeUtf8Slice]) -> MaybeUtf8Buf {
+    join(pieces, MaybeUtf8Slice::new())
+}
+
+/// Concatenates given slices with a separator in between into a new `MaybeUtf8Buf`.
+/// The result is explicitly encoded in UTF-8 only when every slice
+/// (and the separator, if used) is.
+pub fn join(pieces: &[MaybeUtf8Slice], sep: MaybeUtf8Slice) -> MaybeUtf8Buf {
+    let len = pieces.iter().map(|s| s.len()).sum::<usize>() +
+              sep.len() * pieces.len().saturating_sub(1);
+    let mut buf = MaybeUtf8Buf::with_capacity(len);
+    for (i, &s) in pieces.iter().enumerate() {
+        if i > 0 { buf.push_maybe_utf8(sep); }
+        buf.push_maybe_utf8(s);
+    }
+    buf
+}
+
+/// Reads all bytes from given reader and appends them to given `MaybeUtf8Buf`,
+/// returning the number of bytes read.
+/// The buffer is explicitly encoded in UTF-8 if the resulting bytes are valid in UTF-8,
+/// which is checked while reading as like `MaybeUtf8Buf::push_bytes_streaming`.
+/// Otherwise it is no longer explicitly encoded in UTF-8.
+///
+/// If an error occurs, the bytes read so far are still appended to the buffer.
+///
+/// This is only available with the `std` feature.
+#[cfg(feature = "std")]
+pub fn read_to_maybe_utf8<R: io::Read>(mut reader: R, buf: &mut MaybeUtf8Buf) -> io::Result<usize> {
+    let mut chunk = [0; 8192];
+    let mut read = 0;
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        buf.push_bytes_streaming(&chunk[..n]);
+        read += n;
+    }
+    Ok(read)
+}
+
+/// A helper trait for uniformly creating `MaybeUtf8Buf`, `MaybeUtf8Slice`
+/// or `MaybeUtf8Cow` values.
+pub trait IntoMaybeUtf8<T> {
+    /// Converts given value into either `MaybeUtf8Buf`, `MaybeUtf8Slice` or `MaybeUtf8Cow`.
+    fn into_maybe_utf8(self) -> T;
+}
+
+impl IntoMaybeUtf8<MaybeUtf8Buf> for String {
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf { MaybeUtf8Buf::from_str(self) }
+}
+
+impl IntoMaybeUtf8<MaybeUtf8Buf> for Vec<u8> {
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf { MaybeUtf8Buf::from_bytes(self) }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8Slice<'a>> for &'a String {
+    fn into_maybe_utf8(self) -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::from_str(self) }
+}
 
 impl<'a> IntoMaybeUtf8<MaybeUtf8Slice<'a>> for &'a str {
     fn into_maybe_utf8(self) -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::from_str(self) }
@@ -417,3 +4684,108 @@ impl<'a> IntoMaybeUtf8<MaybeUtf8Slice<'a>> for &'a [u8] {
     fn into_maybe_utf8(self) -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::from_bytes(self) }
 }
 
+impl<'a> IntoMaybeUtf8<MaybeUtf8Slice<'a>> for &'a MaybeUtf8 {
+    fn into_maybe_utf8(self) -> MaybeUtf8Slice<'a> { self.to_slice() }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8Slice<'a>> for &'a MaybeUtf8Buf {
+    fn into_maybe_utf8(self) -> MaybeUtf8Slice<'a> { self.to_slice() }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8Slice<'a>> for MaybeUtf8Slice<'a> {
+    fn into_maybe_utf8(self) -> MaybeUtf8Slice<'a> { self }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8Cow<'a>> for MaybeUtf8Buf {
+    fn into_maybe_utf8(self) -> MaybeUtf8Cow<'a> { MaybeUtf8Cow::Owned(self) }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8Cow<'a>> for MaybeUtf8Slice<'a> {
+    fn into_maybe_utf8(self) -> MaybeUtf8Cow<'a> { MaybeUtf8Cow::Borrowed(self) }
+}
+
+impl IntoMaybeUtf8<MaybeUtf8Buf> for Box<str> {
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf { MaybeUtf8Buf::from_str(self.into_string()) }
+}
+
+impl IntoMaybeUtf8<MaybeUtf8Buf> for Box<[u8]> {
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf { MaybeUtf8Buf::from_bytes(self.into_vec()) }
+}
+
+impl IntoMaybeUtf8<MaybeUtf8Buf> for char {
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf { From::from(self) }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8Buf> for Cow<'a, str> {
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf { From::from(self) }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8Buf> for Cow<'a, [u8]> {
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf { From::from(self) }
+}
+
+impl<'a, const N: usize> IntoMaybeUtf8<MaybeUtf8Slice<'a>> for &'a [u8; N] {
+    fn into_maybe_utf8(self) -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::from_bytes(self) }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8Cow<'a>> for Cow<'a, str> {
+    fn into_maybe_utf8(self) -> MaybeUtf8Cow<'a> { From::from(self) }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8Cow<'a>> for Cow<'a, [u8]> {
+    fn into_maybe_utf8(self) -> MaybeUtf8Cow<'a> { From::from(self) }
+}
+
+// OS strings are converted without loss where possible. Unix uses the raw bytes,
+// Windows uses WTF-8 (UTF-8 with unpaired surrogates encoded as ordinary code points)
+// and other platforms replace anything not valid in Unicode with U+FFFD.
+#[cfg(feature = "std")]
+impl IntoMaybeUtf8<MaybeUtf8Buf> for OsString {
+    #[cfg(unix)]
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf { MaybeUtf8Buf::from_bytes(self.into_vec()) }
+
+    #[cfg(windows)]
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf {
+        match self.into_string() {
+            Ok(s) => MaybeUtf8Buf::from_str(s),
+            Err(s) => MaybeUtf8Buf::from(MaybeUtf16Buf::from_wide(s.encode_wide().collect())),
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn into_maybe_utf8(self) -> MaybeUtf8Buf {
+        match self.into_string() {
+            Ok(s) => MaybeUtf8Buf::from_str(s),
+            Err(s) => MaybeUtf8Buf::from_str(s.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> IntoMaybeUtf8<MaybeUtf8Cow<'a>> for &'a OsStr {
+    #[cfg(unix)]
+    fn into_maybe_utf8(self) -> MaybeUtf8Cow<'a> {
+        MaybeUtf8Cow::Borrowed(MaybeUtf8Slice::from_bytes(self.as_bytes()))
+    }
+
+    #[cfg(windows)]
+    fn into_maybe_utf8(self) -> MaybeUtf8Cow<'a> {
+        match self.to_str() {
+            Some(s) => MaybeUtf8Cow::Borrowed(MaybeUtf8Slice::from_str(s)),
+            None => MaybeUtf8Cow::Owned(
+                MaybeUtf8Buf::from(MaybeUtf16Buf::from_wide(self.encode_wide().collect()))),
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn into_maybe_utf8(self) -> MaybeUtf8Cow<'a> { From::from(self.to_string_lossy()) }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl<'a> IntoMaybeUtf8<MaybeUtf8Slice<'a>> for &'a OsStr {
+    fn into_maybe_utf8(self) -> MaybeUtf8Slice<'a> { MaybeUtf8Slice::from_bytes(self.as_bytes()) }
+}
+
+impl<'a> IntoMaybeUtf8<MaybeUtf8SliceMut<'a>> for &'a mut [u8] {
+    fn into_maybe_utf8(self) -> MaybeUtf8SliceMut<'a> { MaybeUtf8SliceMut::from_bytes(self) }
+}
@@ -0,0 +1,112 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Byte patterns for searching in `MaybeUtf8` values.
+
+use core::slice;
+use alloc::string::String;
+
+use crate::{MaybeUtf8, MaybeUtf8Buf, MaybeUtf8Slice, find_bytes, rfind_bytes};
+
+/// A pattern which can be matched against the underlying bytes of `MaybeUtf8` values,
+/// similar to `std::str::pattern::Pattern`.
+///
+/// It is implemented for string slices, `u8` slices, bytes, characters, `MaybeUtf8Slice`
+/// and byte predicates (`FnMut(u8) -> bool`), the last of which matches a single byte.
+pub trait BytePattern {
+    /// Returns the byte range of the first match in given bytes, if any.
+    fn find_in(&mut self, haystack: &[u8]) -> Option<(usize, usize)>;
+
+    /// Returns the byte range of the last match in given bytes, if any.
+    fn rfind_in(&mut self, haystack: &[u8]) -> Option<(usize, usize)>;
+
+    /// Returns the byte length of the match at the start of given bytes, if any.
+    fn prefix_len_in(&mut self, haystack: &[u8]) -> Option<usize>;
+
+    /// Returns the byte length of the match at the end of given bytes, if any.
+    fn suffix_len_in(&mut self, haystack: &[u8]) -> Option<usize>;
+}
+
+macro_rules! define_sequence_pattern {
+    ($(impl[$($gen:tt)*] $ty:ty: |$this:ident| $bytes:expr;)*) => ($(
+        impl<$($gen)*> BytePattern for $ty {
+            fn find_in(&mut self, haystack: &[u8]) -> Option<(usize, usize)> {
+                let $this = &*self;
+                find_sequence(haystack, $bytes)
+            }
+
+            fn rfind_in(&mut self, haystack: &[u8]) -> Option<(usize, usize)> {
+                let $this = &*self;
+                rfind_sequence(haystack, $bytes)
+            }
+
+            fn prefix_len_in(&mut self, haystack: &[u8]) -> Option<usize> {
+                let $this = &*self;
+                haystack.starts_with($bytes).then_some($bytes.len())
+            }
+
+            fn suffix_len_in(&mut self, haystack: &[u8]) -> Option<usize> {
+                let $this = &*self;
+                haystack.ends_with($bytes).then_some($bytes.len())
+            }
+        }
+    )*)
+}
+
+define_sequence_pattern! {
+    impl[] &str: |s| s.as_bytes();
+    impl[] &String: |s| s.as_bytes();
+    impl[] &[u8]: |v| v;
+    impl[const N: usize] &[u8; N]: |v| &v[..];
+    impl[] u8: |b| slice::from_ref(b);
+    impl[] char: |c| c.encode_utf8(&mut [0; 4]).as_bytes();
+    impl['a] MaybeUtf8Slice<'a>: |s| s.as_bytes();
+    impl['a, 'b] &'b MaybeUtf8Slice<'a>: |s| s.as_bytes();
+    impl[] &MaybeUtf8Buf: |s| s.as_bytes();
+    impl[] &MaybeUtf8: |s| s.as_bytes();
+}
+
+impl<F: FnMut(u8) -> bool> BytePattern for F {
+    fn find_in(&mut self, haystack: &[u8]) -> Option<(usize, usize)> {
+        haystack.iter().position(|&b| self(b)).map(|i| (i, i + 1))
+    }
+
+    fn rfind_in(&mut self, haystack: &[u8]) -> Option<(usize, usize)> {
+        haystack.iter().rposition(|&b| self(b)).map(|i| (i, i + 1))
+    }
+
+    fn prefix_len_in(&mut self, haystack: &[u8]) -> Option<usize> {
+        haystack.first().filter(|&&b| self(b)).map(|_| 1)
+    }
+
+    fn suffix_len_in(&mut self, haystack: &[u8]) -> Option<usize> {
+        haystack.last().filter(|&&b| self(b)).map(|_| 1)
+    }
+}
+
+fn find_sequence(haystack: &[u8], needle: &[u8]) -> Option<(usize, usize)> {
+    find_bytes(haystack, needle).map(|i| (i, i + needle.len()))
+}
+
+fn rfind_sequence(haystack: &[u8], needle: &[u8]) -> Option<(usize, usize)> {
+    rfind_bytes(haystack, needle).map(|i| (i, i + needle.len()))
+}
+
+// returns the byte offset right after the repeated matches of `pat` at the start of `v`.
+pub(crate) fn trim_start_offset<P: BytePattern>(v: &[u8], pat: &mut P) -> usize {
+    let mut begin = 0;
+    while let Some(n @ 1..) = pat.prefix_len_in(&v[begin..]) {
+        begin += n;
+    }
+    begin
+}
+
+// returns the byte offset right before the repeated matches of `pat` at the end of `v`.
+pub(crate) fn trim_end_offset<P: BytePattern>(v: &[u8], pat: &mut P) -> usize {
+    let mut end = v.len();
+    while let Some(n @ 1..) = pat.suffix_len_in(&v[..end]) {
+        end -= n;
+    }
+    end
+}
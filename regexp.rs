@@ -0,0 +1,78 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Regular expression matches over `MaybeUtf8Slice`.
+
+use regex::bytes::{self, Regex};
+
+use crate::MaybeUtf8Slice;
+
+/// An iterator over non-overlapping regex matches and their byte offsets.
+/// This is returned by `MaybeUtf8Slice::find_iter`.
+///
+/// Each match is explicitly encoded in UTF-8 only when the original value is
+/// and both ends of the match are at char boundaries.
+/// This is only available with the `regex` feature.
+#[derive(Debug)]
+pub struct RegexMatches<'r, 'a> {
+    haystack: MaybeUtf8Slice<'a>,
+    matches: bytes::Matches<'r, 'a>,
+}
+
+/// Capture groups of a single regex match.
+/// This is returned by `MaybeUtf8Slice::captures`.
+///
+/// Each group is explicitly encoded in UTF-8 only when the original value is
+/// and both ends of the group are at char boundaries.
+/// This is only available with the `regex` feature.
+#[derive(Debug)]
+pub struct RegexCaptures<'a> {
+    haystack: MaybeUtf8Slice<'a>,
+    captures: bytes::Captures<'a>,
+}
+
+impl<'r, 'a> RegexMatches<'r, 'a> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>, re: &'r Regex) -> RegexMatches<'r, 'a> {
+        RegexMatches { haystack, matches: re.find_iter(haystack.as_bytes()) }
+    }
+}
+
+impl<'r, 'a> Iterator for RegexMatches<'r, 'a> {
+    type Item = (usize, MaybeUtf8Slice<'a>);
+
+    fn next(&mut self) -> Option<(usize, MaybeUtf8Slice<'a>)> {
+        let m = self.matches.next()?;
+        Some((m.start(), self.haystack.get(m.range()).unwrap()))
+    }
+}
+
+impl<'a> RegexCaptures<'a> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>, re: &Regex) -> Option<RegexCaptures<'a>> {
+        let captures = re.captures(haystack.as_bytes())?;
+        Some(RegexCaptures { haystack, captures })
+    }
+
+    /// Returns the capture group at given index, or `None` if it didn't participate
+    /// in the match. The group 0 always corresponds to the entire match.
+    pub fn get(&self, i: usize) -> Option<MaybeUtf8Slice<'a>> {
+        self.captures.get(i).map(|m| self.haystack.get(m.range()).unwrap())
+    }
+
+    /// Returns the capture group with given name, or `None` if it didn't participate
+    /// in the match or there is no such group.
+    pub fn name(&self, name: &str) -> Option<MaybeUtf8Slice<'a>> {
+        self.captures.name(name).map(|m| self.haystack.get(m.range()).unwrap())
+    }
+
+    /// Returns the number of capture groups including the group 0,
+    /// whether or not they participated in the match.
+    pub fn len(&self) -> usize {
+        self.captures.len()
+    }
+
+    /// Returns `false`, as there is always the group 0.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,531 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Serde support for `MaybeUtf8` values.
+//!
+//! A value explicitly encoded in UTF-8 is serialized as a string.
+//! Otherwise it is serialized as bytes in binary formats, and as a map with a single entry
+//! `base64` in human-readable formats like JSON, so that it survives round-trips.
+//! Deserializers accept strings, bytes, sequences of bytes and such maps alike,
+//! and only strings result in values explicitly encoded in UTF-8.
+//! Use `compact` or `readable` submodules to choose a representation regardless of the format,
+//! or `byte_seq` for formats without bytes where `serialize_bytes` is not appropriate.
+//! `tagged` represents a value as a struct with an explicit `utf8` flag instead,
+//! which is easier to consume from other languages.
+//!
+//! Formats which are not self-describing (e.g. bincode) can't tell strings from bytes,
+//! so values deserialized from them with the default or `compact` representation
+//! are never explicitly encoded in UTF-8, even when they have been serialized as strings.
+//! Use `tagged` to retain the distinction in such formats.
+//!
+//! `MaybeUtf8Slice` and `&MaybeUtf8` are deserialized without copying,
+//! and fail unless the deserializer can lend the data (e.g. JSON strings without escapes).
+//! `MaybeUtf8Cow` borrows the data if possible and makes an owned value otherwise.
+//!
+//! Submodules of this module can be used with `#[serde(with = "...")]`
+//! to represent a field differently, which is useful for text formats without bytes.
+//! They serialize anything implementing `AsRef<[u8]>`, treating bytes valid in UTF-8 as strings,
+//! and deserialize into anything implementing `From<MaybeUtf8Buf>`.
+//! `tagged` instead serializes anything whose reference converts into `MaybeUtf8Slice`,
+//! so that it can tell whether the value has been explicitly encoded in UTF-8.
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Entry {
+//!     #[serde(with = "maybe_utf8::serde::base64")]
+//!     name: MaybeUtf8Buf,
+//! }
+//! ```
+//!
+//! This is only available with the `serde` feature.
+
+use core::fmt;
+use core::marker::PhantomData;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::ser::SerializeMap;
+use serde::de::{self, Visitor, SeqAccess, MapAccess};
+
+use crate::{MaybeUtf8, MaybeUtf8Buf, MaybeUtf8Slice, MaybeUtf8Cow, Slice};
+use crate::base64::{BASE64_KEY, encode_base64, decode_base64};
+
+// how values not explicitly encoded in UTF-8 are represented
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Form {
+    // `serialize_bytes`, which is the default for binary formats
+    Compact,
+    // a map with a single entry `base64`, which is the default for human-readable formats
+    Readable,
+    // a sequence of `u8`, for formats without bytes
+    ByteSeq,
+}
+
+impl Form {
+    fn default_for(human_readable: bool) -> Form {
+        if human_readable { Form::Readable } else { Form::Compact }
+    }
+}
+
+fn serialize_slice<S: Serializer>(s: MaybeUtf8Slice, serializer: S, form: Form)
+        -> Result<S::Ok, S::Error> {
+    let v = match s.inner {
+        Slice::Utf8(s) => return serializer.serialize_str(s),
+        Slice::Bytes(v) => v,
+    };
+    match form {
+        Form::Compact => serializer.serialize_bytes(v),
+        Form::Readable => {
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry(BASE64_KEY, &encode_base64(v))?;
+            map.end()
+        }
+        Form::ByteSeq => serializer.collect_seq(v),
+    }
+}
+
+impl<'a> Serialize for MaybeUtf8Slice<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let form = Form::default_for(serializer.is_human_readable());
+        serialize_slice(*self, serializer, form)
+    }
+}
+
+impl Serialize for MaybeUtf8Buf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_slice().serialize(serializer)
+    }
+}
+
+impl<'a> Serialize for MaybeUtf8Cow<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_slice().serialize(serializer)
+    }
+}
+
+// `MaybeUtf8` is considered to be explicitly encoded in UTF-8 whenever it is valid
+impl Serialize for MaybeUtf8 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_slice().serialize(serializer)
+    }
+}
+
+struct BufVisitor;
+
+impl<'de> Visitor<'de> for BufVisitor {
+    type Value = MaybeUtf8Buf;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<MaybeUtf8Buf, E> {
+        Ok(MaybeUtf8Buf::from(s))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<MaybeUtf8Buf, E> {
+        Ok(MaybeUtf8Buf::from_str(s))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<MaybeUtf8Buf, E> {
+        Ok(MaybeUtf8Buf::from(v))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<MaybeUtf8Buf, E> {
+        Ok(MaybeUtf8Buf::from_bytes(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<MaybeUtf8Buf, A::Error> {
+        let mut v = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(b) = seq.next_element()? {
+            v.push(b);
+        }
+        Ok(MaybeUtf8Buf::from_bytes(v))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<MaybeUtf8Buf, A::Error> {
+        match map.next_key::<String>()?.as_deref() {
+            Some(BASE64_KEY) => {}
+            Some(key) => return Err(de::Error::unknown_field(key, &[BASE64_KEY])),
+            None => return Err(de::Error::missing_field(BASE64_KEY)),
+        }
+        let value: String = map.next_value()?;
+        if map.next_key::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+        match decode_base64(&value) {
+            Some(v) => Ok(MaybeUtf8Buf::from_bytes(v)),
+            None => Err(de::Error::invalid_value(de::Unexpected::Str(&value), &"a Base64 string")),
+        }
+    }
+}
+
+fn deserialize_buf<'de, D: Deserializer<'de>>(deserializer: D, form: Form)
+        -> Result<MaybeUtf8Buf, D::Error> {
+    match form {
+        // human-readable formats (e.g. JSON) may answer `deserialize_byte_buf` with bytes
+        // even for strings, so they have to look at the data as like other forms
+        Form::Compact if !deserializer.is_human_readable() =>
+            deserializer.deserialize_byte_buf(BufVisitor),
+        // strings can't be distinguished from others without looking at the data
+        Form::Compact | Form::Readable | Form::ByteSeq => deserializer.deserialize_any(BufVisitor),
+    }
+}
+
+impl<'de> Deserialize<'de> for MaybeUtf8Buf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MaybeUtf8Buf, D::Error> {
+        let form = Form::default_for(deserializer.is_human_readable());
+        deserialize_buf(deserializer, form)
+    }
+}
+
+// only accepts data borrowed from the deserializer
+struct SliceVisitor<'a>(PhantomData<MaybeUtf8Slice<'a>>);
+
+impl<'de: 'a, 'a> Visitor<'de> for SliceVisitor<'a> {
+    type Value = MaybeUtf8Slice<'a>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a borrowed string or bytes")
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, s: &'de str) -> Result<MaybeUtf8Slice<'a>, E> {
+        Ok(MaybeUtf8Slice::from_str(s))
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<MaybeUtf8Slice<'a>, E> {
+        Ok(MaybeUtf8Slice::from_bytes(v))
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for MaybeUtf8Slice<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MaybeUtf8Slice<'a>, D::Error> {
+        // the human-readable form of bytes can't be borrowed anyway
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(SliceVisitor(PhantomData))
+        } else {
+            deserializer.deserialize_bytes(SliceVisitor(PhantomData))
+        }
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for &'a MaybeUtf8 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<&'a MaybeUtf8, D::Error> {
+        MaybeUtf8Slice::deserialize(deserializer).map(|s| MaybeUtf8::from_bytes(s.as_bytes()))
+    }
+}
+
+// borrows from the deserializer if possible, and makes an owned value otherwise
+struct CowVisitor<'a>(PhantomData<MaybeUtf8Cow<'a>>);
+
+impl<'de: 'a, 'a> Visitor<'de> for CowVisitor<'a> {
+    type Value = MaybeUtf8Cow<'a>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string or bytes")
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, s: &'de str) -> Result<MaybeUtf8Cow<'a>, E> {
+        Ok(MaybeUtf8Cow::Borrowed(MaybeUtf8Slice::from_str(s)))
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<MaybeUtf8Cow<'a>, E> {
+        Ok(MaybeUtf8Cow::Borrowed(MaybeUtf8Slice::from_bytes(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<MaybeUtf8Cow<'a>, E> {
+        BufVisitor.visit_str(s).map(MaybeUtf8Cow::Owned)
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<MaybeUtf8Cow<'a>, E> {
+        BufVisitor.visit_string(s).map(MaybeUtf8Cow::Owned)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<MaybeUtf8Cow<'a>, E> {
+        BufVisitor.visit_bytes(v).map(MaybeUtf8Cow::Owned)
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<MaybeUtf8Cow<'a>, E> {
+        BufVisitor.visit_byte_buf(v).map(MaybeUtf8Cow::Owned)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<MaybeUtf8Cow<'a>, A::Error> {
+        BufVisitor.visit_seq(seq).map(MaybeUtf8Cow::Owned)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<MaybeUtf8Cow<'a>, A::Error> {
+        BufVisitor.visit_map(map).map(MaybeUtf8Cow::Owned)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for MaybeUtf8Cow<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MaybeUtf8Cow<'a>, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(CowVisitor(PhantomData))
+        } else {
+            deserializer.deserialize_bytes(CowVisitor(PhantomData))
+        }
+    }
+}
+
+/// Represents a value as a string if valid in UTF-8 and as bytes otherwise,
+/// even in human-readable formats.
+pub mod compact {
+    use serde::{Deserializer, Serializer};
+
+    use crate::{MaybeUtf8, MaybeUtf8Buf};
+
+    /// Serializes given value as a string if valid in UTF-8 and as bytes otherwise.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where T: ?Sized + AsRef<[u8]>, S: Serializer {
+        let s = MaybeUtf8::from_bytes(value.as_ref()).to_slice();
+        super::serialize_slice(s, serializer, super::Form::Compact)
+    }
+
+    /// Deserializes a string, bytes or a sequence of bytes.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where T: From<MaybeUtf8Buf>, D: Deserializer<'de> {
+        super::deserialize_buf(deserializer, super::Form::Compact).map(T::from)
+    }
+}
+
+/// Represents a value as a string if valid in UTF-8 and as a map with a single entry `base64`
+/// otherwise, even in binary formats.
+pub mod readable {
+    use serde::{Deserializer, Serializer};
+
+    use crate::{MaybeUtf8, MaybeUtf8Buf};
+
+    /// Serializes given value as a string if valid in UTF-8
+    /// and as a map with a single entry `base64` otherwise.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where T: ?Sized + AsRef<[u8]>, S: Serializer {
+        let s = MaybeUtf8::from_bytes(value.as_ref()).to_slice();
+        super::serialize_slice(s, serializer, super::Form::Readable)
+    }
+
+    /// Deserializes a string, bytes, a sequence of bytes or a map with a single entry `base64`.
+    /// This requires a self-describing format.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where T: From<MaybeUtf8Buf>, D: Deserializer<'de> {
+        super::deserialize_buf(deserializer, super::Form::Readable).map(T::from)
+    }
+}
+
+/// Represents a value as a string if valid in UTF-8 and as a sequence of bytes otherwise,
+/// for formats without a dedicated type for bytes.
+pub mod byte_seq {
+    use serde::{Deserializer, Serializer};
+
+    use crate::{MaybeUtf8, MaybeUtf8Buf};
+
+    /// Serializes given value as a string if valid in UTF-8 and as a sequence of bytes otherwise.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where T: ?Sized + AsRef<[u8]>, S: Serializer {
+        let s = MaybeUtf8::from_bytes(value.as_ref()).to_slice();
+        super::serialize_slice(s, serializer, super::Form::ByteSeq)
+    }
+
+    /// Deserializes a string, bytes or a sequence of bytes.
+    /// This requires a self-describing format.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where T: From<MaybeUtf8Buf>, D: Deserializer<'de> {
+        super::deserialize_buf(deserializer, super::Form::ByteSeq).map(T::from)
+    }
+}
+
+/// Represents a value as a struct `MaybeUtf8` with two fields,
+/// `utf8` (whether it has been explicitly encoded in UTF-8) and `data` (the underlying bytes).
+///
+/// Unlike other representations, this keeps the distinction between
+/// values explicitly encoded in UTF-8 and bytes which happen to be valid in UTF-8,
+/// and consumers in other languages can reconstruct it without looking at the data.
+pub mod tagged {
+    use core::fmt;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::ser::SerializeStruct;
+    use serde::de::{self, Visitor, SeqAccess, MapAccess};
+
+    use crate::{IntoMaybeUtf8, MaybeUtf8Buf, MaybeUtf8Slice, Slice};
+
+    const NAME: &str = "MaybeUtf8";
+    const FIELDS: &[&str] = &["utf8", "data"];
+
+    /// Serializes given value as a struct with `utf8` and `data` fields.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where T: ?Sized, for<'a> &'a T: IntoMaybeUtf8<MaybeUtf8Slice<'a>>, S: Serializer {
+        let s = value.into_maybe_utf8();
+        let mut st = serializer.serialize_struct(NAME, 2)?;
+        // bytes which happen to be valid in UTF-8 are still not explicitly encoded in UTF-8
+        st.serialize_field("utf8", &matches!(s.inner, Slice::Utf8(_)))?;
+        st.serialize_field("data", &Data(s.as_bytes()))?;
+        st.end()
+    }
+
+    /// Deserializes a struct with `utf8` and `data` fields.
+    /// It is an error if `utf8` is `true` but `data` is not valid in UTF-8.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where T: From<MaybeUtf8Buf>, D: Deserializer<'de> {
+        deserializer.deserialize_struct(NAME, FIELDS, TaggedVisitor).map(T::from)
+    }
+
+    // serializes and deserializes the underlying bytes
+    struct Data<'a>(&'a [u8]);
+
+    impl<'a> serde::Serialize for Data<'a> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+
+    struct DataBuf(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for DataBuf {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<DataBuf, D::Error> {
+            let buf = deserializer.deserialize_byte_buf(super::BufVisitor)?;
+            Ok(DataBuf(buf.into_bytes()))
+        }
+    }
+
+    struct TaggedVisitor;
+
+    impl TaggedVisitor {
+        fn make<E: de::Error>(utf8: bool, data: Vec<u8>) -> Result<MaybeUtf8Buf, E> {
+            if !utf8 {
+                return Ok(MaybeUtf8Buf::from_bytes(data));
+            }
+            match String::from_utf8(data) {
+                Ok(s) => Ok(MaybeUtf8Buf::from_str(s)),
+                Err(e) => Err(E::invalid_value(de::Unexpected::Bytes(e.as_bytes()),
+                                               &"bytes valid in UTF-8")),
+            }
+        }
+    }
+
+    impl<'de> Visitor<'de> for TaggedVisitor {
+        type Value = MaybeUtf8Buf;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a struct with `utf8` and `data` fields")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<MaybeUtf8Buf, A::Error> {
+            let utf8 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let DataBuf(data) =
+                seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            TaggedVisitor::make(utf8, data)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<MaybeUtf8Buf, A::Error> {
+            let mut utf8 = None;
+            let mut data = None;
+            while let Some(key) = map.next_key::<String>()? {
+                match &*key {
+                    "utf8" if utf8.is_none() => utf8 = Some(map.next_value()?),
+                    "data" if data.is_none() => data = Some(map.next_value::<DataBuf>()?.0),
+                    "utf8" => return Err(de::Error::duplicate_field("utf8")),
+                    "data" => return Err(de::Error::duplicate_field("data")),
+                    key => return Err(de::Error::unknown_field(key, FIELDS)),
+                }
+            }
+            let utf8 = utf8.ok_or_else(|| de::Error::missing_field("utf8"))?;
+            let data = data.ok_or_else(|| de::Error::missing_field("data"))?;
+            TaggedVisitor::make(utf8, data)
+        }
+    }
+}
+
+/// Represents a value as a string, where invalid UTF-8 sequences are replaced by U+FFFD.
+/// This is lossy for values not valid in UTF-8.
+pub mod lossy_string {
+    use alloc::string::String;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::MaybeUtf8Buf;
+
+    /// Serializes given value as a string, replacing invalid UTF-8 sequences by U+FFFD.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where T: ?Sized + AsRef<[u8]>, S: Serializer {
+        serializer.serialize_str(&String::from_utf8_lossy(value.as_ref()))
+    }
+
+    /// Deserializes a string into a value explicitly encoded in UTF-8.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where T: From<MaybeUtf8Buf>, D: Deserializer<'de> {
+        String::deserialize(deserializer).map(|s| T::from(MaybeUtf8Buf::from_str(s)))
+    }
+}
+
+/// Represents a value as a string of the underlying bytes in the standard Base64 encoding.
+pub mod base64 {
+    use alloc::string::String;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::{Error, Unexpected};
+
+    use crate::MaybeUtf8Buf;
+
+    /// Serializes the underlying bytes of given value as a padded Base64 string.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where T: ?Sized + AsRef<[u8]>, S: Serializer {
+        serializer.serialize_str(&super::encode_base64(value.as_ref()))
+    }
+
+    /// Deserializes a Base64 string, with or without paddings, into bytes.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where T: From<MaybeUtf8Buf>, D: Deserializer<'de> {
+        let s = String::deserialize(deserializer)?;
+        match super::decode_base64(&s) {
+            Some(v) => Ok(T::from(MaybeUtf8Buf::from_bytes(v))),
+            None => Err(D::Error::invalid_value(Unexpected::Str(&s), &"a Base64 string")),
+        }
+    }
+}
+
+/// Represents a value as a string of the underlying bytes in hexadecimal digits.
+pub mod hex {
+    use alloc::string::String;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::{Error, Unexpected};
+
+    use crate::MaybeUtf8Buf;
+
+    /// Serializes the underlying bytes of given value as lowercase hexadecimal digits.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where T: ?Sized + AsRef<[u8]>, S: Serializer {
+        serializer.serialize_str(&super::encode_hex(value.as_ref()))
+    }
+
+    /// Deserializes hexadecimal digits in either case into bytes.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where T: From<MaybeUtf8Buf>, D: Deserializer<'de> {
+        let s = String::deserialize(deserializer)?;
+        match super::decode_hex(&s) {
+            Some(v) => Ok(T::from(MaybeUtf8Buf::from_bytes(v))),
+            None => Err(D::Error::invalid_value(Unexpected::Str(&s), &"hexadecimal digits")),
+        }
+    }
+}
+
+fn encode_hex(v: &[u8]) -> String {
+    let mut ret = String::with_capacity(v.len() * 2);
+    for &b in v {
+        ret.push(char::from_digit((b >> 4) as u32, 16).unwrap());
+        ret.push(char::from_digit((b & 0xf) as u32, 16).unwrap());
+    }
+    ret
+}
+
+// returns `None` if `s` is not a valid sequence of hexadecimal digit pairs.
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) { return None; }
+    s.as_bytes().chunks(2).map(|pair| {
+        let hi = (pair[0] as char).to_digit(16)?;
+        let lo = (pair[1] as char).to_digit(16)?;
+        Some((hi << 4 | lo) as u8)
+    }).collect()
+}
@@ -0,0 +1,286 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Splitting iterators over `MaybeUtf8Slice`.
+
+use crate::{BytePattern, MaybeUtf8Slice, Slice};
+
+/// An iterator over sub-slices separated by a pattern.
+/// This is returned by `MaybeUtf8Slice::split` and `MaybeUtf8Slice::split_str`.
+///
+/// Each sub-slice is explicitly encoded in UTF-8 only when the original value is
+/// and both ends of the sub-slice are at char boundaries.
+#[derive(Clone)]
+pub struct Split<'a, P> {
+    haystack: MaybeUtf8Slice<'a>,
+    pat: P,
+    // the remaining range, which is yet to be split
+    start: usize,
+    end: usize,
+    allow_trailing_empty: bool,
+    finished: bool,
+}
+
+/// An iterator over sub-slices separated by a pattern, starting from the end.
+/// This is returned by `MaybeUtf8Slice::rsplit`.
+#[derive(Clone)]
+pub struct RSplit<'a, P>(Split<'a, P>);
+
+/// An iterator over at most given number of sub-slices separated by a pattern.
+/// The last sub-slice contains the remainder. This is returned by `MaybeUtf8Slice::splitn`.
+#[derive(Clone)]
+pub struct SplitN<'a, P> { inner: Split<'a, P>, count: usize }
+
+/// An iterator over at most given number of sub-slices separated by a pattern,
+/// starting from the end. The last sub-slice contains the remainder.
+/// This is returned by `MaybeUtf8Slice::rsplitn`.
+#[derive(Clone)]
+pub struct RSplitN<'a, P> { inner: Split<'a, P>, count: usize }
+
+/// An iterator over sub-slices terminated by a pattern.
+/// It differs from `Split` in that a trailing empty sub-slice is skipped.
+/// This is returned by `MaybeUtf8Slice::split_terminator`.
+#[derive(Clone)]
+pub struct SplitTerminator<'a, P>(Split<'a, P>);
+
+/// An iterator over lines, which are terminated by `\n` optionally preceded by `\r`.
+/// The line terminators are not included, and a trailing empty line is skipped.
+/// A `\r` not followed by `\n` is kept, even at the end of the last line.
+/// This is returned by `MaybeUtf8Slice::lines`.
+#[derive(Clone)]
+pub struct Lines<'a> { inner: SplitTerminator<'a, u8>, haystack: &'a [u8] }
+
+/// An iterator over non-empty sub-slices separated by whitespace.
+/// This is returned by `MaybeUtf8Slice::split_whitespace`.
+///
+/// Unicode whitespace is recognized if the original value is explicitly encoded in UTF-8,
+/// and only ASCII whitespace is recognized otherwise.
+#[derive(Clone)]
+pub struct SplitWhitespace<'a> {
+    haystack: MaybeUtf8Slice<'a>,
+    // the remaining range, which is yet to be split
+    start: usize,
+    end: usize,
+}
+
+impl<'a, P: BytePattern> Split<'a, P> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>, pat: P) -> Split<'a, P> {
+        Split { haystack, pat, start: 0, end: haystack.len(),
+                allow_trailing_empty: true, finished: false }
+    }
+
+    fn piece(&self, begin: usize, end: usize) -> MaybeUtf8Slice<'a> {
+        self.haystack.get(begin..end).unwrap()
+    }
+
+    fn last_piece(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        if self.finished { return None; }
+        self.finished = true;
+        if self.allow_trailing_empty || self.start < self.end {
+            Some(self.piece(self.start, self.end))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, P: BytePattern> RSplit<'a, P> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>, pat: P) -> RSplit<'a, P> {
+        RSplit(Split::new(haystack, pat))
+    }
+}
+
+impl<'a, P: BytePattern> SplitN<'a, P> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>, pat: P, count: usize) -> SplitN<'a, P> {
+        SplitN { inner: Split::new(haystack, pat), count }
+    }
+}
+
+impl<'a, P: BytePattern> RSplitN<'a, P> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>, pat: P, count: usize) -> RSplitN<'a, P> {
+        RSplitN { inner: Split::new(haystack, pat), count }
+    }
+}
+
+impl<'a, P: BytePattern> SplitTerminator<'a, P> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>, pat: P) -> SplitTerminator<'a, P> {
+        SplitTerminator(Split { allow_trailing_empty: false, ..Split::new(haystack, pat) })
+    }
+}
+
+impl<'a, P: BytePattern> Iterator for Split<'a, P> {
+    type Item = MaybeUtf8Slice<'a>;
+
+    fn next(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        if self.finished { return None; }
+        let rest = &self.haystack.as_bytes()[self.start..self.end];
+        match self.pat.find_in(rest) {
+            Some((begin, end)) => {
+                assert!(begin < end, "cannot split with an empty pattern");
+                let piece = self.piece(self.start, self.start + begin);
+                self.start += end;
+                Some(piece)
+            }
+            None => self.last_piece(),
+        }
+    }
+}
+
+impl<'a, P: BytePattern> DoubleEndedIterator for Split<'a, P> {
+    fn next_back(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        if self.finished { return None; }
+        if !self.allow_trailing_empty {
+            // skip the trailing empty sub-slice once
+            self.allow_trailing_empty = true;
+            match self.next_back() {
+                Some(piece) if !piece.is_empty() => return Some(piece),
+                _ => if self.finished { return None; },
+            }
+        }
+        let rest = &self.haystack.as_bytes()[self.start..self.end];
+        match self.pat.rfind_in(rest) {
+            Some((begin, end)) => {
+                assert!(begin < end, "cannot split with an empty pattern");
+                let piece = self.piece(self.start + end, self.end);
+                self.end = self.start + begin;
+                Some(piece)
+            }
+            None => {
+                self.finished = true;
+                Some(self.piece(self.start, self.end))
+            }
+        }
+    }
+}
+
+impl<'a, P: BytePattern> Iterator for RSplit<'a, P> {
+    type Item = MaybeUtf8Slice<'a>;
+    fn next(&mut self) -> Option<MaybeUtf8Slice<'a>> { self.0.next_back() }
+}
+
+impl<'a, P: BytePattern> DoubleEndedIterator for RSplit<'a, P> {
+    fn next_back(&mut self) -> Option<MaybeUtf8Slice<'a>> { self.0.next() }
+}
+
+impl<'a, P: BytePattern> Iterator for SplitN<'a, P> {
+    type Item = MaybeUtf8Slice<'a>;
+
+    fn next(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        match self.count {
+            0 => None,
+            1 => { self.count = 0; self.inner.last_piece() }
+            _ => { self.count -= 1; self.inner.next() }
+        }
+    }
+}
+
+impl<'a, P: BytePattern> Iterator for RSplitN<'a, P> {
+    type Item = MaybeUtf8Slice<'a>;
+
+    fn next(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        match self.count {
+            0 => None,
+            1 => { self.count = 0; self.inner.last_piece() }
+            _ => { self.count -= 1; self.inner.next_back() }
+        }
+    }
+}
+
+impl<'a, P: BytePattern> Iterator for SplitTerminator<'a, P> {
+    type Item = MaybeUtf8Slice<'a>;
+    fn next(&mut self) -> Option<MaybeUtf8Slice<'a>> { self.0.next() }
+}
+
+impl<'a, P: BytePattern> DoubleEndedIterator for SplitTerminator<'a, P> {
+    fn next_back(&mut self) -> Option<MaybeUtf8Slice<'a>> { self.0.next_back() }
+}
+
+impl<'a> Lines<'a> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>) -> Lines<'a> {
+        Lines { inner: SplitTerminator::new(haystack, b'\n'), haystack: haystack.as_bytes() }
+    }
+
+    fn strip_cr(&self, line: MaybeUtf8Slice<'a>) -> MaybeUtf8Slice<'a> {
+        // the last line without `\n` ends at the end of the haystack and keeps its `\r`
+        let unterminated = line.as_bytes().as_ptr_range().end == self.haystack.as_ptr_range().end;
+        if unterminated { return line; }
+        line.strip_suffix(b'\r').unwrap_or(line)
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = MaybeUtf8Slice<'a>;
+
+    fn next(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        let line = self.inner.next()?;
+        Some(self.strip_cr(line))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Lines<'a> {
+    fn next_back(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        let line = self.inner.next_back()?;
+        Some(self.strip_cr(line))
+    }
+}
+
+impl<'a> SplitWhitespace<'a> {
+    pub(crate) fn new(haystack: MaybeUtf8Slice<'a>) -> SplitWhitespace<'a> {
+        SplitWhitespace { haystack, start: 0, end: haystack.len() }
+    }
+}
+
+impl<'a> Iterator for SplitWhitespace<'a> {
+    type Item = MaybeUtf8Slice<'a>;
+
+    fn next(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        let (begin, end) = match self.haystack.inner {
+            Slice::Utf8(s) => {
+                let rest = s[self.start..self.end].trim_start();
+                let begin = self.end - rest.len();
+                (begin, begin + rest.find(char::is_whitespace).unwrap_or(rest.len()))
+            }
+            Slice::Bytes(v) => {
+                let rest = v[self.start..self.end].trim_ascii_start();
+                let begin = self.end - rest.len();
+                (begin, begin + rest.iter().position(u8::is_ascii_whitespace).unwrap_or(rest.len()))
+            }
+        };
+        if begin == end {
+            self.start = self.end;
+            return None;
+        }
+        self.start = end;
+        self.haystack.get(begin..end)
+    }
+}
+
+impl<'a> DoubleEndedIterator for SplitWhitespace<'a> {
+    fn next_back(&mut self) -> Option<MaybeUtf8Slice<'a>> {
+        let (begin, end) = match self.haystack.inner {
+            Slice::Utf8(s) => {
+                let rest = s[self.start..self.end].trim_end();
+                let end = self.start + rest.len();
+                let begin = match rest.char_indices().rfind(|&(_, c)| c.is_whitespace()) {
+                    Some((i, c)) => self.start + i + c.len_utf8(),
+                    None => self.start,
+                };
+                (begin, end)
+            }
+            Slice::Bytes(v) => {
+                let rest = v[self.start..self.end].trim_ascii_end();
+                let end = self.start + rest.len();
+                let begin = rest.iter().rposition(u8::is_ascii_whitespace)
+                                .map_or(self.start, |i| self.start + i + 1);
+                (begin, end)
+            }
+        };
+        if begin == end {
+            self.end = self.start;
+            return None;
+        }
+        self.end = begin;
+        self.haystack.get(begin..end)
+    }
+}
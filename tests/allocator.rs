@@ -0,0 +1,56 @@
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+#![cfg(feature = "allocator_api")]
+
+use std::alloc::{AllocError, Allocator, Global, Layout};
+use std::cell::Cell;
+use std::ptr::NonNull;
+
+use maybe_utf8::MaybeUtf8BufIn;
+
+// counts allocations made through it
+#[derive(Clone, Copy)]
+struct Counting<'a>(&'a Cell<usize>);
+
+unsafe impl Allocator for Counting<'_> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.0.set(self.0.get() + 1);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { Global.deallocate(ptr, layout) }
+    }
+}
+
+#[test]
+fn grow_in_allocator() {
+    let count = Cell::new(0);
+    let mut v = MaybeUtf8BufIn::new_in(Counting(&count));
+    assert_eq!(count.get(), 0);
+    for _ in 0..100 {
+        v.push_str("caf\u{e9} ");
+    }
+    v.insert_str(0, ">");
+    assert!(count.get() > 0);
+    assert_eq!(v.len(), 1 + 100 * 6);
+    assert!(v.as_str().is_some());
+
+    v.push_byte(0xff);
+    assert!(v.as_str().is_none());
+    assert!(v.as_bytes().ends_with(b"\xc3\xa9 \xff"));
+    let bytes = v.into_inner();
+    assert!(std::ptr::eq(bytes.allocator().0, &count));
+}
+
+#[test]
+fn copy_in_allocator() {
+    let count = Cell::new(0);
+    let mut v = MaybeUtf8BufIn::from_str_in("caf\u{e9}", Counting(&count));
+    assert_eq!(count.get(), 1);
+    v.truncate(4);
+    assert_eq!(v.as_bytes(), b"caf\xc3");
+    assert!(v.as_str().is_none());
+    let v = MaybeUtf8BufIn::from_bytes_in(b"\xff", Counting(&count));
+    assert_eq!(count.get(), 2);
+    assert_eq!(v.to_buf().as_bytes(), b"\xff");
+}
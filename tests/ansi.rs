@@ -0,0 +1,51 @@
+use maybe_utf8::MaybeUtf8Slice;
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn strip(s: &str) -> String { format!("{:?}", MaybeUtf8Slice::from_str(s).strip_ansi_escapes()) }
+fn strip_bytes(v: &[u8]) -> String {
+    format!("{:?}", MaybeUtf8Slice::from_bytes(v).strip_ansi_escapes())
+}
+
+#[test]
+fn csi() {
+    assert_eq!(strip("\x1b[1;31mcaf\u{e9}\x1b[0m!"), "\"caf\u{e9}!\"");
+    assert_eq!(strip("a\x1b[2Jb\x1b[?25lc\x1b[ qd"), "\"abcd\"");
+    // the final byte ends the sequence
+    assert_eq!(strip("\x1b[31mm"), "\"m\"");
+    assert_eq!(strip_bytes(b"\xff\x1b[31m\xfe"), r#"b"\xff\xfe""#);
+}
+
+#[test]
+fn osc() {
+    // terminated by BEL or ST (`ESC \`)
+    assert_eq!(strip("a\x1b]0;title\x07b"), "\"ab\"");
+    assert_eq!(strip("a\x1b]0;title\x1b\\b"), "\"ab\"");
+    assert_eq!(strip("a\x1b]8;;http://x/\u{e9}\x1b\\link\x1b]8;;\x1b\\b"), "\"alinkb\"");
+    assert_eq!(strip_bytes(b"a\x1b]0;\xff\x07b"), r#"b"ab""#);
+    // DCS, SOS, PM and APC are strings as well
+    assert_eq!(strip("a\x1bPq#0\x1b\\b\x1b_x\x07c"), "\"abc\"");
+    // ESC not followed by `\` ends the string and starts a new sequence
+    assert_eq!(strip("a\x1b]0;title\x1b[31mb"), "\"ab\"");
+}
+
+#[test]
+fn other_sequences() {
+    assert_eq!(strip("a\x1b7b\x1b8c"), "\"abc\"");
+    assert_eq!(strip("a\x1b(Bb"), "\"ab\"");
+    assert_eq!(strip("a\x1b=b"), "\"ab\"");
+}
+
+#[test]
+fn unterminated() {
+    assert_eq!(strip("abc\x1b"), "\"abc\"");
+    assert_eq!(strip("abc\x1b[31"), "\"abc\"");
+    assert_eq!(strip("abc\x1b]0;title"), "\"abc\"");
+    assert_eq!(strip("abc\x1b]0;title\x1b"), "\"abc\"");
+    assert_eq!(strip_bytes(b"abc\x1b]0;\xc3"), r#"b"abc""#);
+}
+
+#[test]
+fn c1_introducers_are_kept() {
+    assert_eq!(strip("a\u{9b}31mb"), "\"a\\u{9b}31mb\"");
+    assert_eq!(strip_bytes(b"a\x9b31mb"), r#"b"a\x9b31mb""#);
+}
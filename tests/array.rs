@@ -0,0 +1,54 @@
+use maybe_utf8::{CapacityError, MaybeUtf8Array};
+
+#[test]
+fn try_push_within_capacity() {
+    let mut name = MaybeUtf8Array::<8>::new();
+    assert_eq!(name.capacity(), 8);
+    assert_eq!(name.try_push_str("READ"), Ok(()));
+    assert_eq!(name.try_push('\u{e9}'), Ok(()));
+    assert_eq!(name.try_push_str("."), Ok(()));
+    assert_eq!(name.as_str(), Some("READ\u{e9}."));
+    assert_eq!(format!("{:?}", name), "\"READ\u{e9}.\"");
+    assert_eq!(name.len(), 7);
+
+    // bytes fill the capacity exactly and drop the UTF-8 flag
+    assert_eq!(name.try_push_byte(b'M'), Ok(()));
+    assert_eq!(name.len(), name.capacity());
+    assert_eq!(name.as_bytes(), b"READ\xc3\xa9.M");
+    assert_eq!(format!("{:?}", name), r#"b"READ\xc3\xa9.M""#);
+}
+
+#[test]
+fn try_push_over_capacity() {
+    let mut name = MaybeUtf8Array::<4>::from_str("abc").unwrap();
+    // a failed push leaves the value intact, including the UTF-8 flag
+    assert_eq!(name.try_push_str("de"), Err(CapacityError));
+    assert_eq!(name.try_push('\u{e9}'), Err(CapacityError));
+    assert_eq!(name.try_push_bytes(b"\xff\xff"), Err(CapacityError));
+    assert_eq!(name.as_str(), Some("abc"));
+    assert_eq!(format!("{:?}", name), "\"abc\"");
+
+    assert_eq!(name.try_push_byte(0xff), Ok(()));
+    assert_eq!(name.try_push_byte(0xff), Err(CapacityError));
+    assert_eq!(name.try_push_str(""), Ok(()));
+    assert_eq!(name.as_bytes(), b"abc\xff");
+
+    let mut empty = MaybeUtf8Array::<0>::new();
+    assert_eq!(empty.try_push_str(""), Ok(()));
+    assert_eq!(empty.try_push_byte(0), Err(CapacityError));
+}
+
+#[test]
+fn from_over_capacity() {
+    assert_eq!(MaybeUtf8Array::<3>::from_str("abcd"), Err(CapacityError));
+    assert_eq!(MaybeUtf8Array::<3>::from_bytes(b"\xff\xfe\xfd\xfc"), Err(CapacityError));
+    assert_eq!(MaybeUtf8Array::<4>::from_bytes(b"\xff\xfe\xfd\xfc").unwrap().as_bytes(),
+               b"\xff\xfe\xfd\xfc");
+}
+
+#[test]
+#[should_panic(expected = "capacity exceeded")]
+fn push_over_capacity() {
+    let mut name = MaybeUtf8Array::<2>::from_str("ab").unwrap();
+    name.push('c');
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "bincode")]
+
+use bincode::config;
+use bincode::error::DecodeError;
+use maybe_utf8::{MaybeUtf8Buf, MaybeUtf8Cow, MaybeUtf8Slice};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<T: std::fmt::Debug>(v: T) -> String { format!("{:?}", v) }
+
+fn decode(v: &[u8]) -> Result<MaybeUtf8Buf, DecodeError> {
+    bincode::decode_from_slice(v, config::standard()).map(|(buf, _)| buf)
+}
+
+#[test]
+fn round_trip() {
+    let v = bincode::encode_to_vec(MaybeUtf8Slice::from_str("caf\u{e9}"), config::standard()).unwrap();
+    assert_eq!(v, b"\x01\x05caf\xc3\xa9");
+    assert_eq!(debug(decode(&v).unwrap()), "\"caf\u{e9}\"");
+
+    // bytes valid in UTF-8 are still not explicitly encoded in UTF-8
+    let buf = MaybeUtf8Buf::from_bytes(b"caf\xc3\xa9".to_vec());
+    let v = bincode::encode_to_vec(&buf, config::standard()).unwrap();
+    assert_eq!(v, b"\x00\x05caf\xc3\xa9");
+    assert_eq!(debug(decode(&v).unwrap()), r#"b"caf\xc3\xa9""#);
+
+    // borrowing from the input
+    let (s, _): (MaybeUtf8Slice, _) =
+        bincode::borrow_decode_from_slice(&v, config::standard()).unwrap();
+    assert_eq!(debug(s), r#"b"caf\xc3\xa9""#);
+    assert_eq!(s.as_bytes().as_ptr(), v[2..].as_ptr());
+    let (cow, _): (MaybeUtf8Cow, _) =
+        bincode::borrow_decode_from_slice(b"\x01\x01a", config::standard()).unwrap();
+    assert!(matches!(cow, MaybeUtf8Cow::Borrowed(_)));
+    assert_eq!(debug(&cow), "\"a\"");
+}
+
+#[test]
+fn reject_invalid() {
+    match decode(b"\x02\x01a") {
+        Err(DecodeError::UnexpectedVariant { type_name: "MaybeUtf8", found: 2, .. }) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+
+    // explicitly encoded in UTF-8 but not valid in UTF-8
+    assert!(matches!(decode(b"\x01\x01\xff"), Err(DecodeError::Utf8 { .. })));
+    let r = bincode::borrow_decode_from_slice::<MaybeUtf8Slice, _>(b"\x01\x01\xff",
+                                                                  config::standard());
+    assert!(matches!(r, Err(DecodeError::Utf8 { .. })));
+    assert_eq!(debug(decode(b"\x00\x01\xff").unwrap()), r#"b"\xff""#);
+
+    assert!(matches!(decode(b"\x01\x02a"), Err(DecodeError::UnexpectedEnd { .. })));
+    assert!(matches!(decode(b""), Err(DecodeError::UnexpectedEnd { .. })));
+}
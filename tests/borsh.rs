@@ -0,0 +1,41 @@
+#![cfg(feature = "borsh")]
+
+use maybe_utf8::{MaybeUtf8Buf, MaybeUtf8Cow, MaybeUtf8Slice};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<T: std::fmt::Debug>(v: T) -> String { format!("{:?}", v) }
+
+#[test]
+fn round_trip() {
+    let v = borsh::to_vec(&MaybeUtf8Slice::from_str("caf\u{e9}")).unwrap();
+    assert_eq!(v, b"\x01\x05\0\0\0caf\xc3\xa9");
+    let buf: MaybeUtf8Buf = borsh::from_slice(&v).unwrap();
+    assert_eq!(debug(&buf), "\"caf\u{e9}\"");
+
+    // bytes valid in UTF-8 are still not explicitly encoded in UTF-8
+    let v = borsh::to_vec(&MaybeUtf8Buf::from_bytes(b"caf\xc3\xa9".to_vec())).unwrap();
+    assert_eq!(v, b"\x00\x05\0\0\0caf\xc3\xa9");
+    let buf: MaybeUtf8Buf = borsh::from_slice(&v).unwrap();
+    assert_eq!(debug(&buf), r#"b"caf\xc3\xa9""#);
+
+    let v = borsh::to_vec(&MaybeUtf8Cow::from(MaybeUtf8Slice::from_bytes(b"\xff"))).unwrap();
+    let cow: MaybeUtf8Cow = borsh::from_slice(&v).unwrap();
+    assert_eq!(debug(&cow), r#"b"\xff""#);
+}
+
+#[test]
+fn reject_invalid() {
+    let err = borsh::from_slice::<MaybeUtf8Buf>(b"\x02\x01\0\0\0a").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "invalid MaybeUtf8 tag");
+
+    // explicitly encoded in UTF-8 but not valid in UTF-8
+    let err = borsh::from_slice::<MaybeUtf8Buf>(b"\x01\x01\0\0\0\xff").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(borsh::from_slice::<MaybeUtf8Buf>(b"\x00\x01\0\0\0\xff").is_ok());
+
+    // truncated or trailing bytes
+    assert!(borsh::from_slice::<MaybeUtf8Buf>(b"\x01\x02\0\0\0a").is_err());
+    assert!(borsh::from_slice::<MaybeUtf8Buf>(b"\x01\x01\0\0\0ab").is_err());
+    assert!(borsh::from_slice::<MaybeUtf8Buf>(b"").is_err());
+}
@@ -0,0 +1,64 @@
+use maybe_utf8::{MaybeUtf8Buf, MaybeUtf8Builder, MaybeUtf8Slice};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<T: std::fmt::Debug>(v: T) -> String { format!("{:?}", v) }
+
+#[test]
+fn build_utf8() {
+    let mut builder = MaybeUtf8Builder::new();
+    assert!(builder.is_empty());
+    builder.push_str("caf\u{e9}");
+    builder.push(MaybeUtf8Slice::from_str(" au "));
+    builder.push(MaybeUtf8Buf::from_str("lait".to_owned()));
+    assert_eq!(builder.len(), 13);
+    assert_eq!(debug(builder.build()), "\"caf\u{e9} au lait\"");
+    assert_eq!(debug(MaybeUtf8Builder::new().build()), "\"\"");
+}
+
+#[test]
+fn build_valid() {
+    // bytes known to be valid are still valid but not explicitly encoded in UTF-8
+    let valid = MaybeUtf8Buf::from_bytes(b"caf\xc3\xa9".to_vec());
+    assert!(valid.is_utf8());
+    let mut builder = MaybeUtf8Builder::new();
+    builder.push_str("un ");
+    builder.push(valid);
+    let buf = builder.build();
+    assert_eq!(debug(&buf), r#"b"un caf\xc3\xa9""#);
+    assert_eq!(buf.as_str(), Some("un caf\u{e9}"));
+}
+
+#[test]
+fn build_bytes() {
+    let mut builder = MaybeUtf8Builder::new();
+    builder.push_str("a");
+    builder.push_bytes(b"\xff");
+    let buf = builder.build();
+    assert_eq!(debug(&buf), r#"b"a\xff""#);
+    assert_eq!(buf.as_str(), None);
+
+    // segments invalid by themselves can be valid when concatenated
+    let (head, tail) = (MaybeUtf8Buf::from_bytes(vec![0xc3]), MaybeUtf8Buf::from_bytes(vec![0xa9]));
+    assert!(!head.is_utf8() && !tail.is_utf8());
+    let mut builder = MaybeUtf8Builder::new();
+    builder.extend([head, tail]);
+    let buf = builder.build();
+    assert_eq!(debug(&buf), r#"b"\xc3\xa9""#);
+    assert_eq!(buf.as_str(), Some("\u{e9}"));
+
+    let mut builder = MaybeUtf8Builder::new();
+    builder.extend([MaybeUtf8Slice::from_bytes(b"ok"), MaybeUtf8Slice::from_str("!")]);
+    assert_eq!(debug(MaybeUtf8Buf::from(builder)), r#"b"ok!""#);
+}
+
+#[test]
+fn build_with_single_allocation() {
+    let piece = "0123456789".repeat(10);
+    let mut builder = MaybeUtf8Builder::new();
+    for _ in 0..10 { builder.push_str(&piece); }
+    builder.push_bytes(b"\xff");
+    let buf = builder.build();
+    // the capacity would have been grown past the length if reallocated
+    assert_eq!(buf.len(), 1001);
+    assert_eq!(buf.capacity(), 1001);
+}
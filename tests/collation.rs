@@ -0,0 +1,37 @@
+#![cfg(feature = "icu_collator")]
+
+use std::cmp::Ordering;
+
+use icu_collator::Collator;
+use icu_collator::options::{CollatorOptions, Strength};
+use maybe_utf8::MaybeUtf8Slice;
+
+const VALUES: &[&[u8]] = &[
+    b"", b"\0", b"\0\0", b"\x01", b"a", b"a\0", b"a\0\0", b"a\0b", b"a\x01", b"ab", b"A", b"b",
+    b"\xc3\xa1", b"a\xcc\x81", b"a\xff", b"\xff", b"\xff\0", b"\xfe",
+];
+
+fn check_order(strength: Strength) {
+    let mut options = CollatorOptions::default();
+    options.strength = Some(strength);
+    let collator = Collator::try_new(Default::default(), options).unwrap();
+    for &a in VALUES {
+        for &b in VALUES {
+            let (sa, sb) = (MaybeUtf8Slice::from_bytes(a), MaybeUtf8Slice::from_bytes(b));
+            // ordered by the collator first, and then by the underlying bytes
+            let expected = match collator.compare_utf8(a, b) {
+                Ordering::Equal => a.cmp(b),
+                ord => ord,
+            };
+            let actual = sa.collation_key(&collator).cmp(&sb.collation_key(&collator));
+            assert_eq!(actual, expected, "{:?} vs {:?} at {:?}", sa, sb, strength);
+        }
+    }
+}
+
+#[test]
+fn collation_key_order() {
+    check_order(Strength::Primary);
+    check_order(Strength::Tertiary);
+    check_order(Strength::Identical);
+}
@@ -0,0 +1,19 @@
+use maybe_utf8::MaybeUtf8Buf;
+
+#[test]
+fn collect_bufs() {
+    let bufs = vec![MaybeUtf8Buf::from_str("caf".to_string()), MaybeUtf8Buf::from_bytes(b"\xe9!".to_vec())];
+    let buf: MaybeUtf8Buf = bufs.into_iter().collect();
+    assert_eq!(buf.as_bytes(), b"caf\xe9!");
+    let buf: MaybeUtf8Buf = Vec::<MaybeUtf8Buf>::new().into_iter().collect();
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn collect_bufs_drops_hint() {
+    let bufs = vec![MaybeUtf8Buf::from_bytes_with_hint(b"\x82\xa0".to_vec(), "shift_jis"),
+                    MaybeUtf8Buf::from_str("abc".to_string())];
+    let buf: MaybeUtf8Buf = bufs.into_iter().collect();
+    assert_eq!(buf.as_bytes(), b"\x82\xa0abc");
+    assert!(buf.hint().is_none());
+}
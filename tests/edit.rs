@@ -0,0 +1,104 @@
+use maybe_utf8::{CharOrByte, MaybeUtf8Buf};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<T: std::fmt::Debug>(v: T) -> String { format!("{:?}", v) }
+
+fn utf8(s: &str) -> MaybeUtf8Buf { MaybeUtf8Buf::from_str(s.to_owned()) }
+
+// bytes which are valid in UTF-8, and known so after `is_utf8`
+fn valid(s: &str) -> MaybeUtf8Buf {
+    let buf = MaybeUtf8Buf::from_bytes(s.as_bytes().to_vec());
+    assert!(buf.is_utf8());
+    buf
+}
+
+#[test]
+fn insert_str() {
+    let mut buf = utf8("caf\u{e9}");
+    buf.insert_str(3, "\u{2615}");
+    buf.insert_str(buf.len(), "!");
+    assert_eq!(debug(&buf), "\"caf\u{2615}\u{e9}!\"");
+    buf.insert_str(7, "x");
+    assert_eq!(debug(&buf), r#"b"caf\xe2\x98\x95\xc3x\xa9!""#);
+    assert!(!buf.is_utf8());
+
+    let mut buf = valid("\u{e9}");
+    buf.insert_str(0, "a");
+    assert_eq!(debug(&buf), r#"b"a\xc3\xa9""#);
+    assert!(buf.is_utf8());
+    buf.insert_str(2, "b");
+    assert!(!buf.is_utf8());
+}
+
+#[test]
+fn remove() {
+    let mut buf = utf8("a\u{e9}b");
+    assert_eq!(buf.remove(1), CharOrByte::Char('\u{e9}'));
+    assert_eq!(debug(&buf), "\"ab\"");
+
+    let mut buf = utf8("a\u{e9}b");
+    assert_eq!(buf.remove(2), CharOrByte::Byte(0xa9));
+    assert_eq!(debug(&buf), r#"b"a\xc3b""#);
+    assert!(!buf.is_utf8());
+    assert_eq!(buf.remove(1), CharOrByte::Byte(0xc3));
+    assert_eq!(debug(&buf), r#"b"ab""#);
+    assert!(buf.is_utf8());
+
+    let mut buf = valid("\u{e9}a");
+    assert_eq!(buf.remove(2), CharOrByte::Byte(b'a'));
+    assert!(buf.is_utf8());
+    assert_eq!(buf.remove(0), CharOrByte::Byte(0xc3));
+    assert!(!buf.is_utf8());
+}
+
+#[test]
+fn drain() {
+    let mut buf = utf8("a\u{e9}\u{2615}b");
+    let drained = buf.drain(1..6);
+    assert_eq!(debug(drained.to_slice()), "\"\u{e9}\u{2615}\"");
+    assert_eq!(drained.collect::<Vec<_>>(), [CharOrByte::Char('\u{e9}'), CharOrByte::Char('\u{2615}')]);
+    assert_eq!(debug(&buf), "\"ab\"");
+
+    // both values are demoted when either end is not at a char boundary
+    let mut buf = utf8("a\u{e9}b");
+    let mut drained = buf.drain(2..);
+    assert_eq!(debug(drained.to_slice()), r#"b"\xa9b""#);
+    assert_eq!(drained.next_back(), Some(CharOrByte::Byte(b'b')));
+    assert_eq!(drained.next(), Some(CharOrByte::Byte(0xa9)));
+    assert_eq!(drained.next(), None);
+    assert_eq!(debug(&buf), r#"b"a\xc3""#);
+    assert!(!buf.is_utf8());
+
+    // the range is removed even when the iterator is dropped
+    let mut buf = utf8("a\u{e9}b");
+    buf.drain(..1);
+    assert_eq!(debug(&buf), "\"\u{e9}b\"");
+    buf.drain(..);
+    assert_eq!(debug(&buf), "\"\"");
+
+    let mut buf = valid("a\u{e9}b");
+    assert_eq!(debug(buf.drain(1..3).to_slice()), r#"b"\xc3\xa9""#);
+    assert!(buf.is_utf8());
+}
+
+#[test]
+fn retain() {
+    let mut buf = utf8("a\u{e9}b\u{2615}");
+    buf.retain(|c| matches!(c, CharOrByte::Char(c) if !c.is_ascii()));
+    assert_eq!(debug(&buf), "\"\u{e9}\u{2615}\"");
+
+    // bytes are given to the predicate, and the result may be no longer valid
+    let mut buf = valid("a\u{e9}b");
+    let mut seen = vec![];
+    buf.retain(|c| { seen.push(c); c != CharOrByte::Byte(0xa9) });
+    assert_eq!(seen, [b'a', 0xc3, 0xa9, b'b'].map(CharOrByte::Byte));
+    assert_eq!(debug(&buf), r#"b"a\xc3b""#);
+    assert!(!buf.is_utf8());
+
+    let mut buf = valid("a\u{e9}");
+    buf.retain(|_| true);
+    assert_eq!(debug(&buf), r#"b"a\xc3\xa9""#);
+    assert!(buf.is_utf8());
+    buf.retain(|c| c != CharOrByte::Byte(b'a'));
+    assert!(buf.is_utf8());
+}
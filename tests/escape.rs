@@ -0,0 +1,16 @@
+use maybe_utf8::{MaybeUtf8Buf, MaybeUtf8Slice};
+
+#[test]
+fn escape_default_depends_on_variant() {
+    let utf8 = MaybeUtf8Slice::from_str("caf\u{e9}\n");
+    assert_eq!(utf8.escape_default().to_string(), "caf\\u{e9}\\n");
+
+    // bytes are escaped byte-wise even when valid in UTF-8
+    let bytes = MaybeUtf8Slice::from_bytes("caf\u{e9}\n".as_bytes());
+    assert_eq!(bytes.escape_default().to_string(), "caf\\xc3\\xa9\\n");
+    let buf = MaybeUtf8Buf::from_bytes("caf\u{e9}\n".as_bytes().to_vec());
+    assert_eq!(buf.escape_default().to_string(), "caf\\xc3\\xa9\\n");
+
+    let invalid = MaybeUtf8Slice::from_bytes(b"caf\xe9");
+    assert_eq!(invalid.escape_default().to_string(), "caf\\xe9");
+}
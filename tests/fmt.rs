@@ -0,0 +1,24 @@
+use maybe_utf8::{MaybeUtf8, MaybeUtf8Buf, MaybeUtf8Slice};
+
+#[test]
+fn hex() {
+    let bytes = MaybeUtf8Buf::from_bytes(b"caf\xe9".to_vec());
+    assert_eq!(format!("{:x}", bytes), "636166e9");
+    assert_eq!(format!("{:X}", bytes), "636166E9");
+    assert_eq!(format!("{:#x}", bytes.to_slice()), "0x636166e9");
+    assert_eq!(format!("{:x}", MaybeUtf8::from_str("hi")), "6869");
+    assert_eq!(format!("{:x}", MaybeUtf8Slice::new()), "");
+}
+
+#[test]
+fn hex_with_padding() {
+    let s = MaybeUtf8Slice::from_bytes(b"\xab\xcd");
+    assert_eq!(format!("{:>8x}", s), "    abcd");
+    assert_eq!(format!("{:<8X}|", s), "ABCD    |");
+    assert_eq!(format!("{:^8x}", s), "  abcd  ");
+    assert_eq!(format!("{:*>8x}", s), "****abcd");
+    assert_eq!(format!("{:08x}", s), "0000abcd");
+    assert_eq!(format!("{:#010x}", s), "0x0000abcd");
+    assert_eq!(format!("{:>#10x}", s), "    0xabcd");
+    assert_eq!(format!("{:2x}", s), "abcd");
+}
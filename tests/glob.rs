@@ -0,0 +1,118 @@
+#![cfg(feature = "glob")]
+
+use maybe_utf8::{GlobOptions, MaybeUtf8Slice};
+
+fn glob(name: &str, pattern: &str) -> bool {
+    MaybeUtf8Slice::from_str(name).matches_glob(pattern)
+}
+
+fn glob_with(name: &str, pattern: &str, options: GlobOptions) -> bool {
+    MaybeUtf8Slice::from_str(name).matches_glob_with(pattern, options)
+}
+
+#[test]
+fn wildcards() {
+    assert!(glob("main.rs", "*.rs"));
+    assert!(glob("main.rs", "m??n.rs"));
+    assert!(glob("caf\u{e9}", "caf?"));
+    assert!(glob("", "*"));
+    assert!(!glob("main.rs", "*.c"));
+    assert!(!glob("src/main.rs", "*.rs"));
+    assert!(!glob("src/main.rs", "src?main.rs"));
+    assert!(glob("src/main.rs", "src/*"));
+    assert!(glob("src/main.rs", "*/*.rs"));
+}
+
+#[test]
+fn recursive_wildcards() {
+    assert!(glob("src/main.rs", "src/**/*.rs"));
+    assert!(glob("src/a/b/main.rs", "src/**/*.rs"));
+    assert!(glob("main.rs", "**/*.rs"));
+    assert!(glob("a/b/main.rs", "**/main.rs"));
+    assert!(!glob("a/bmain.rs", "**/main.rs"));
+    assert!(!glob("lib/main.rs", "src/**/*.rs"));
+
+    // trailing `**` matches everything including separators
+    assert!(glob("src/a/b", "src/**"));
+    assert!(glob("src/", "src/**"));
+    assert!(!glob("srcx/a", "src/**"));
+    assert!(glob("a/b/c", "**"));
+
+    // `**` not forming a whole path component is same to `*`
+    assert!(glob("abc", "a**"));
+    assert!(!glob("a/b/c", "a**"));
+    assert!(!glob("x/y.rs", "x**.rs"));
+}
+
+#[test]
+fn classes() {
+    assert!(glob("a1", "a[0-9]"));
+    assert!(!glob("ax", "a[0-9]"));
+    assert!(glob("ax", "a[!0-9]"));
+    assert!(glob("ax", "a[^0-9]"));
+    assert!(!glob("a5", "a[!0-9]"));
+    assert!(glob("b", "[abc]"));
+    assert!(glob("-", "[a-]"));
+    assert!(glob("\u{e9}", "[\u{e0}-\u{ff}]"));
+    assert!(!glob("\u{100}", "[\u{e0}-\u{ff}]"));
+
+    // `]` right after the opening bracket is a literal
+    assert!(glob("]", "[]]"));
+    assert!(glob("a", "[]a]"));
+    assert!(!glob("]", "[!]]"));
+    assert!(glob("x", "[!]]"));
+
+    // an unterminated class is a literal `[`
+    assert!(glob("[a", "[a"));
+    assert!(!glob("a", "[a"));
+
+    // classes never match `/` by default
+    assert!(!glob("a/b", "a[/]b"));
+    assert!(!glob("a/b", "a[!x]b"));
+}
+
+#[test]
+fn case_insensitive() {
+    let options = GlobOptions { case_sensitive: false, ..GlobOptions::new() };
+    assert!(glob_with("MAIN.RS", "*.rs", options));
+    assert!(glob_with("\u{c9}T\u{c9}", "\u{e9}t\u{e9}", options));
+    assert!(glob_with("Q", "[a-z]", options));
+    assert!(glob_with("q", "[A-Z]", options));
+    assert!(!glob_with("Q", "[!a-z]", options));
+    assert!(glob_with("\u{c9}", "[\u{e0}-\u{ff}]", options));
+    assert!(!glob("MAIN.RS", "*.rs"));
+    assert!(!glob("Q", "[a-z]"));
+}
+
+#[test]
+fn literal_separator() {
+    let options = GlobOptions { require_literal_separator: false, ..GlobOptions::new() };
+    assert!(glob_with("src/main.rs", "*.rs", options));
+    assert!(glob_with("src/main.rs", "src?main.rs", options));
+    assert!(glob_with("a/b", "a[/]b", options));
+    assert!(glob_with("a/b", "a[!x]b", options));
+    assert!(glob_with("src/a/b/main.rs", "src/**/*.rs", options));
+}
+
+#[test]
+fn invalid_bytes() {
+    let name = MaybeUtf8Slice::from_bytes(b"caf\xe9.txt");
+    assert!(name.matches_glob("*.txt"));
+    assert!(name.matches_glob("caf?.txt"));
+    assert!(name.matches_glob(&b"caf\xe9.*"[..]));
+    assert!(!name.matches_glob("caf\u{e9}.txt"));
+    assert!(!name.matches_glob(&b"caf\xe8.*"[..]));
+
+    // each invalid byte is a separate character
+    let name = MaybeUtf8Slice::from_bytes(b"\xff\xfe");
+    assert!(name.matches_glob("??"));
+    assert!(!name.matches_glob("?"));
+    assert!(name.matches_glob(&b"[\xfe\xff][\xfe-\xff]"[..]));
+    assert!(name.matches_glob(&b"[!a-z\xfe]\xfe"[..]));
+    assert!(!name.matches_glob(&b"[!\xff]*"[..]));
+
+    // a range of characters never includes bytes, even case-insensitively
+    let options = GlobOptions { case_sensitive: false, ..GlobOptions::new() };
+    assert!(!name.matches_glob_with("[\0-\u{10ffff}]*", options));
+    assert!(MaybeUtf8Slice::from_bytes(b"\xc3").matches_glob_with(&b"[\xc3]"[..], options));
+}
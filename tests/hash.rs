@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use maybe_utf8::{MaybeUtf8, MaybeUtf8Buf, MaybeUtf8Slice, MaybeUtf8Cow};
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn utf8_and_bytes_hash_identically() {
+    let utf8 = MaybeUtf8Buf::from_str("a".to_owned());
+    let bytes = MaybeUtf8Buf::from_bytes(b"a".to_vec());
+    assert_eq!(hash(&utf8), hash(&bytes));
+
+    assert_eq!(hash(&MaybeUtf8Slice::from_str("a")), hash(&MaybeUtf8Slice::from_bytes(b"a")));
+    assert_eq!(hash(&MaybeUtf8Slice::from_str("a")), hash(&utf8));
+
+    let borrowed = MaybeUtf8Cow::Borrowed(MaybeUtf8Slice::from_bytes(b"a"));
+    let owned = MaybeUtf8Cow::Owned(MaybeUtf8Buf::from_str("a".to_owned()));
+    assert_eq!(hash(&borrowed), hash(&owned));
+    assert_eq!(hash(&borrowed), hash(&utf8));
+
+    assert_eq!(hash(MaybeUtf8::from_str("a")), hash(MaybeUtf8::from_bytes(b"a")));
+    assert_eq!(hash(MaybeUtf8::from_str("a")), hash(&utf8));
+}
+
+// the cached UTF-8 validity is interior mutable, but never affects `Hash` or `Eq`
+#[allow(clippy::mutable_key_type)]
+#[test]
+fn hash_map_lookup_across_variants() {
+    let mut map = HashMap::new();
+    map.insert(MaybeUtf8Buf::from_str("caf\u{e9}".to_owned()), 1);
+    map.insert(MaybeUtf8Buf::from_bytes(b"caf\xe9".to_vec()), 2);
+
+    assert_eq!(map.get(&MaybeUtf8Buf::from_bytes(b"caf\xc3\xa9".to_vec())), Some(&1));
+    assert_eq!(map.get(&MaybeUtf8Buf::from_bytes(b"caf\xe9".to_vec())), Some(&2));
+    assert_eq!(map.get(&b"caf\xc3\xa9"[..]), Some(&1));
+    assert_eq!(map.get(MaybeUtf8::from_str("caf\u{e9}")), Some(&1));
+    assert_eq!(map.get(MaybeUtf8::from_bytes(b"caf\xe9")), Some(&2));
+}
@@ -0,0 +1,35 @@
+use maybe_utf8::MaybeUtf8Slice;
+
+fn hexdump(v: &[u8]) -> String { MaybeUtf8Slice::from_bytes(v).hexdump().to_string() }
+
+#[test]
+fn full_lines() {
+    assert_eq!(hexdump(b"Hello, world!\n\x00\xff\x7f\x80 ~abcdefghijklm"),
+               "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 ff  |Hello, world!...|\n\
+                00000010  7f 80 20 7e 61 62 63 64  65 66 67 68 69 6a 6b 6c  |.. ~abcdefghijkl|\n\
+                00000020  6d                                                |m|");
+    assert_eq!(hexdump(b""), "");
+}
+
+#[test]
+fn partial_last_line() {
+    // missing bytes are padded so that the ASCII column stays aligned
+    assert_eq!(hexdump(b"abc"),
+               "00000000  61 62 63                                          |abc|");
+    assert_eq!(hexdump(b"abcdefgh"),
+               "00000000  61 62 63 64 65 66 67 68                           |abcdefgh|");
+    assert_eq!(hexdump(b"abcdefghi"),
+               "00000000  61 62 63 64 65 66 67 68  69                       |abcdefghi|");
+    assert_eq!(hexdump(b"Hello World\n"),
+               "00000000  48 65 6c 6c 6f 20 57 6f  72 6c 64 0a              |Hello World.|");
+    for line in hexdump(&[b'x'; 40]).lines() {
+        assert_eq!(line.find('|'), Some(60));
+    }
+}
+
+#[test]
+fn debug() {
+    let s = MaybeUtf8Slice::from_str("\u{e9}");
+    assert_eq!(format!("{:?}", s.hexdump()),
+               "00000000  c3 a9                                             |..|");
+}
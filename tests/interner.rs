@@ -0,0 +1,49 @@
+#![cfg(feature = "std")]
+
+use maybe_utf8::{MaybeUtf8Interner, MaybeUtf8Slice};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<T: std::fmt::Debug>(v: T) -> String { format!("{:?}", v) }
+
+#[test]
+fn intern() {
+    let mut interner = MaybeUtf8Interner::new();
+    assert!(interner.is_empty());
+    let a = interner.intern(MaybeUtf8Slice::from_str("caf\u{e9}"));
+    let b = interner.intern(MaybeUtf8Slice::from_bytes(b"\xff"));
+    assert_eq!(interner.intern(MaybeUtf8Slice::from_str("caf\u{e9}")), a);
+    assert_eq!(interner.intern(MaybeUtf8Slice::from_bytes(b"\xff")), b);
+    assert_ne!(a, b);
+    assert_eq!((a.to_usize(), b.to_usize()), (0, 1));
+    assert_eq!(interner.len(), 2);
+    assert_eq!(debug(interner.resolve(a)), "\"caf\u{e9}\"");
+    assert_eq!(debug(interner.resolve(b)), r#"b"\xff""#);
+}
+
+#[test]
+fn utf8_and_bytes_are_distinct() {
+    let mut interner = MaybeUtf8Interner::new();
+    let utf8 = interner.intern(MaybeUtf8Slice::from_str("caf\u{e9}"));
+    assert_eq!(interner.get(MaybeUtf8Slice::from_bytes(b"caf\xc3\xa9")), None);
+    let bytes = interner.intern(MaybeUtf8Slice::from_bytes(b"caf\xc3\xa9"));
+    assert_ne!(utf8, bytes);
+    assert_eq!(interner.len(), 2);
+
+    // each symbol resolves to the value as interned
+    assert_eq!(debug(interner.resolve(utf8)), "\"caf\u{e9}\"");
+    assert_eq!(debug(interner.resolve(bytes)), r#"b"caf\xc3\xa9""#);
+    assert_eq!(interner.get(MaybeUtf8Slice::from_str("caf\u{e9}")), Some(utf8));
+    assert_eq!(interner.get(MaybeUtf8Slice::from_bytes(b"caf\xc3\xa9")), Some(bytes));
+    assert_eq!(interner.get(MaybeUtf8Slice::from_str("cafe")), None);
+}
+
+#[test]
+#[should_panic]
+fn resolve_foreign_symbol() {
+    let mut other = MaybeUtf8Interner::new();
+    other.intern(MaybeUtf8Slice::from_str("a"));
+    let sym = other.intern(MaybeUtf8Slice::from_str("b"));
+    let mut interner = MaybeUtf8Interner::new();
+    interner.intern(MaybeUtf8Slice::from_str("a"));
+    interner.resolve(sym);
+}
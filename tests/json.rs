@@ -0,0 +1,53 @@
+#![cfg(feature = "serde_json")]
+
+use maybe_utf8::{JsonBytes, MaybeUtf8Buf, MaybeUtf8Slice};
+use serde_json::json;
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<T: std::fmt::Debug>(v: T) -> String { format!("{:?}", v) }
+
+#[test]
+fn to_json_value() {
+    let s = MaybeUtf8Slice::from_str("caf\u{e9}");
+    assert_eq!(s.to_json_value(JsonBytes::Base64), json!("caf\u{e9}"));
+    assert_eq!(s.to_json_value(JsonBytes::Array), json!("caf\u{e9}"));
+
+    let s = MaybeUtf8Slice::from_bytes(b"\xff\0a");
+    assert_eq!(s.to_json_value(JsonBytes::Base64), json!({"base64": "/wBh"}));
+    assert_eq!(s.to_json_value(JsonBytes::Array), json!([255, 0, 97]));
+    assert_eq!(MaybeUtf8Slice::from_bytes(b"").to_json_value(JsonBytes::Array), json!([]));
+}
+
+#[test]
+fn from_json_value() {
+    let from = |value| MaybeUtf8Buf::from_json_value(&value).map(debug);
+    assert_eq!(from(json!("caf\u{e9}")).as_deref(), Some("\"caf\u{e9}\""));
+    assert_eq!(from(json!({"base64": "/wBh"})).as_deref(), Some(r#"b"\xff\x00a""#));
+    assert_eq!(from(json!([255, 0, 97])).as_deref(), Some(r#"b"\xff\x00a""#));
+    assert_eq!(from(json!([])).as_deref(), Some(r#"b"""#));
+
+    // round trips keep the UTF-8 flag
+    for s in [MaybeUtf8Slice::from_str("a"), MaybeUtf8Slice::from_bytes(b"a")] {
+        for bytes in [JsonBytes::Base64, JsonBytes::Array] {
+            let buf = MaybeUtf8Buf::from_json_value(&s.to_json_value(bytes)).unwrap();
+            assert_eq!(debug(buf), debug(s));
+        }
+    }
+}
+
+#[test]
+fn from_invalid_json_value() {
+    let from = |value| MaybeUtf8Buf::from_json_value(&value);
+    assert!(from(json!([256])).is_none());
+    assert!(from(json!([1, 1000, 2])).is_none());
+    assert!(from(json!([-1])).is_none());
+    assert!(from(json!([1.5])).is_none());
+    assert!(from(json!(["a"])).is_none());
+    assert!(from(json!({"base64": "/wBh", "extra": 1})).is_none());
+    assert!(from(json!({"hex": "ff"})).is_none());
+    assert!(from(json!({"base64": 1})).is_none());
+    assert!(from(json!({"base64": "!!"})).is_none());
+    assert!(from(json!({})).is_none());
+    assert!(from(json!(null)).is_none());
+    assert!(from(json!(42)).is_none());
+}
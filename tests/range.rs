@@ -0,0 +1,33 @@
+use maybe_utf8::{MaybeUtf8Buf, MaybeUtf8Shared};
+
+fn buf() -> MaybeUtf8Buf { MaybeUtf8Buf::from_str("abc".to_owned()) }
+
+#[test]
+#[should_panic(expected = "attempted to index up to maximum usize")]
+fn drain_up_to_max_panics() {
+    buf().drain(..=usize::MAX);
+}
+
+#[test]
+#[should_panic(expected = "attempted to index from after maximum usize")]
+fn drain_from_after_max_panics() {
+    use std::ops::Bound;
+    buf().drain((Bound::Excluded(usize::MAX), Bound::Unbounded));
+}
+
+#[test]
+#[should_panic(expected = "attempted to index up to maximum usize")]
+fn replace_range_up_to_max_panics() {
+    buf().replace_range(1..=usize::MAX, "x");
+}
+
+#[test]
+#[should_panic(expected = "attempted to index up to maximum usize")]
+fn shared_slice_up_to_max_panics() {
+    MaybeUtf8Shared::from(buf()).slice(..=usize::MAX);
+}
+
+#[test]
+fn get_up_to_max_is_none() {
+    assert!(buf().get(..=usize::MAX).is_none());
+}
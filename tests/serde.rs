@@ -0,0 +1,225 @@
+#![cfg(feature = "serde")]
+
+use std::fmt;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde_test::{Token, Configure, assert_tokens, assert_ser_tokens, assert_de_tokens};
+
+use maybe_utf8::MaybeUtf8Buf;
+
+// wraps a value to be (de)serialized with given submodule of `maybe_utf8::serde`.
+// wrapped values are equal only when they agree on being explicitly encoded in UTF-8.
+macro_rules! define_with {
+    ($($name:ident = $module:ident;)*) => ($(
+        struct $name(MaybeUtf8Buf);
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                maybe_utf8::serde::$module::serialize(&self.0, serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<$name, D::Error> {
+                maybe_utf8::serde::$module::deserialize(deserializer).map($name)
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &$name) -> bool {
+                // `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+                format!("{:?}", self.0) == format!("{:?}", other.0)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Debug::fmt(&self.0, f)
+            }
+        }
+    )*)
+}
+
+define_with! {
+    Compact = compact;
+    Readable = readable;
+    ByteSeq = byte_seq;
+    Tagged = tagged;
+    LossyString = lossy_string;
+    Base64 = base64;
+    Hex = hex;
+}
+
+// uses the default representation
+struct Plain(MaybeUtf8Buf);
+
+impl Serialize for Plain {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Plain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Plain, D::Error> {
+        MaybeUtf8Buf::deserialize(deserializer).map(Plain)
+    }
+}
+
+impl PartialEq for Plain {
+    fn eq(&self, other: &Plain) -> bool {
+        format!("{:?}", self.0) == format!("{:?}", other.0)
+    }
+}
+
+impl fmt::Debug for Plain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+fn utf8() -> MaybeUtf8Buf { MaybeUtf8Buf::from_str("caf\u{e9}".to_owned()) }
+fn bytes() -> MaybeUtf8Buf { MaybeUtf8Buf::from_bytes(b"caf\xe9".to_vec()) }
+
+const BASE64_MAP: &[Token] = &[
+    Token::Map { len: Some(1) }, Token::Str("base64"), Token::Str("Y2Fm6Q=="), Token::MapEnd,
+];
+
+fn tagged_tokens(v: &'static [u8], utf8: bool) -> Vec<Token> {
+    vec![
+        Token::Struct { name: "MaybeUtf8", len: 2 },
+        Token::Str("utf8"), Token::Bool(utf8),
+        Token::Str("data"), Token::Bytes(v),
+        Token::StructEnd,
+    ]
+}
+
+#[test]
+fn serialize_default() {
+    assert_ser_tokens(&utf8().compact(), &[Token::Str("caf\u{e9}")]);
+    assert_ser_tokens(&utf8().readable(), &[Token::Str("caf\u{e9}")]);
+    assert_ser_tokens(&bytes().compact(), &[Token::Bytes(b"caf\xe9")]);
+    assert_ser_tokens(&bytes().readable(), BASE64_MAP);
+}
+
+#[test]
+fn serialize_compact_uses_bytes() {
+    assert_ser_tokens(&Compact(utf8()).readable(), &[Token::Str("caf\u{e9}")]);
+    assert_ser_tokens(&Compact(bytes()).readable(), &[Token::Bytes(b"caf\xe9")]);
+    assert_ser_tokens(&Compact(bytes()).compact(), &[Token::Bytes(b"caf\xe9")]);
+}
+
+#[test]
+fn serialize_readable_uses_base64_map() {
+    assert_ser_tokens(&Readable(utf8()).compact(), &[Token::Str("caf\u{e9}")]);
+    assert_ser_tokens(&Readable(bytes()).compact(), BASE64_MAP);
+}
+
+#[test]
+fn serialize_byte_seq_uses_seq() {
+    assert_ser_tokens(&ByteSeq(utf8()).compact(), &[Token::Str("caf\u{e9}")]);
+    assert_ser_tokens(&ByteSeq(bytes()).compact(), &[
+        Token::Seq { len: Some(4) },
+        Token::U8(b'c'), Token::U8(b'a'), Token::U8(b'f'), Token::U8(0xe9),
+        Token::SeqEnd,
+    ]);
+}
+
+#[test]
+fn serialize_tagged_keeps_utf8_flag() {
+    assert_ser_tokens(&Tagged(utf8()), &tagged_tokens(b"caf\xc3\xa9", true));
+    assert_ser_tokens(&Tagged(bytes()), &tagged_tokens(b"caf\xe9", false));
+    // valid in UTF-8 but not explicitly encoded so
+    assert_ser_tokens(&Tagged(MaybeUtf8Buf::from_bytes(b"cafe".to_vec())),
+                      &tagged_tokens(b"cafe", false));
+}
+
+#[test]
+fn round_trip_default() {
+    assert_tokens(&Plain(utf8()).compact(), &[Token::Str("caf\u{e9}")]);
+    assert_tokens(&Plain(utf8()).readable(), &[Token::Str("caf\u{e9}")]);
+    assert_tokens(&Plain(bytes()).compact(), &[Token::Bytes(b"caf\xe9")]);
+    assert_tokens(&Plain(bytes()).readable(), BASE64_MAP);
+}
+
+#[test]
+fn round_trip_compact() {
+    assert_tokens(&Compact(utf8()).readable(), &[Token::Str("caf\u{e9}")]);
+    assert_tokens(&Compact(bytes()).readable(), &[Token::Bytes(b"caf\xe9")]);
+}
+
+#[test]
+fn round_trip_readable() {
+    assert_tokens(&Readable(utf8()).compact(), &[Token::Str("caf\u{e9}")]);
+    assert_tokens(&Readable(bytes()).compact(), BASE64_MAP);
+}
+
+#[test]
+fn round_trip_byte_seq() {
+    assert_tokens(&ByteSeq(utf8()), &[Token::Str("caf\u{e9}")]);
+    assert_tokens(&ByteSeq(bytes()), &[
+        Token::Seq { len: Some(4) },
+        Token::U8(b'c'), Token::U8(b'a'), Token::U8(b'f'), Token::U8(0xe9),
+        Token::SeqEnd,
+    ]);
+}
+
+#[test]
+fn round_trip_tagged() {
+    assert_tokens(&Tagged(utf8()), &tagged_tokens(b"caf\xc3\xa9", true));
+    assert_tokens(&Tagged(bytes()), &tagged_tokens(b"caf\xe9", false));
+    assert_tokens(&Tagged(MaybeUtf8Buf::from_bytes(b"cafe".to_vec())),
+                  &tagged_tokens(b"cafe", false));
+}
+
+#[test]
+fn round_trip_lossy_string() {
+    assert_tokens(&LossyString(utf8()), &[Token::Str("caf\u{e9}")]);
+    // lossy for bytes not valid in UTF-8
+    assert_ser_tokens(&LossyString(bytes()), &[Token::Str("caf\u{fffd}")]);
+    assert_de_tokens(&LossyString(MaybeUtf8Buf::from_str("caf\u{fffd}".to_owned())),
+                     &[Token::Str("caf\u{fffd}")]);
+}
+
+#[test]
+fn round_trip_base64_and_hex() {
+    // both only retain the underlying bytes
+    assert_tokens(&Base64(bytes()), &[Token::Str("Y2Fm6Q==")]);
+    assert_tokens(&Hex(bytes()), &[Token::Str("636166e9")]);
+    assert_de_tokens(&Base64(MaybeUtf8Buf::from_bytes(b"caf\xc3\xa9".to_vec())),
+                     &[Token::Str("Y2Fmw6k=")]);
+}
+
+#[test]
+fn round_trip_non_self_describing() {
+    let encoded = bincode1::serialize(&utf8()).unwrap();
+    let decoded: MaybeUtf8Buf = bincode1::deserialize(&encoded).unwrap();
+    // bincode can't tell strings from bytes
+    assert_eq!(decoded, utf8());
+    assert_eq!(format!("{:?}", decoded), r#"b"caf\xc3\xa9""#);
+
+    for value in [utf8(), bytes()] {
+        let encoded = bincode1::serialize(&Tagged(value.clone())).unwrap();
+        let decoded: Tagged = bincode1::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, Tagged(value));
+    }
+}
+
+#[test]
+fn round_trip_json() {
+    // unlike `serde_test`, serde_json answers `deserialize_bytes` with bytes even for strings
+    fn check<T: Serialize + for<'de> Deserialize<'de> + PartialEq + fmt::Debug>(value: T, json: &str) {
+        assert_eq!(serde_json::to_string(&value).unwrap(), json);
+        assert_eq!(serde_json::from_str::<T>(json).unwrap(), value);
+    }
+
+    check(Plain(utf8()), r#""café""#);
+    check(Plain(bytes()), r#"{"base64":"Y2Fm6Q=="}"#);
+    check(Compact(utf8()), r#""café""#);
+    check(Compact(bytes()), "[99,97,102,233]");
+    check(Readable(utf8()), r#""café""#);
+    check(Readable(bytes()), r#"{"base64":"Y2Fm6Q=="}"#);
+    check(ByteSeq(utf8()), r#""café""#);
+    check(ByteSeq(bytes()), "[99,97,102,233]");
+    check(Tagged(utf8()), r#"{"utf8":true,"data":[99,97,102,195,169]}"#);
+    check(Tagged(bytes()), r#"{"utf8":false,"data":[99,97,102,233]}"#);
+}
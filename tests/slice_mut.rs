@@ -0,0 +1,72 @@
+use maybe_utf8::{MaybeUtf8Buf, MaybeUtf8SliceMut};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<T: std::fmt::Debug>(v: T) -> String { format!("{:?}", v) }
+
+fn utf8(s: &str) -> MaybeUtf8Buf { MaybeUtf8Buf::from_str(s.to_owned()) }
+
+#[test]
+fn ascii_edits_retain_utf8() {
+    let mut buf = utf8("Caf\u{c9} \u{e9}t\u{e9}");
+    let mut s = buf.to_slice_mut();
+    s.make_ascii_uppercase();
+    assert_eq!(debug(&s), "\"CAF\u{c9} \u{e9}T\u{e9}\"");
+    s.make_ascii_lowercase();
+    s.replace_byte(b' ', b'_');
+    assert_eq!(debug(&s), "\"caf\u{c9}_\u{e9}t\u{e9}\"");
+    assert_eq!(debug(&buf), "\"caf\u{c9}_\u{e9}t\u{e9}\"");
+}
+
+#[test]
+fn byte_edits_demote_owner() {
+    let mut buf = utf8("caf\u{e9}");
+    let mut s = buf.to_slice_mut();
+    s.replace_byte(0xa9, 0xa8);
+    assert_eq!(debug(&s), r#"b"caf\xc3\xa8""#);
+    assert_eq!(debug(&buf), r#"b"caf\xc3\xa8""#);
+    assert_eq!(buf.as_str(), Some("caf\u{e8}"));
+
+    // replacing with a non-ASCII byte demotes even without any occurrence
+    let mut buf = utf8("abc");
+    buf.to_slice_mut().replace_byte(b'x', 0xff);
+    assert_eq!(debug(&buf), r#"b"abc""#);
+
+    let mut buf = utf8("caf\u{e9}");
+    let mut s = buf.to_slice_mut();
+    s.as_bytes_mut()[4] = 0xff;
+    assert_eq!(s.as_str(), None);
+    assert_eq!(debug(&buf), r#"b"caf\xc3\xff""#);
+    assert!(!buf.is_utf8());
+}
+
+#[test]
+fn truncate() {
+    let mut buf = utf8("caf\u{e9}");
+    let mut s = buf.to_slice_mut();
+    s.truncate(5);
+    assert_eq!(debug(&s), "\"caf\u{e9}\"");
+    s.truncate(4);
+    assert_eq!(debug(&s), r#"b"caf\xc3""#);
+    assert_eq!(s.len(), 4);
+    s.truncate(0);
+    assert!(s.is_empty());
+    // the owner is not truncated, and is still explicitly encoded in UTF-8
+    assert_eq!(debug(&buf), "\"caf\u{e9}\"");
+}
+
+#[test]
+#[should_panic(expected = "new length 4 is greater than the current length 3")]
+fn truncate_out_of_bounds() {
+    let mut bytes = *b"abc";
+    MaybeUtf8SliceMut::from_bytes(&mut bytes).truncate(4);
+}
+
+#[test]
+fn from_bytes() {
+    let mut bytes = *b"abc\xff";
+    let mut s = MaybeUtf8SliceMut::from_bytes(&mut bytes);
+    s.make_ascii_uppercase();
+    assert_eq!(debug(&s), r#"b"ABC\xff""#);
+    assert_eq!(debug(s.to_slice()), r#"b"ABC\xff""#);
+    assert_eq!(&bytes, b"ABC\xff");
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "smallvec")]
+
+use maybe_utf8::{MaybeUtf8Buf, MaybeUtf8SmallBuf};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<T: std::fmt::Debug>(v: T) -> String { format!("{:?}", v) }
+
+#[test]
+fn grow_small_buf() {
+    let mut v = MaybeUtf8SmallBuf::<8>::default();
+    v.push_str("caf");
+    v.push('\u{e9}');
+    assert_eq!(debug(&v), "\"caf\u{e9}\"");
+    assert!(!v.clone().into_inner().spilled());
+
+    // spills to the heap while retaining the UTF-8 encoding
+    v.push_str(" au lait");
+    assert!(v.clone().into_inner().spilled());
+    assert_eq!(debug(&v), "\"caf\u{e9} au lait\"");
+
+    v.insert_str(0, "un ");
+    v.truncate(8);
+    assert_eq!(debug(&v), "\"un caf\u{e9}\"");
+    v.truncate(7);
+    assert_eq!(debug(&v), r#"b"un caf\xc3""#);
+
+    let mut v = MaybeUtf8SmallBuf::<4>::default();
+    v.push_str("ab");
+    v.insert_byte(1, 0xff);
+    v.push_bytes(b"cd");
+    assert_eq!(debug(&v), r#"b"a\xffbcd""#);
+}
+
+#[test]
+fn convert_small_buf() {
+    let v = MaybeUtf8SmallBuf::<4>::from(MaybeUtf8Buf::from_str("\u{2615}".to_string()));
+    assert_eq!(debug(&v), "\"\u{2615}\"");
+    assert!(!v.clone().into_inner().spilled());
+    let v = MaybeUtf8SmallBuf::<4>::from(MaybeUtf8Buf::from_bytes(b"\xff\xfe".to_vec()));
+    assert_eq!(debug(&v), r#"b"\xff\xfe""#);
+
+    let mut v = MaybeUtf8SmallBuf::<2>::default();
+    v.push_str("x");
+    v.push_str("yz");
+    let buf = MaybeUtf8Buf::from(v);
+    assert_eq!(debug(&buf), "\"xyz\"");
+    let mut v = MaybeUtf8SmallBuf::<2>::default();
+    v.push_str("x");
+    v.push_byte(0x80);
+    let buf = MaybeUtf8Buf::from(v);
+    assert_eq!(debug(&buf), r#"b"x\x80""#);
+}
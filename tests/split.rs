@@ -0,0 +1,176 @@
+use maybe_utf8::{MaybeUtf8Slice, MaybeUtf8Buf};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<'a, I: IntoIterator<Item = MaybeUtf8Slice<'a>>>(it: I) -> Vec<String> {
+    it.into_iter().map(|s| format!("{:?}", s)).collect()
+}
+
+#[test]
+fn lines() {
+    let s = MaybeUtf8Slice::from_str("a\r\nb\n\r\nc\r");
+    assert_eq!(debug(s.lines()), ["\"a\"", "\"b\"", "\"\"", "\"c\\r\""]);
+    assert_eq!(debug(s.lines().rev()), ["\"c\\r\"", "\"\"", "\"b\"", "\"a\""]);
+
+    let s = MaybeUtf8Slice::from_str("a\r\rb\r\n");
+    assert_eq!(debug(s.lines()), ["\"a\\r\\rb\""]);
+    assert_eq!(debug(s.lines().rev()), ["\"a\\r\\rb\""]);
+    let s = MaybeUtf8Slice::from_str("a\r");
+    assert_eq!(debug(s.lines()), ["\"a\\r\""]);
+    assert_eq!(debug(s.lines().rev()), ["\"a\\r\""]);
+    let s = MaybeUtf8Slice::from_str("\r");
+    assert_eq!(debug(s.lines()), ["\"\\r\""]);
+    let s = MaybeUtf8Slice::from_str("\r\n\n");
+    assert_eq!(debug(s.lines()), ["\"\"", "\"\""]);
+    assert_eq!(debug(MaybeUtf8Slice::from_str("").lines()), Vec::<String>::new());
+}
+
+#[test]
+fn lines_with_invalid_bytes() {
+    let s = MaybeUtf8Slice::from_bytes(b"\xff\r\n\xc3\n\xa9\r");
+    assert_eq!(debug(s.lines()), [r#"b"\xff""#, r#"b"\xc3""#, r#"b"\xa9\r""#]);
+    assert_eq!(debug(s.lines().rev()), [r#"b"\xa9\r""#, r#"b"\xc3""#, r#"b"\xff""#]);
+
+    // each line of a UTF-8 value is explicitly encoded in UTF-8
+    let buf = MaybeUtf8Buf::from_str("\u{e9}\r\n\u{2615}\r".to_string());
+    assert_eq!(debug(buf.lines()), ["\"\u{e9}\"", "\"\u{2615}\\r\""]);
+    let mut lines = buf.lines();
+    assert_eq!(debug(lines.next_back()), ["\"\u{2615}\\r\""]);
+    assert_eq!(debug(lines.next()), ["\"\u{e9}\""]);
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn split() {
+    let s = MaybeUtf8Slice::from_str("usr/lib//caf\u{e9}/");
+    assert_eq!(debug(s.split(b'/')),
+               ["\"usr\"", "\"lib\"", "\"\"", "\"caf\u{e9}\"", "\"\""]);
+    assert_eq!(debug(s.split_str("//")), ["\"usr/lib\"", "\"caf\u{e9}/\""]);
+    assert_eq!(debug(s.split(b'/').rev()),
+               ["\"\"", "\"caf\u{e9}\"", "\"\"", "\"lib\"", "\"usr\""]);
+    assert_eq!(debug(MaybeUtf8Slice::from_str("").split(b'/')), ["\"\""]);
+    assert_eq!(debug(MaybeUtf8Slice::from_str("x").split_str("xyz")), ["\"x\""]);
+
+    // a piece is explicitly encoded in UTF-8 only when both ends are at char boundaries
+    let s = MaybeUtf8Slice::from_str("a\u{e9}b\u{e9}c");
+    assert_eq!(debug(s.split(0xa9u8)), [r#"b"a\xc3""#, r#"b"b\xc3""#, "\"c\""]);
+    assert_eq!(debug(s.split('\u{e9}')), ["\"a\"", "\"b\"", "\"c\""]);
+    assert_eq!(debug(s.split_str(&b"\xa9b\xc3"[..])), [r#"b"a\xc3""#, r#"b"\xa9c""#]);
+
+    // pieces of bytes are never explicitly encoded in UTF-8
+    let s = MaybeUtf8Slice::from_bytes(b"a/\xff/b");
+    assert_eq!(debug(s.split(b'/')), [r#"b"a""#, r#"b"\xff""#, r#"b"b""#]);
+    assert_eq!(debug(s.split(|b: u8| b >= 0x80)), [r#"b"a/""#, r#"b"/b""#]);
+}
+
+#[test]
+fn split_from_both_ends() {
+    let s = MaybeUtf8Slice::from_str("a,b,c,d");
+    let mut it = s.split(b',');
+    assert_eq!(debug(it.next()), ["\"a\""]);
+    assert_eq!(debug(it.next_back()), ["\"d\""]);
+    assert_eq!(debug(it.next()), ["\"b\""]);
+    assert_eq!(debug(it.next_back()), ["\"c\""]);
+    assert_eq!(it.next(), None);
+    assert_eq!(it.next_back(), None);
+
+    let s = MaybeUtf8Slice::from_str(",");
+    let mut it = s.split(b',');
+    assert_eq!(debug(it.next_back()), ["\"\""]);
+    assert_eq!(debug(it.next()), ["\"\""]);
+    assert_eq!(it.next_back(), None);
+    assert_eq!(it.next(), None);
+}
+
+#[test]
+#[should_panic(expected = "cannot split with an empty pattern")]
+fn split_with_empty_pattern() {
+    MaybeUtf8Slice::from_str("abc").split("").next();
+}
+
+#[test]
+fn rsplit_and_splitn() {
+    let s = MaybeUtf8Slice::from_str("usr/lib/caf\u{e9}");
+    assert_eq!(debug(s.rsplit(b'/')), ["\"caf\u{e9}\"", "\"lib\"", "\"usr\""]);
+    assert_eq!(debug(s.rsplit(b'/').rev()), ["\"usr\"", "\"lib\"", "\"caf\u{e9}\""]);
+    assert_eq!(debug(s.splitn(2, b'/')), ["\"usr\"", "\"lib/caf\u{e9}\""]);
+    assert_eq!(debug(s.splitn(5, b'/')), ["\"usr\"", "\"lib\"", "\"caf\u{e9}\""]);
+    assert_eq!(debug(s.splitn(1, b'/')), ["\"usr/lib/caf\u{e9}\""]);
+    assert_eq!(debug(s.splitn(0, b'/')), Vec::<String>::new());
+
+    // the last path component
+    assert_eq!(debug(s.rsplitn(2, b'/')), ["\"caf\u{e9}\"", "\"usr/lib\""]);
+    assert_eq!(debug(s.rsplitn(4, b'/')), ["\"caf\u{e9}\"", "\"lib\"", "\"usr\""]);
+    assert_eq!(debug(MaybeUtf8Slice::from_str("name").rsplitn(2, b'/')), ["\"name\""]);
+    assert_eq!(debug(MaybeUtf8Slice::from_str("dir/").rsplitn(2, b'/')), ["\"\"", "\"dir\""]);
+
+    let s = MaybeUtf8Slice::from_bytes(b"\xff/lib/caf\xc3\xa9");
+    assert_eq!(debug(s.rsplitn(2, b'/')), [r#"b"caf\xc3\xa9""#, r#"b"\xff/lib""#]);
+    let s = MaybeUtf8Slice::from_str("\u{e9}\u{e9}");
+    assert_eq!(debug(s.splitn(2, 0xc3u8)), ["\"\"", r#"b"\xa9\xc3\xa9""#]);
+    assert_eq!(debug(s.rsplitn(2, 0xc3u8)), [r#"b"\xa9""#, "\"\u{e9}\""]);
+}
+
+#[test]
+fn split_terminator() {
+    let s = MaybeUtf8Slice::from_str("a;b;;");
+    assert_eq!(debug(s.split_terminator(b';')), ["\"a\"", "\"b\"", "\"\""]);
+    assert_eq!(debug(s.split_terminator(b';').rev()), ["\"\"", "\"b\"", "\"a\""]);
+    let s = MaybeUtf8Slice::from_str("a;b");
+    assert_eq!(debug(s.split_terminator(b';')), ["\"a\"", "\"b\""]);
+    assert_eq!(debug(s.split_terminator(b';').rev()), ["\"b\"", "\"a\""]);
+    assert_eq!(debug(MaybeUtf8Slice::from_str("").split_terminator(b';')), Vec::<String>::new());
+    assert_eq!(debug(MaybeUtf8Slice::from_str("").split_terminator(b';').rev()),
+               Vec::<String>::new());
+    assert_eq!(debug(MaybeUtf8Slice::from_str(";").split_terminator(b';').rev()), ["\"\""]);
+
+    // the trailing empty slice is skipped only once from the back
+    let s = MaybeUtf8Slice::from_str("a;;");
+    let mut it = s.split_terminator(b';');
+    assert_eq!(debug(it.next_back()), ["\"\""]);
+    assert_eq!(debug(it.next()), ["\"a\""]);
+    assert_eq!(it.next_back(), None);
+    assert_eq!(it.next(), None);
+
+    let s = MaybeUtf8Slice::from_bytes(b"\xff;b;");
+    let mut it = s.split_terminator(b';');
+    assert_eq!(debug(it.next_back()), [r#"b"b""#]);
+    assert_eq!(debug(it.next()), [r#"b"\xff""#]);
+    assert_eq!(it.next(), None);
+}
+
+#[test]
+fn split_whitespace() {
+    // Unicode whitespace is recognized only when explicitly encoded in UTF-8
+    let s = MaybeUtf8Slice::from_str(" a\u{3000}b\t\u{e9} \n");
+    assert_eq!(debug(s.split_whitespace()), ["\"a\"", "\"b\"", "\"\u{e9}\""]);
+    assert_eq!(debug(s.split_whitespace().rev()), ["\"\u{e9}\"", "\"b\"", "\"a\""]);
+    let s = MaybeUtf8Slice::from_bytes(" a\u{3000}b\t\u{e9} \n".as_bytes());
+    assert_eq!(debug(s.split_whitespace()),
+               [r#"b"a\xe3\x80\x80b""#, r#"b"\xc3\xa9""#]);
+    assert_eq!(debug(s.split_whitespace().rev()),
+               [r#"b"\xc3\xa9""#, r#"b"a\xe3\x80\x80b""#]);
+
+    let s = MaybeUtf8Slice::from_bytes(b"\xff \x0b\x0c\xfe");
+    assert_eq!(debug(s.split_whitespace()), [r#"b"\xff""#, r#"b"\x0b""#, r#"b"\xfe""#]);
+    assert_eq!(debug(MaybeUtf8Slice::from_str(" \u{a0}\r\n").split_whitespace()),
+               Vec::<String>::new());
+    assert_eq!(debug(MaybeUtf8Slice::from_str("").split_whitespace().rev()),
+               Vec::<String>::new());
+}
+
+#[test]
+fn split_whitespace_from_both_ends() {
+    let s = MaybeUtf8Slice::from_str("a b\u{2003}c d");
+    let mut it = s.split_whitespace();
+    assert_eq!(debug(it.next_back()), ["\"d\""]);
+    assert_eq!(debug(it.next()), ["\"a\""]);
+    assert_eq!(debug(it.next_back()), ["\"c\""]);
+    assert_eq!(debug(it.next()), ["\"b\""]);
+    assert_eq!(it.next(), None);
+    assert_eq!(it.next_back(), None);
+
+    let s = MaybeUtf8Slice::from_bytes(b" x ");
+    let mut it = s.split_whitespace();
+    assert_eq!(debug(it.next()), [r#"b"x""#]);
+    assert_eq!(it.next_back(), None);
+}
@@ -0,0 +1,57 @@
+use std::fmt::Write;
+
+use maybe_utf8::{MaybeUtf8Of, MaybeUtf8Slice};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn debug<T: std::fmt::Debug>(v: T) -> String { format!("{:?}", v) }
+
+#[test]
+fn grow_vec_storage() {
+    let mut v = MaybeUtf8Of::<Vec<u8>>::default();
+    v.push_str("caf");
+    v.push('\u{e9}');
+    v.extend(" au".chars());
+    v.extend([" ", "lait"]);
+    write!(v, "{}", 2).unwrap();
+    assert_eq!(debug(&v), "\"caf\u{e9} au lait2\"");
+
+    v.push_maybe_utf8(MaybeUtf8Slice::from_str("!"));
+    assert_eq!(debug(&v), "\"caf\u{e9} au lait2!\"");
+    v.push_maybe_utf8(MaybeUtf8Slice::from_bytes(b"?"));
+    assert_eq!(debug(&v), r#"b"caf\xc3\xa9 au lait2!?""#);
+    assert_eq!(v.into_inner(), b"caf\xc3\xa9 au lait2!?");
+}
+
+#[test]
+fn edit_vec_storage() {
+    let mut v = MaybeUtf8Of::<Vec<u8>>::from_str("caf\u{e9}");
+    v.insert_str(0, "un ");
+    v.insert(3, '\u{2615}');
+    assert_eq!(debug(&v), "\"un \u{2615}caf\u{e9}\"");
+
+    // truncating or inserting at a char boundary retains the UTF-8 encoding
+    v.truncate(100);
+    v.truncate(v.len() - 2);
+    assert_eq!(debug(&v), "\"un \u{2615}caf\"");
+    v.truncate(4);
+    assert_eq!(debug(&v), r#"b"un \xe2""#);
+
+    let mut v = MaybeUtf8Of::<Vec<u8>>::from_str("\u{e9}");
+    v.insert_str(1, "x");
+    assert_eq!(debug(&v), r#"b"\xc3x\xa9""#);
+    v.clear();
+    assert_eq!(debug(&v), r#"b"""#);
+
+    let mut v = MaybeUtf8Of::<Vec<u8>>::from_str("ab");
+    v.insert_byte(1, 0xff);
+    assert_eq!(debug(&v), r#"b"a\xffb""#);
+    v.push_str("c");
+    assert_eq!(debug(&v), r#"b"a\xffbc""#);
+}
+
+#[test]
+#[should_panic(expected = "insertion index 3 is greater than the current length 2")]
+fn insert_out_of_bounds() {
+    let mut v = MaybeUtf8Of::<Vec<u8>>::from_str("ab");
+    v.insert_bytes(3, b"c");
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "std")]
+
+use std::io::Write;
+
+use maybe_utf8::{MaybeUtf8Buf, read_to_maybe_utf8};
+
+// `Debug` prints bytes not explicitly encoded in UTF-8 as `b"..."`
+fn is_explicit_utf8(buf: &MaybeUtf8Buf) -> bool {
+    !format!("{:?}", buf).starts_with('b')
+}
+
+#[test]
+fn push_bytes_streaming_promotes_to_utf8() {
+    let mut buf = MaybeUtf8Buf::new();
+    buf.push_bytes_streaming(b"caf\xc3");
+    assert!(!is_explicit_utf8(&buf));
+    buf.push_bytes_streaming(b"\xa9 au lait");
+    assert!(is_explicit_utf8(&buf));
+    assert_eq!(buf.as_str(), Some("caf\u{e9} au lait"));
+
+    let mut buf = MaybeUtf8Buf::from_bytes(b"abc".to_vec());
+    buf.push_bytes_streaming(b"def");
+    assert!(is_explicit_utf8(&buf));
+
+    let mut buf = MaybeUtf8Buf::new();
+    buf.push_bytes_streaming(b"caf\xe9");
+    buf.push_bytes_streaming(b"abc");
+    assert!(!is_explicit_utf8(&buf));
+}
+
+#[test]
+fn streaming_paths_agree() {
+    let data = "caf\u{e9} \u{1f600}".as_bytes();
+    for split in 0..=data.len() {
+        let mut pushed = MaybeUtf8Buf::new();
+        pushed.push_bytes_streaming(&data[..split]);
+        pushed.push_bytes_streaming(&data[split..]);
+
+        let mut written = MaybeUtf8Buf::new();
+        written.write_all(&data[..split]).unwrap();
+        written.write_all(&data[split..]).unwrap();
+
+        let mut read = MaybeUtf8Buf::new();
+        read_to_maybe_utf8(data, &mut read).unwrap();
+
+        for buf in [&pushed, &written, &read] {
+            assert!(is_explicit_utf8(buf), "split at {}", split);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::borrow::Cow;
+
+use maybe_utf8::{MaybeUtf8Buf, MaybeUtf8Slice};
+
+#[test]
+fn sanitize_for_terminal() {
+    let s = MaybeUtf8Slice::from_str("caf\u{e9} au lait");
+    assert!(matches!(s.sanitize_for_terminal(), Cow::Borrowed("caf\u{e9} au lait")));
+
+    let s = MaybeUtf8Slice::from_str("a\u{1b}[31mb\u{7f}\u{85}c");
+    assert_eq!(s.sanitize_for_terminal(), "a\\x1b[31mb\\x7f\\u{85}c");
+    let s = MaybeUtf8Slice::from_bytes(b"caf\xe9\n\xf0\x9f");
+    assert_eq!(s.sanitize_for_terminal(), "caf\\xe9\\x0a\\xf0\\x9f");
+}
+
+#[test]
+fn sanitize_for_terminal_escapes_backslashes() {
+    // a literal escape can't be confused with an escaped byte
+    let literal = MaybeUtf8Slice::from_bytes(b"\\x1b");
+    let escaped = MaybeUtf8Slice::from_bytes(b"\x1b");
+    assert_eq!(literal.sanitize_for_terminal(), "\\\\x1b");
+    assert_eq!(escaped.sanitize_for_terminal(), "\\x1b");
+    assert_eq!(MaybeUtf8Slice::from_str("C:\\").sanitize_for_terminal(), "C:\\\\");
+    assert_eq!(MaybeUtf8Buf::from_bytes(b"\\\xff".to_vec()).sanitize_for_terminal(), "\\\\\\xff");
+}
+
+#[test]
+fn sanitize_for_terminal_escapes_bidi() {
+    for c in ['\u{61c}', '\u{200e}', '\u{200f}', '\u{202a}', '\u{202e}', '\u{2066}', '\u{2069}'] {
+        let s = format!("a{}b", c);
+        let expected = format!("a\\u{{{:x}}}b", c as u32);
+        assert_eq!(MaybeUtf8Slice::from_str(&s).sanitize_for_terminal(), expected);
+    }
+    // joiners are not bidirectional marks
+    let s = MaybeUtf8Slice::from_str("a\u{200d}b");
+    assert_eq!(s.sanitize_for_terminal(), "a\u{200d}b");
+}
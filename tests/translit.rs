@@ -0,0 +1,11 @@
+#![cfg(feature = "deunicode")]
+
+use maybe_utf8::MaybeUtf8Slice;
+
+#[test]
+fn to_ascii_lossy_translit() {
+    assert_eq!(MaybeUtf8Slice::from_str("\u{c6}neid").to_ascii_lossy_translit(), "AEneid");
+    assert_eq!(MaybeUtf8Slice::from_bytes(b"caf\xe9 \xc3\xa9\xff\x0f").to_ascii_lossy_translit(),
+               "caf%E9 e%FF\x0f");
+    assert_eq!(MaybeUtf8Slice::from_bytes(b"").to_ascii_lossy_translit(), "");
+}
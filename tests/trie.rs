@@ -0,0 +1,103 @@
+use maybe_utf8::{MaybeUtf8Slice, MaybeUtf8Trie};
+
+fn keys<'t, V: 't>(iter: impl Iterator<Item=(&'t maybe_utf8::MaybeUtf8Buf, &'t V)>) -> Vec<Vec<u8>> {
+    iter.map(|(k, _)| k.as_bytes().to_vec()).collect()
+}
+
+#[test]
+fn insert_and_remove() {
+    let mut trie = MaybeUtf8Trie::new();
+    assert_eq!(trie.insert("a/b", 1), None);
+    assert_eq!(trie.insert("a", 2), None);
+    assert_eq!(trie.insert(&b"a/\xff"[..], 3), None);
+    assert_eq!(trie.len(), 3);
+
+    // the key is kept as first inserted
+    assert_eq!(trie.insert(&b"a/b"[..], 4), Some(1));
+    let (key, value) = trie.get_key_value("a/b").unwrap();
+    assert_eq!((key.as_str(), *value), (Some("a/b"), 4));
+    assert_eq!(format!("{:?}", key), "\"a/b\"");
+
+    assert_eq!(trie.remove("a/"), None);
+    assert_eq!(trie.remove("a"), Some(2));
+    assert_eq!(trie.remove("a"), None);
+    assert_eq!(trie.len(), 2);
+    assert!(!trie.contains_key("a"));
+    assert_eq!(trie.get("a/b"), Some(&4));
+    assert_eq!(keys(trie.iter_prefix("a")), [&b"a/b"[..], b"a/\xff"]);
+
+    // removing every key leaves nothing behind
+    assert_eq!(trie.remove("a/b"), Some(4));
+    assert_eq!(trie.remove(&b"a/\xff"[..]), Some(3));
+    assert!(trie.is_empty());
+    assert_eq!(trie.iter().count(), 0);
+    assert_eq!(trie.longest_prefix("a/b/c"), None);
+}
+
+#[test]
+fn longest_prefix() {
+    let mut trie = MaybeUtf8Trie::new();
+    trie.insert("", 0);
+    trie.insert("usr", 1);
+    trie.insert("usr/lib", 2);
+    trie.insert("usr/libexec", 3);
+    trie.insert("usr/local", 4);
+
+    assert_eq!(found_in(&trie, b"usr/lib/x"), Some(2));
+    assert_eq!(found_in(&trie, b"usr/lib"), Some(2));
+    assert_eq!(found_in(&trie, b"usr/li"), Some(1));
+    assert_eq!(found_in(&trie, b"usr/locale"), Some(4));
+    assert_eq!(found_in(&trie, b"usr/libe"), Some(2));
+    assert_eq!(found_in(&trie, b"usr/m"), Some(1));
+    assert_eq!(found_in(&trie, b"var"), Some(0));
+    assert_eq!(found_in(&trie, b""), Some(0));
+
+    trie.remove("");
+    assert_eq!(found_in(&trie, b"var"), None);
+    assert_eq!(found_in(&trie, b"us"), None);
+
+    // prefixes are matched by bytes
+    let mut trie = MaybeUtf8Trie::new();
+    trie.insert(&b"\xea\xb0"[..], 5);
+    assert_eq!(found_in(&trie, "가".as_bytes()), Some(5));
+    assert_eq!(found_in(&trie, b"\xea\xb1"), None);
+}
+
+fn found_in(trie: &MaybeUtf8Trie<i32>, key: &[u8]) -> Option<i32> {
+    trie.longest_prefix(MaybeUtf8Slice::from_bytes(key)).map(|(_, &v)| v)
+}
+
+#[test]
+fn iter_prefix_order() {
+    let mut trie = MaybeUtf8Trie::new();
+    for (i, key) in [&b"b"[..], b"a\xff\xff", b"a\xff", b"ab", b"a", b"a\xfe\xff", b"", b"c"]
+            .iter().enumerate() {
+        trie.insert(MaybeUtf8Slice::from_bytes(key), i);
+    }
+
+    assert_eq!(keys(trie.iter()),
+               [&b""[..], b"a", b"ab", b"a\xfe\xff", b"a\xff", b"a\xff\xff", b"b", b"c"]);
+    assert_eq!(keys(trie.iter_prefix("a")),
+               [&b"a"[..], b"ab", b"a\xfe\xff", b"a\xff", b"a\xff\xff"]);
+    assert_eq!(keys(trie.iter_prefix(&b"a\xfe"[..])), [&b"a\xfe\xff"[..]]);
+    assert_eq!(keys(trie.iter_prefix(&b"a\xff"[..])), [&b"a\xff"[..], b"a\xff\xff"]);
+    assert_eq!(keys(trie.iter_prefix(&b"\xff"[..])), Vec::<Vec<u8>>::new());
+    assert_eq!(keys(trie.iter_prefix("")).len(), 8);
+    assert_eq!(keys(trie.iter_prefix("a").rev()),
+               [&b"a\xff\xff"[..], b"a\xff", b"a\xfe\xff", b"ab", b"a"]);
+}
+
+#[test]
+fn long_key() {
+    // a long key should neither overflow the stack nor take quadratic time
+    let key = vec![b'x'; 1_000_000];
+    let mut trie = MaybeUtf8Trie::new();
+    trie.insert(MaybeUtf8Slice::from_bytes(&key), 1);
+    trie.insert(MaybeUtf8Slice::from_bytes(&key[..10]), 2);
+    let mut longer = key.clone();
+    longer.push(b'y');
+    assert_eq!(trie.longest_prefix(MaybeUtf8Slice::from_bytes(&longer)).map(|(_, &v)| v), Some(1));
+    assert_eq!(trie.iter_prefix("xx").count(), 2);
+    assert_eq!(trie.remove(MaybeUtf8Slice::from_bytes(&key)), Some(1));
+    drop(trie);
+}
@@ -0,0 +1,16 @@
+use maybe_utf8::MaybeUtf16Buf;
+
+#[test]
+fn from_le_bytes() {
+    let (buf, rest) = MaybeUtf16Buf::from_le_bytes(b"h\0i\0");
+    assert_eq!(buf.as_wide(), &[0x68, 0x69]);
+    assert_eq!(rest, None);
+
+    let (buf, rest) = MaybeUtf16Buf::from_le_bytes(b"h\0i\0\x3d");
+    assert_eq!(buf.as_wide(), &[0x68, 0x69]);
+    assert_eq!(rest, Some(0x3d));
+
+    let (buf, rest) = MaybeUtf16Buf::from_le_bytes(b"");
+    assert!(buf.as_wide().is_empty());
+    assert_eq!(rest, None);
+}
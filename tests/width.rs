@@ -0,0 +1,83 @@
+#![cfg(feature = "unicode-width")]
+
+use maybe_utf8::MaybeUtf8Buf;
+
+fn samples() -> Vec<MaybeUtf8Buf> {
+    vec![
+        MaybeUtf8Buf::from_str("caf\u{e9} au lait".to_owned()),
+        MaybeUtf8Buf::from_str("\u{d55c}\u{ad6d}\u{c5b4} \u{d30c}\u{c77c}".to_owned()),
+        // control characters
+        MaybeUtf8Buf::from_str("a\u{1}\u{2}b\nc\td".to_owned()),
+        // emoji ZWJ sequences and variation selectors
+        MaybeUtf8Buf::from_str("a\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}b".to_owned()),
+        MaybeUtf8Buf::from_str("\u{2764}\u{fe0f}\u{2764}\u{fe0f}\u{2764}\u{fe0f}".to_owned()),
+        MaybeUtf8Buf::from_bytes(b"caf\xe9\xe2\x82 \xff\xfe name".to_vec()),
+    ]
+}
+
+#[test]
+fn truncated_widths_fit() {
+    for s in samples() {
+        for n in 0..=s.display_width() + 1 {
+            assert!(s.truncate_to_width(n).display_width() <= n, "{:?} {}", s, n);
+            assert!(s.truncate_start_to_width(n).display_width() <= n, "{:?} {}", s, n);
+        }
+        let width = s.display_width();
+        assert_eq!(s.truncate_to_width(width).as_bytes(), s.as_bytes());
+        assert_eq!(s.truncate_start_to_width(width).as_bytes(), s.as_bytes());
+    }
+}
+
+#[test]
+fn ellipsized_widths_fit() {
+    for s in samples() {
+        for n in 0..=s.display_width() + 1 {
+            assert!(s.ellipsize(n).to_slice().display_width() <= n, "{:?} {}", s, n);
+            assert!(s.ellipsize_middle(n).to_slice().display_width() <= n, "{:?} {}", s, n);
+        }
+        assert_eq!(s.ellipsize(s.display_width()).as_bytes(), s.as_bytes());
+    }
+}
+
+#[test]
+fn truncate_emoji_sequences() {
+    let s = MaybeUtf8Buf::from_str("a\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}b".to_owned());
+    assert_eq!(s.display_width(), 4);
+    assert_eq!(s.truncate_to_width(2).as_str(), Some("a"));
+    assert_eq!(s.truncate_to_width(3).as_str(), Some("a\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}"));
+    assert_eq!(s.truncate_start_to_width(1).as_str(), Some("b"));
+    assert_eq!(s.truncate_start_to_width(3).as_str(), Some("\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}b"));
+    assert_eq!(s.ellipsize(3).as_str(), Some("a\u{2026}"));
+    assert_eq!(s.ellipsize_middle(3).as_str(), Some("a\u{2026}b"));
+
+    // the emoji presentation selector widens the preceding character
+    let s = MaybeUtf8Buf::from_str("\u{2764}\u{fe0f}\u{2764}\u{fe0f}".to_owned());
+    assert_eq!(s.truncate_to_width(2).as_str(), Some("\u{2764}\u{fe0f}"));
+    assert_eq!(s.truncate_to_width(3).as_str(), Some("\u{2764}\u{fe0f}\u{2764}"));
+
+    let s = MaybeUtf8Buf::from_bytes(b"\xf0\x9f\x98\x80\xff\xf0\x9f\x98\x80".to_vec());
+    assert_eq!(s.display_width(), 5);
+    assert_eq!(s.truncate_to_width(3).as_bytes(), b"\xf0\x9f\x98\x80\xff");
+    assert_eq!(s.truncate_to_width(2).as_bytes(), b"\xf0\x9f\x98\x80");
+    assert_eq!(s.truncate_start_to_width(3).as_bytes(), b"\xff\xf0\x9f\x98\x80");
+    assert_eq!(s.truncate_start_to_width(2).as_bytes(), b"\xf0\x9f\x98\x80");
+}
+
+#[test]
+fn truncate_long_values() {
+    // each value should be measured in a near linear time
+    let ascii = MaybeUtf8Buf::from_str("x".repeat(200_000));
+    assert_eq!(ascii.ellipsize_middle(199_990).to_slice().display_width(), 199_990);
+    assert_eq!(ascii.ellipsize(100_000).len(), 99_999 + "\u{2026}".len());
+
+    let emoji = MaybeUtf8Buf::from_str("\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}".repeat(20_000));
+    assert_eq!(emoji.display_width(), 40_000);
+    assert_eq!(emoji.truncate_to_width(30_001).display_width(), 30_000);
+    assert_eq!(emoji.truncate_start_to_width(30_001).display_width(), 30_000);
+
+    let mut bytes = b"\xff".repeat(100_000);
+    bytes.extend_from_slice("\u{d55c}".repeat(100_000).as_bytes());
+    let bytes = MaybeUtf8Buf::from_bytes(bytes);
+    assert_eq!(bytes.truncate_to_width(100_001).len(), 100_000);
+    assert_eq!(bytes.truncate_start_to_width(100_001).len(), 150_000);
+}
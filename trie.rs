@@ -0,0 +1,174 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! Prefix-searchable map keyed by `MaybeUtf8` values.
+
+use core::{fmt, mem};
+use core::ops::Bound;
+use alloc::collections::{btree_map, BTreeMap};
+use alloc::vec::Vec;
+
+use crate::{IntoMaybeUtf8, MaybeUtf8Buf, MaybeUtf8Slice};
+
+/// A map keyed by `MaybeUtf8` values, which can be also queried by key prefixes.
+///
+/// Keys are compared by the underlying bytes as like `MaybeUtf8Buf`,
+/// and entries are iterated in the byte order of keys.
+/// Each key is kept as first inserted, so it remembers whether
+/// it has been explicitly encoded in UTF-8.
+#[derive(Clone)]
+pub struct MaybeUtf8Trie<V> {
+    // the cached encoding tag of keys does not affect their order
+    map: BTreeMap<MaybeUtf8Buf, V>,
+}
+
+/// An iterator over entries of `MaybeUtf8Trie` in the byte order of keys.
+/// This is returned by `MaybeUtf8Trie::iter` and `MaybeUtf8Trie::iter_prefix`.
+#[derive(Clone)]
+pub struct TrieIter<'t, V> {
+    inner: btree_map::Range<'t, MaybeUtf8Buf, V>,
+}
+
+// returns the smallest byte string greater than all strings starting with `prefix`, if any.
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let len = prefix.iter().rposition(|&b| b != 0xff)?;
+    let mut end = prefix[..len + 1].to_vec();
+    end[len] += 1;
+    Some(end)
+}
+
+impl<V> MaybeUtf8Trie<V> {
+    /// Creates a new empty `MaybeUtf8Trie`.
+    pub fn new() -> MaybeUtf8Trie<V> {
+        MaybeUtf8Trie { map: BTreeMap::new() }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if there is no entry.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Inserts a value for given key, returning the previous value if any.
+    /// The key is not updated if it has been already inserted.
+    pub fn insert<'a, K: IntoMaybeUtf8<MaybeUtf8Slice<'a>>>(&mut self, key: K, value: V)
+            -> Option<V> {
+        let key = key.into_maybe_utf8();
+        match self.map.get_mut(key.as_bytes()) {
+            Some(old) => Some(mem::replace(old, value)),
+            None => {
+                self.map.insert(key.to_owned(), value);
+                None
+            }
+        }
+    }
+
+    /// Returns a reference to the value for given key, if any.
+    pub fn get<'a, K: IntoMaybeUtf8<MaybeUtf8Slice<'a>>>(&self, key: K) -> Option<&V> {
+        self.map.get(key.into_maybe_utf8().as_bytes())
+    }
+
+    /// Returns references to the stored key and the value for given key, if any.
+    pub fn get_key_value<'a, K>(&self, key: K) -> Option<(&MaybeUtf8Buf, &V)>
+            where K: IntoMaybeUtf8<MaybeUtf8Slice<'a>> {
+        self.map.get_key_value(key.into_maybe_utf8().as_bytes())
+    }
+
+    /// Returns a mutable reference to the value for given key, if any.
+    pub fn get_mut<'a, K: IntoMaybeUtf8<MaybeUtf8Slice<'a>>>(&mut self, key: K) -> Option<&mut V> {
+        self.map.get_mut(key.into_maybe_utf8().as_bytes())
+    }
+
+    /// Returns `true` if there is an entry for given key.
+    pub fn contains_key<'a, K: IntoMaybeUtf8<MaybeUtf8Slice<'a>>>(&self, key: K) -> bool {
+        self.map.contains_key(key.into_maybe_utf8().as_bytes())
+    }
+
+    /// Removes the entry for given key, returning the value if any.
+    pub fn remove<'a, K: IntoMaybeUtf8<MaybeUtf8Slice<'a>>>(&mut self, key: K) -> Option<V> {
+        self.map.remove(key.into_maybe_utf8().as_bytes())
+    }
+
+    /// Returns the entry with the longest key which is a prefix of given key, if any.
+    /// Prefixes are matched by bytes, so they may end in the middle of a character.
+    pub fn longest_prefix<'a, K>(&self, key: K) -> Option<(&MaybeUtf8Buf, &V)>
+            where K: IntoMaybeUtf8<MaybeUtf8Slice<'a>> {
+        let key = key.into_maybe_utf8();
+        let mut key = key.as_bytes();
+        loop {
+            // the longest prefix, if any, is also a prefix of the greatest key up to `key`.
+            // otherwise the common prefix of both is strictly shorter, so this terminates.
+            let range = (Bound::Unbounded, Bound::Included(key));
+            let (found, value) = self.map.range::<[u8], _>(range).next_back()?;
+            let found_bytes = found.as_bytes();
+            if key.starts_with(found_bytes) {
+                return Some((found, value));
+            }
+            let common = key.iter().zip(found_bytes).take_while(|&(a, b)| a == b).count();
+            key = &key[..common];
+        }
+    }
+
+    /// Returns an iterator over all entries in the byte order of keys.
+    pub fn iter(&self) -> TrieIter<'_, V> {
+        TrieIter { inner: self.map.range::<[u8], _>(..) }
+    }
+
+    /// Returns an iterator over entries whose keys start with given prefix,
+    /// in the byte order of keys.
+    pub fn iter_prefix<'a, K: IntoMaybeUtf8<MaybeUtf8Slice<'a>>>(&self, prefix: K)
+            -> TrieIter<'_, V> {
+        let prefix = prefix.into_maybe_utf8();
+        let prefix = prefix.as_bytes();
+        let end = prefix_end(prefix);
+        let end = match end {
+            Some(ref end) => Bound::Excluded(&end[..]),
+            None => Bound::Unbounded,
+        };
+        TrieIter { inner: self.map.range::<[u8], _>((Bound::Included(prefix), end)) }
+    }
+}
+
+impl<'t, V> Iterator for TrieIter<'t, V> {
+    type Item = (&'t MaybeUtf8Buf, &'t V);
+
+    fn next(&mut self) -> Option<(&'t MaybeUtf8Buf, &'t V)> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'t, V> DoubleEndedIterator for TrieIter<'t, V> {
+    fn next_back(&mut self) -> Option<(&'t MaybeUtf8Buf, &'t V)> {
+        self.inner.next_back()
+    }
+}
+
+impl<'t, V> IntoIterator for &'t MaybeUtf8Trie<V> {
+    type Item = (&'t MaybeUtf8Buf, &'t V);
+    type IntoIter = TrieIter<'t, V>;
+    fn into_iter(self) -> TrieIter<'t, V> { self.iter() }
+}
+
+impl<V> Default for MaybeUtf8Trie<V> {
+    fn default() -> MaybeUtf8Trie<V> { MaybeUtf8Trie::new() }
+}
+
+impl<V: fmt::Debug> fmt::Debug for MaybeUtf8Trie<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,251 @@
+// maybe_utf8: Byte container optionally encoded as UTF-8.
+// Copyright (c) 2015, Kang Seonghoon.
+// See README.md and LICENSE.txt for details.
+
+//! UTF-16 counterparts of `MaybeUtf8Buf` and `MaybeUtf8Slice`.
+
+use core::{char, fmt};
+use core::cmp::Ordering;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::MaybeUtf8Buf;
+
+/// Container of 16-bit code units optionally encoded as UTF-16. It might be either...
+///
+/// - Definitely UTF-16-encoded string, or
+/// - Code units. They may be encoded in UTF-16 (possibly with unpaired surrogates
+///   as like Windows file names) or other encodings, or they may be simply garbage.
+///
+/// This is to UTF-16 what `MaybeUtf8Buf` is to UTF-8.
+#[derive(Clone)]
+pub struct MaybeUtf16Buf { units: Vec<u16>, utf16: bool }
+
+/// Slice of 16-bit code units optionally encoded as UTF-16.
+/// A borrowed version of `MaybeUtf16Buf`.
+#[derive(Clone, Copy)]
+pub struct MaybeUtf16Slice<'a> { units: &'a [u16], utf16: bool }
+
+impl MaybeUtf16Buf {
+    /// Creates a new empty `MaybeUtf16Buf` value (which is, naturally, encoded in UTF-16).
+    pub fn new() -> MaybeUtf16Buf {
+        MaybeUtf16Buf { units: Vec::new(), utf16: true }
+    }
+
+    /// Creates a `MaybeUtf16Buf` value by encoding a string slice in UTF-16.
+    pub fn from_str(s: &str) -> MaybeUtf16Buf {
+        MaybeUtf16Buf { units: s.encode_utf16().collect(), utf16: true }
+    }
+
+    /// Creates a `MaybeUtf16Buf` value from an owned `Vec` of `u16` code units.
+    pub fn from_wide(v: Vec<u16>) -> MaybeUtf16Buf {
+        MaybeUtf16Buf { units: v, utf16: false }
+    }
+
+    /// Creates a `MaybeUtf16Buf` value from bytes with little-endian code units.
+    /// A trailing odd byte, if any, is not a code unit and is returned separately
+    /// (e.g. to be prepended to the next chunk of bytes).
+    pub fn from_le_bytes(v: &[u8]) -> (MaybeUtf16Buf, Option<u8>) {
+        let chunks = v.chunks_exact(2);
+        let rest = chunks.remainder().first().cloned();
+        let units = chunks.map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        (MaybeUtf16Buf { units, utf16: false }, rest)
+    }
+
+    /// Returns a slice of underlying code units. It might or might not be encoded in UTF-16.
+    pub fn as_wide(&self) -> &[u16] {
+        &self.units
+    }
+
+    /// Returns a `MaybeUtf16Slice` borrowed from this `MaybeUtf16Buf`.
+    pub fn to_slice(&self) -> MaybeUtf16Slice<'_> {
+        MaybeUtf16Slice { units: &self.units, utf16: self.utf16 }
+    }
+
+    /// Returns a `String` decoded from the code units if possible.
+    /// It returns `None` if there is an unpaired surrogate.
+    pub fn to_str(&self) -> Option<String> {
+        self.to_slice().to_str()
+    }
+
+    /// Returns a `String` decoded from the code units.
+    /// Any unpaired surrogates are replaced by U+FFFD, as like `String::from_utf16_lossy`.
+    pub fn to_str_lossy(&self) -> String {
+        self.to_slice().to_str_lossy()
+    }
+
+    /// Converts a `MaybeUtf16Buf` into a `Vec` of `u16` code units.
+    pub fn into_wide(self) -> Vec<u16> {
+        self.units
+    }
+
+    /// Returns a length of the `MaybeUtf16Buf` value in code units.
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    /// Returns `true` if the `MaybeUtf16Buf` value has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a> MaybeUtf16Slice<'a> {
+    /// Creates a new empty `MaybeUtf16Slice` value (which is, naturally, encoded in UTF-16).
+    pub fn new() -> MaybeUtf16Slice<'static> {
+        MaybeUtf16Slice { units: &[], utf16: true }
+    }
+
+    /// Creates a `MaybeUtf16Slice` reference from a `u16` slice.
+    pub fn from_wide(v: &'a [u16]) -> MaybeUtf16Slice<'a> {
+        MaybeUtf16Slice { units: v, utf16: false }
+    }
+
+    /// Returns a slice of underlying code units. It might or might not be encoded in UTF-16.
+    pub fn as_wide(&self) -> &'a [u16] {
+        self.units
+    }
+
+    /// Returns a `String` decoded from the code units if possible.
+    /// It returns `None` if there is an unpaired surrogate.
+    pub fn to_str(&self) -> Option<String> {
+        char::decode_utf16(self.units.iter().cloned()).collect::<Result<String, _>>().ok()
+    }
+
+    /// Returns a `String` decoded from the code units.
+    /// Any unpaired surrogates are replaced by U+FFFD, as like `String::from_utf16_lossy`.
+    pub fn to_str_lossy(&self) -> String {
+        String::from_utf16_lossy(self.units)
+    }
+
+    /// Returns a new `MaybeUtf16Buf` from the current `MaybeUtf16Slice`.
+    pub fn to_owned(&self) -> MaybeUtf16Buf {
+        MaybeUtf16Buf { units: self.units.to_vec(), utf16: self.utf16 }
+    }
+
+    /// Returns a length of the `MaybeUtf16Slice` value in code units.
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    /// Returns `true` if the `MaybeUtf16Slice` value has a length of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a> From<MaybeUtf16Slice<'a>> for MaybeUtf8Buf {
+    /// Converts a `MaybeUtf16Slice` into a `MaybeUtf8Buf` without any loss.
+    /// The result is explicitly encoded in UTF-8 only when the original is explicitly
+    /// encoded in UTF-16. Unpaired surrogates are encoded as if they were ordinary
+    /// code points, as like WTF-8, so the result is not valid in UTF-8 in that case.
+    fn from(s: MaybeUtf16Slice<'a>) -> MaybeUtf8Buf {
+        if s.utf16 {
+            return MaybeUtf8Buf::from_str(String::from_utf16_lossy(s.units));
+        }
+        let mut v = Vec::with_capacity(s.units.len());
+        for c in char::decode_utf16(s.units.iter().cloned()) {
+            match c {
+                Ok(c) => v.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                Err(e) => {
+                    let c = e.unpaired_surrogate();
+                    v.extend_from_slice(&[0xe0 | (c >> 12) as u8,
+                                          0x80 | ((c >> 6) & 0x3f) as u8,
+                                          0x80 | (c & 0x3f) as u8]);
+                }
+            }
+        }
+        MaybeUtf8Buf::from_bytes(v)
+    }
+}
+
+impl From<MaybeUtf16Buf> for MaybeUtf8Buf {
+    fn from(buf: MaybeUtf16Buf) -> MaybeUtf8Buf { MaybeUtf8Buf::from(buf.to_slice()) }
+}
+
+impl<'a> From<&'a str> for MaybeUtf16Buf {
+    fn from(s: &'a str) -> MaybeUtf16Buf { MaybeUtf16Buf::from_str(s) }
+}
+
+impl PartialEq for MaybeUtf16Buf {
+    fn eq(&self, other: &MaybeUtf16Buf) -> bool { self.units == other.units }
+}
+
+impl<'a, 'b> PartialEq<MaybeUtf16Slice<'b>> for MaybeUtf16Slice<'a> {
+    fn eq(&self, other: &MaybeUtf16Slice<'b>) -> bool { self.units == other.units }
+}
+
+impl<'a> PartialEq<MaybeUtf16Slice<'a>> for MaybeUtf16Buf {
+    fn eq(&self, other: &MaybeUtf16Slice<'a>) -> bool { self.units == other.units }
+}
+
+impl<'a> PartialEq<MaybeUtf16Buf> for MaybeUtf16Slice<'a> {
+    fn eq(&self, other: &MaybeUtf16Buf) -> bool { self.units == other.units }
+}
+
+impl Eq for MaybeUtf16Buf {
+}
+
+impl<'a> Eq for MaybeUtf16Slice<'a> {
+}
+
+impl PartialOrd for MaybeUtf16Buf {
+    fn partial_cmp(&self, other: &MaybeUtf16Buf) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl<'a> PartialOrd for MaybeUtf16Slice<'a> {
+    fn partial_cmp(&self, other: &MaybeUtf16Slice<'a>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MaybeUtf16Buf {
+    fn cmp(&self, other: &MaybeUtf16Buf) -> Ordering { self.units.cmp(&other.units) }
+}
+
+impl<'a> Ord for MaybeUtf16Slice<'a> {
+    fn cmp(&self, other: &MaybeUtf16Slice<'a>) -> Ordering { self.units.cmp(other.units) }
+}
+
+impl Default for MaybeUtf16Buf {
+    fn default() -> MaybeUtf16Buf { MaybeUtf16Buf::new() }
+}
+
+impl<'a> Default for MaybeUtf16Slice<'a> {
+    fn default() -> MaybeUtf16Slice<'a> { MaybeUtf16Slice::new() }
+}
+
+impl fmt::Debug for MaybeUtf16Buf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_slice(), f)
+    }
+}
+
+impl fmt::Display for MaybeUtf16Buf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_slice(), f)
+    }
+}
+
+impl<'a> fmt::Debug for MaybeUtf16Slice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.utf16 {
+            return fmt::Debug::fmt(&self.to_str_lossy(), f);
+        }
+        // code units are shown as a string prefixed with `u`, with unpaired surrogates escaped
+        write!(f, "u\"")?;
+        for c in char::decode_utf16(self.units.iter().cloned()) {
+            match c {
+                Ok(c) => write!(f, "{}", c.escape_debug())?,
+                Err(e) => write!(f, "\\u{{{:x}}}", e.unpaired_surrogate())?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+impl<'a> fmt::Display for MaybeUtf16Slice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_str_lossy(), f)
+    }
+}